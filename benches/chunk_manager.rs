@@ -0,0 +1,47 @@
+//! Inserting 100k chunks into a [`ChunkManager`], with and without reserving
+//! room for them first.
+
+use bevy::prelude::*;
+use chunky_bevy::ChunkManager;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+
+const CHUNKS: i32 = 100_000;
+
+/// 100k chunk positions in a 100x10x100 block, each with its own entity
+fn chunks() -> Vec<(IVec3, Entity)> {
+    (0..CHUNKS)
+        .map(|i| {
+            let pos = IVec3::new(i % 100, i / 100 % 10, i / 1000);
+            (pos, Entity::from_raw_u32(i as u32).unwrap())
+        })
+        .collect()
+}
+
+fn insert(chunk_manager: &mut ChunkManager, chunks: &[(IVec3, Entity)]) {
+    for &(pos, entity) in chunks {
+        chunk_manager.insert(pos, entity);
+    }
+}
+
+fn spawn_100k(c: &mut Criterion) {
+    let chunks = chunks();
+    let mut group = c.benchmark_group("insert 100k chunks");
+    group.bench_function("growing", |b| {
+        b.iter_batched_ref(
+            || ChunkManager::new(Vec3::ONE),
+            |chunk_manager| insert(chunk_manager, &chunks),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("reserved", |b| {
+        b.iter_batched_ref(
+            || ChunkManager::with_capacity(Vec3::ONE, chunks.len()),
+            |chunk_manager| insert(chunk_manager, &chunks),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, spawn_100k);
+criterion_main!(benches);
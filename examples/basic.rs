@@ -1,10 +1,18 @@
 use bevy::{input::mouse::MouseMotion, prelude::*};
-use chunky_bevy::prelude::{ChunkBoundryVisualizer, ChunkLoader, ChunkyPlugin};
+use chunky_bevy::prelude::{
+    ChunkBoundryVisualizer, ChunkHighlightTarget, ChunkLoader, ChunkVisualizerConfig, ChunkyPlugin,
+};
 
 fn main() {
     let mut app = App::new();
     app.add_plugins(DefaultPlugins);
     app.add_plugins(ChunkyPlugin::default());
+    // Run with `--features chunk_minimap` for a minimap of the loaded chunks
+    #[cfg(feature = "chunk_minimap")]
+    app.add_plugins(chunky_bevy::prelude::ChunkMinimapPlugin);
+    // Run with `--features chunk_debug_ui` for chunk stats next to the visualizer
+    #[cfg(feature = "chunk_debug_ui")]
+    app.add_plugins(chunky_bevy::prelude::ChunkDebugUiPlugin);
     app.add_systems(Startup, setup)
         .add_systems(Update, (camera_movement, camera_look, cube_movement));
     app.run();
@@ -17,6 +25,7 @@ fn setup(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut visualizer: ResMut<NextState<ChunkBoundryVisualizer>>,
+    mut visualizer_config: ResMut<ChunkVisualizerConfig>,
 ) {
     commands.spawn((
         Camera3d::default(),
@@ -29,7 +38,9 @@ fn setup(
         Mesh3d(meshes.add(Cuboid::new(1.0, 1.0, 1.0))),
         MeshMaterial3d(materials.add(Color::srgb_u8(124, 144, 255))),
         Transform::from_xyz(0.0, 0.5, 0.0),
-        ChunkLoader::default(),
+        // Load a 3x1x3 area, highlighting the chunk the cube is in
+        ChunkLoader(IVec3::new(1, 0, 1)),
+        ChunkHighlightTarget::Position,
     ));
     // light
     commands.spawn((
@@ -39,7 +50,10 @@ fn setup(
         },
         Transform::from_xyz(4.0, 8.0, 4.0),
     ));
+    // Toggled with Alt+F3
     visualizer.set(ChunkBoundryVisualizer::On);
+    visualizer_config.toggle_modifier = Some(KeyCode::AltLeft);
+    visualizer_config.highlight_neighbors = true;
 }
 
 fn camera_movement(
@@ -0,0 +1,85 @@
+//! Colors chunks by how many wandering NPCs they contain, from blue to red.
+//!
+//! Press Space to switch between the heatmap and the plain chunk boundaries.
+//! Run with `cargo run --example heatmap`.
+
+use bevy::prelude::*;
+use chunky_bevy::{helpers::spawn_chunks_rect, prelude::*};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(ChunkyPlugin::default())
+        .insert_resource(ChunkVisualizerConfig {
+            draw_mode: DrawMode::Heatmap,
+            heatmap_max: 12,
+            ..default()
+        })
+        .add_systems(Startup, setup)
+        .add_systems(Update, (wander, toggle_heatmap))
+        .run();
+}
+
+/// Walks in a circle around its own center
+#[derive(Component)]
+struct Npc {
+    center: Vec3,
+    radius: f32,
+    speed: f32,
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut visualizer: ResMut<NextState<ChunkBoundryVisualizer>>,
+) {
+    visualizer.set(ChunkBoundryVisualizer::On);
+    spawn_chunks_rect(&mut commands, IVec3::new(-4, 0, -4), IVec3::new(3, 0, 3));
+
+    let mesh = meshes.add(Sphere::new(0.5));
+    let material = materials.add(Color::srgb_u8(124, 144, 255));
+    // Most NPCs crowd around a hotspot near the origin
+    for i in 0..120 {
+        let spread = if i % 4 == 0 { 35.0 } else { 8.0 };
+        let angle = i as f32 * 2.4;
+        let center = Vec3::new(angle.cos(), 0.0, angle.sin()) * spread * (i % 7) as f32 / 6.0;
+        commands.spawn((
+            Npc {
+                center: center + Vec3::new(5.0, 5.0, 5.0),
+                radius: 1.0 + (i % 5) as f32,
+                speed: 0.3 + (i % 3) as f32 * 0.2,
+            },
+            ChunkHeatSource,
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            Transform::default(),
+        ));
+    }
+
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 90.0, 60.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+    commands.spawn((
+        DirectionalLight::default(),
+        Transform::from_xyz(1.0, 2.0, 0.5).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
+
+fn wander(time: Res<Time>, mut npcs: Query<(&Npc, &mut Transform)>) {
+    for (npc, mut transform) in npcs.iter_mut() {
+        let angle = time.elapsed_secs() * npc.speed;
+        transform.translation = npc.center + Vec3::new(angle.cos(), 0.0, angle.sin()) * npc.radius;
+    }
+}
+
+/// Switches between the heatmap and the chunk boundaries with Space
+fn toggle_heatmap(keys: Res<ButtonInput<KeyCode>>, mut config: ResMut<ChunkVisualizerConfig>) {
+    if keys.just_pressed(KeyCode::Space) {
+        config.draw_mode = match config.draw_mode {
+            DrawMode::Heatmap => DrawMode::Full,
+            _ => DrawMode::Heatmap,
+        };
+    }
+}
@@ -0,0 +1,87 @@
+//! Colors chunk boundaries by their load state while a loader streams chunks.
+//!
+//! Generation is slowed down on purpose, so `Requested` (yellow) and
+//! `Generating` (orange) chunks stay visible before turning `Ready` (green).
+//! Some chunks fail to generate (red), and the chunks around the start are
+//! pinned (blue), so they stay loaded after the loader left. Run with
+//! `cargo run --example load_states --features chunk_generation`.
+
+use std::{thread, time::Duration};
+
+use bevy::prelude::*;
+use chunky_bevy::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(ChunkyPlugin::default())
+        .add_plugins(ChunkGenerationPlugin::new(SlowGenerator))
+        .insert_resource(ChunkUnloadByDistance)
+        .add_systems(Startup, setup)
+        .add_systems(Update, move_loader)
+        .run();
+}
+
+/// Takes up to a second per chunk, and fails for every seventh diagonal
+struct SlowGenerator;
+
+impl ChunkGenerator for SlowGenerator {
+    type Output = ();
+
+    fn generate(&self, pos: IVec3, _ctx: GenerationContext) -> GenerationResult<()> {
+        let delay = (pos.x * 31 + pos.z * 17).rem_euclid(10) as u64 * 100;
+        thread::sleep(Duration::from_millis(delay));
+        if (pos.x + pos.z).rem_euclid(7) == 0 {
+            return Err(GenerationError::new("cursed diagonal"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Component)]
+struct Traveler;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut visualizer: ResMut<NextState<ChunkBoundryVisualizer>>,
+) {
+    visualizer.set(ChunkBoundryVisualizer::On);
+
+    commands.spawn((
+        Traveler,
+        Mesh3d(meshes.add(Sphere::new(1.0))),
+        MeshMaterial3d(materials.add(Color::srgb_u8(124, 144, 255))),
+        Transform::from_xyz(5.0, 5.0, 5.0),
+        ChunkLoader(IVec3::new(4, 0, 4)),
+        ChunkUnloadRadius(IVec3::new(5, 0, 5)),
+    ));
+
+    // Keep the start area loaded
+    for x in -1..=1 {
+        for z in -1..=1 {
+            commands.spawn((
+                Chunk,
+                ChunkPos(ivec3(x, 0, z)),
+                ChunkLoadState::Requested,
+                ChunkPinned,
+            ));
+        }
+    }
+
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 110.0, 70.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+    commands.spawn((
+        DirectionalLight::default(),
+        Transform::from_xyz(1.0, 2.0, 0.5).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
+
+/// Circles the loader around the start, through fresh chunks
+fn move_loader(time: Res<Time>, mut traveler: Single<&mut Transform, With<Traveler>>) {
+    let angle = time.elapsed_secs() * 0.2;
+    traveler.translation = Vec3::new(angle.cos() * 40.0, 5.0, angle.sin() * 40.0);
+}
@@ -0,0 +1,218 @@
+//! Streams voxel chunks with a mesh each around a moving loader.
+//!
+//! The loader travels across an endless field of hills. Chunks around it are
+//! generated on background threads, get a cube-grid mesh built from their
+//! voxels once they are `Ready`, and are despawned with their mesh once they
+//! are out of the unload radius. Run with
+//! `cargo run --example streaming --features chunk_generation`.
+
+use bevy::{
+    asset::RenderAssetUsages,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+};
+use chunky_bevy::prelude::*;
+
+/// Voxels along each axis of a chunk
+const CELLS: usize = 10;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(ChunkyPlugin::default())
+        .add_plugins(ChunkGenerationPlugin::new(HillsGenerator))
+        .insert_resource(ChunkUnloadByDistance)
+        .add_systems(Startup, setup)
+        .add_systems(
+            Update,
+            (
+                build_chunk_meshes.in_set(ChunkySystems::PostGenerate),
+                move_loader,
+                follow_loader,
+                update_ui,
+            ),
+        )
+        .run();
+}
+
+/// Filled voxels of a chunk, indexed by [`Voxels::index`]
+struct Voxels(Vec<bool>);
+
+impl Voxels {
+    fn index(cell: IVec3) -> usize {
+        (cell.x as usize * CELLS + cell.y as usize) * CELLS + cell.z as usize
+    }
+
+    /// Whether the voxel at `cell` is filled, treating cells outside the chunk
+    /// as empty
+    fn filled(&self, cell: IVec3) -> bool {
+        let inside = cell.cmpge(IVec3::ZERO).all() && cell.cmplt(IVec3::splat(CELLS as i32)).all();
+        inside && self.0[Self::index(cell)]
+    }
+}
+
+/// Fills every voxel below a rolling height
+struct HillsGenerator;
+
+impl ChunkGenerator for HillsGenerator {
+    type Output = Voxels;
+
+    fn generate(&self, pos: IVec3, _ctx: GenerationContext) -> GenerationResult<Voxels> {
+        let origin = pos * CELLS as i32;
+        let mut voxels = vec![false; CELLS * CELLS * CELLS];
+        for x in 0..CELLS as i32 {
+            for z in 0..CELLS as i32 {
+                let world = (origin.xz() + ivec2(x, z)).as_vec2();
+                let height = 4.0 + 3.0 * (world.x * 0.15).sin() + 2.0 * (world.y * 0.1).cos();
+                for y in 0..CELLS as i32 {
+                    let cell = ivec3(x, y, z);
+                    voxels[Voxels::index(cell)] = ((origin.y + y) as f32) < height;
+                }
+            }
+        }
+        Ok(Voxels(voxels))
+    }
+}
+
+/// The voxel faces, as their normal and two edges whose cross product is the
+/// normal, so the corners wind counter-clockwise seen from outside
+const FACES: [(IVec3, IVec3, IVec3); 6] = [
+    (IVec3::X, IVec3::Y, IVec3::Z),
+    (IVec3::NEG_X, IVec3::Z, IVec3::Y),
+    (IVec3::Y, IVec3::Z, IVec3::X),
+    (IVec3::NEG_Y, IVec3::X, IVec3::Z),
+    (IVec3::Z, IVec3::X, IVec3::Y),
+    (IVec3::NEG_Z, IVec3::Y, IVec3::X),
+];
+
+/// Builds a mesh with a quad for every voxel face not covered by a neighbor
+fn voxel_mesh(voxels: &Voxels, cell_size: Vec3) -> Mesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices = Vec::new();
+    for x in 0..CELLS as i32 {
+        for y in 0..CELLS as i32 {
+            for z in 0..CELLS as i32 {
+                let cell = ivec3(x, y, z);
+                if !voxels.filled(cell) {
+                    continue;
+                }
+                for (normal, u, v) in FACES {
+                    if voxels.filled(cell + normal) {
+                        continue;
+                    }
+                    let origin = cell + normal.max(IVec3::ZERO);
+                    let start = positions.len() as u32;
+                    for corner in [origin, origin + u, origin + u + v, origin + v] {
+                        positions.push((corner.as_vec3() * cell_size).to_array());
+                        normals.push(normal.as_vec3().to_array());
+                    }
+                    indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+                }
+            }
+        }
+    }
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+#[derive(Resource)]
+struct GroundMaterial(Handle<StandardMaterial>);
+
+/// Gives every newly ready chunk the mesh of its voxels. The mesh lives on the
+/// chunk entity, so unloading the chunk removes it as well.
+fn build_chunk_meshes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material: Res<GroundMaterial>,
+    chunk_manager: Res<ChunkManager>,
+    chunks: Query<(Entity, &ChunkData<Voxels>), NewlyReadyChunks>,
+) {
+    let cell_size = chunk_manager.get_size() / CELLS as f32;
+    for (entity, ChunkData(voxels)) in chunks.iter() {
+        if !voxels.0.contains(&true) {
+            continue;
+        }
+        commands.entity(entity).insert((
+            Mesh3d(meshes.add(voxel_mesh(voxels, cell_size))),
+            MeshMaterial3d(material.0.clone()),
+        ));
+    }
+}
+
+#[derive(Component)]
+struct Traveler;
+
+#[derive(Component)]
+struct StatsText;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(GroundMaterial(materials.add(Color::srgb(0.35, 0.6, 0.3))));
+
+    commands.spawn((
+        Traveler,
+        Mesh3d(meshes.add(Sphere::new(1.5))),
+        MeshMaterial3d(materials.add(Color::srgb_u8(255, 80, 80))),
+        Transform::from_xyz(0.0, 12.0, 0.0),
+        // Load the chunks around the traveler, keep them a little longer
+        ChunkLoader(IVec3::new(5, 1, 5)),
+        ChunkUnloadRadius(IVec3::new(7, 2, 7)),
+    ));
+
+    commands.spawn(Camera3d::default());
+    commands.spawn((
+        DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(1.0, 2.0, 0.5).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+
+    commands.spawn((
+        Text::new(""),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..default()
+        },
+        StatsText,
+    ));
+}
+
+fn move_loader(time: Res<Time>, mut traveler: Single<&mut Transform, With<Traveler>>) {
+    let direction = Vec3::new(1.0, 0.0, (time.elapsed_secs() * 0.1).sin()).normalize();
+    traveler.translation += direction * 15.0 * time.delta_secs();
+}
+
+fn follow_loader(
+    traveler: Single<&Transform, (With<Traveler>, Without<Camera3d>)>,
+    mut camera: Single<&mut Transform, With<Camera3d>>,
+) {
+    let target = traveler.translation;
+    **camera = Transform::from_translation(target + Vec3::new(-60.0, 60.0, 60.0))
+        .looking_at(target, Vec3::Y);
+}
+
+fn update_ui(
+    mut text: Single<&mut Text, With<StatsText>>,
+    chunks: Query<(), With<Chunk>>,
+    ready: Query<(), ReadyChunks>,
+    meshed: Query<(), (With<Chunk>, With<Mesh3d>)>,
+) {
+    text.0 = format!(
+        "Chunks: {}\nReady: {}\nWith a mesh: {}",
+        chunks.iter().count(),
+        ready.iter().count(),
+        meshed.iter().count(),
+    );
+}
@@ -0,0 +1,61 @@
+//! Streams noise terrain around a moving loader.
+//!
+//! The loader circles the origin; chunks are generated on background threads
+//! and appear as boxes once they are `Ready`. Run with
+//! `cargo run --example terrain --features noise_example`.
+
+use bevy::prelude::*;
+use chunky_bevy::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(ChunkyPlugin::default())
+        .add_plugins(NoiseTerrainPlugin::new(42))
+        .add_systems(Startup, setup)
+        .add_systems(Update, (move_loader, count_ready_chunks))
+        .run();
+}
+
+#[derive(Component, Debug)]
+struct Wanderer;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        Wanderer,
+        Mesh3d(meshes.add(Sphere::new(2.0))),
+        MeshMaterial3d(materials.add(Color::srgb_u8(255, 80, 80))),
+        Transform::from_xyz(0.0, 5.0, 0.0),
+        // Only load the layer of chunks the loader is in
+        ChunkLoader(IVec3::new(6, 0, 6)),
+    ));
+
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 160.0, 160.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+    commands.spawn((
+        DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(1.0, 2.0, 1.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
+
+fn move_loader(time: Res<Time>, mut wanderer: Single<&mut Transform, With<Wanderer>>) {
+    let angle = time.elapsed_secs() * 0.2;
+    wanderer.translation.x = angle.cos() * 80.0;
+    wanderer.translation.z = angle.sin() * 80.0;
+}
+
+fn count_ready_chunks(newly_ready: Query<&ChunkPos, NewlyReadyChunks>) {
+    let count = newly_ready.iter().count();
+    if count > 0 {
+        info!("{} chunks became ready", count);
+    }
+}
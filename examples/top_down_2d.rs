@@ -0,0 +1,104 @@
+//! Streams flat 2D chunks around a sprite, seen through a `Camera2d`.
+//!
+//! Chunks are 64x64 on the X/Y plane and zero deep along Z, so the visualizer
+//! draws each one as a single rectangle and sprites may sit at any depth. Each
+//! loaded chunk gets a checkered tile, the chunk of the player is highlighted,
+//! and chunks are unloaded once the player walked away. Move with WASD or the
+//! arrow keys. Run with `cargo run --example top_down_2d`.
+
+use bevy::{prelude::*, sprite::Anchor};
+use chunky_bevy::prelude::*;
+
+/// Size of a chunk in pixels
+const CHUNK_SIZE: i32 = 64;
+
+/// Speed of the player in pixels per second
+const SPEED: f32 = 300.0;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(
+            ChunkyPlugin::default()
+                .with_int_chunk_size(IVec3::new(CHUNK_SIZE, CHUNK_SIZE, 0))
+                .with_up_axis(ChunkUpAxis::Z),
+        )
+        .insert_resource(ChunkUnloadByDistance)
+        .add_systems(Startup, setup)
+        .add_systems(
+            Update,
+            (
+                move_player,
+                follow_player.after(move_player),
+                add_chunk_tiles.after(ChunkySystems::Load),
+            ),
+        )
+        .run();
+}
+
+#[derive(Component)]
+struct Player;
+
+fn setup(mut commands: Commands, mut visualizer: ResMut<NextState<ChunkBoundryVisualizer>>) {
+    visualizer.set(ChunkBoundryVisualizer::On);
+
+    commands.spawn(Camera2d);
+    commands.spawn((
+        Player,
+        Sprite::from_color(Color::srgb_u8(124, 144, 255), Vec2::splat(24.0)),
+        // In front of the chunk tiles
+        Transform::from_xyz(0.0, 0.0, 1.0),
+        ChunkLoader(ChunkUpAxis::Z.flat(4)),
+        ChunkUnloadRadius(ChunkUpAxis::Z.flat(6)),
+        ChunkHighlightTarget::Position,
+    ));
+}
+
+fn move_player(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut player: Single<&mut Transform, With<Player>>,
+) {
+    let mut direction = Vec2::ZERO;
+    if keys.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]) {
+        direction.y += 1.0;
+    }
+    if keys.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]) {
+        direction.y -= 1.0;
+    }
+    if keys.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]) {
+        direction.x -= 1.0;
+    }
+    if keys.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]) {
+        direction.x += 1.0;
+    }
+    let step = direction.normalize_or_zero() * SPEED * time.delta_secs();
+    player.translation += step.extend(0.0);
+}
+
+fn follow_player(
+    player: Single<&Transform, With<Player>>,
+    mut camera: Single<&mut Transform, (With<Camera2d>, Without<Player>)>,
+) {
+    camera.translation = player.translation.truncate().extend(camera.translation.z);
+}
+
+/// Gives every new chunk a checkered tile, behind the player
+fn add_chunk_tiles(
+    mut commands: Commands,
+    chunk_manager: Res<ChunkManager>,
+    chunks: Query<(Entity, &ChunkPos), Added<Chunk>>,
+) {
+    // Chunks are placed at their minimum corner
+    let size = chunk_manager.get_size().truncate();
+    for (entity, ChunkPos(chunk_pos)) in chunks.iter() {
+        let color = if (chunk_pos.x + chunk_pos.y).rem_euclid(2) == 0 {
+            Color::srgb(0.16, 0.2, 0.16)
+        } else {
+            Color::srgb(0.2, 0.25, 0.2)
+        };
+        commands
+            .entity(entity)
+            .insert((Sprite::from_color(color, size), Anchor::BOTTOM_LEFT));
+    }
+}
@@ -0,0 +1,92 @@
+//! A system param combining chunk lookups with spawning.
+
+use std::collections::HashMap;
+
+use bevy::{
+    ecs::{
+        component::Tick,
+        system::{SystemChangeTick, SystemParam},
+    },
+    prelude::*,
+};
+
+use crate::{Chunk, ChunkLoadState, ChunkManager, ChunkPos};
+
+/// Reads the [`ChunkManager`] and spawns chunks from the same system param.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Player;
+///
+/// fn keep_player_grounded(mut chunks: ChunkAccess, player: Single<&Transform, With<Player>>) {
+///     let below = player.translation - Vec3::Y;
+///     if !chunks.is_loaded_at_world(below) {
+///         let chunk_pos = chunks.manager().get_chunk_pos(&below);
+///         let chunk = chunks.ensure_chunk(chunk_pos);
+///         chunks.commands().entity(chunk).insert(Name::new("Ground"));
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct ChunkAccess<'w, 's> {
+    chunk_manager: Res<'w, ChunkManager>,
+    commands: Commands<'w, 's>,
+    tick: SystemChangeTick,
+    spawned: Local<'s, SpawnedThisRun>,
+}
+
+/// Chunks spawned by ensure_chunk during the current system run, as they are
+/// not registered before the commands are applied
+#[derive(Default)]
+struct SpawnedThisRun {
+    tick: Option<Tick>,
+    chunks: HashMap<IVec3, Entity>,
+}
+
+impl<'w, 's> ChunkAccess<'w, 's> {
+    /// The chunk manager
+    pub fn manager(&self) -> &ChunkManager {
+        &self.chunk_manager
+    }
+
+    /// The commands queue of the system
+    pub fn commands(&mut self) -> &mut Commands<'w, 's> {
+        &mut self.commands
+    }
+
+    /// Checks if the chunk containing a world position is loaded
+    pub fn is_loaded_at_world(&self, pos: Vec3) -> bool {
+        self.chunk_manager.get_chunk_form_pos(&pos).is_some()
+    }
+
+    /// Returns the chunk at `chunk_pos`, spawning it if it is not loaded.
+    ///
+    /// Calling this repeatedly for the same position within one system run
+    /// spawns only one chunk.
+    pub fn ensure_chunk(&mut self, chunk_pos: IVec3) -> Entity {
+        if let Some(entity) = self.chunk_manager.get_chunk(&chunk_pos) {
+            return entity;
+        }
+
+        let this_run = self.tick.this_run();
+        if self.spawned.tick != Some(this_run) {
+            self.spawned.tick = Some(this_run);
+            self.spawned.chunks.clear();
+        }
+        if let Some(&entity) = self.spawned.chunks.get(&chunk_pos) {
+            return entity;
+        }
+
+        let entity = self
+            .commands
+            .spawn((Chunk, ChunkPos(chunk_pos), ChunkLoadState::Requested))
+            .id();
+        self.spawned.chunks.insert(chunk_pos, entity);
+        entity
+    }
+}
@@ -0,0 +1,192 @@
+//! An on-screen panel with chunk streaming stats.
+//!
+//! [`ChunkDebugUiPlugin`] shows how many chunks are loaded, pending and
+//! generating, how many were spawned this frame and the chunk of the primary
+//! loader, plus the chunk selected with `chunk_picking`, the visualizer's y
+//! slice and a legend of its heatmap while they are in use. It is shown
+//! while the chunk visualizer is on, so the visualizer's
+//! [`toggle_key`](crate::prelude::ChunkVisualizerConfig::toggle_key) toggles
+//! both.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(ChunkyPlugin::default())
+//!     .add_plugins(ChunkDebugUiPlugin);
+//! ```
+
+use std::fmt::Write;
+
+use bevy::prelude::*;
+
+use crate::{
+    ChunkStats, ChunkStreamingProgress, ChunkySystems,
+    chunk_loader::{ChunkLoaderDisabled, ChunkLoaderStats},
+    chunk_progress::update_chunk_stats,
+    chunk_visualizer::{
+        ChunkBoundryVisualizer, ChunkVisualizerConfig, ChunkVisualizerEnabled, DrawMode,
+        visualizer_enabled,
+    },
+};
+
+/// Shows a panel with chunk streaming stats, see the [module docs](self).
+pub struct ChunkDebugUiPlugin;
+
+impl Plugin for ChunkDebugUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_chunk_debug_ui).add_systems(
+            Update,
+            (
+                update_chunk_debug_ui.run_if(visualizer_enabled),
+                show_chunk_debug_ui,
+            )
+                .chain()
+                .after(ChunkySystems::PostGenerate)
+                .after(update_chunk_stats),
+        );
+        #[cfg(feature = "reflect")]
+        app.register_type::<ChunkDebugUiPanel>();
+    }
+}
+
+/// Marks the text node of the chunk debug panel, in the top left corner.
+///
+/// Restyle its `Node` or `TextFont` to move or resize the panel.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkDebugUiPanel;
+
+/// Creates the hidden panel
+fn setup_chunk_debug_ui(mut commands: Commands) {
+    commands.spawn((
+        ChunkDebugUiPanel,
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        BackgroundColor(Color::BLACK.with_alpha(0.6)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+/// Writes the current stats into the panel
+fn update_chunk_debug_ui(
+    stats: Res<ChunkStats>,
+    progress: Res<ChunkStreamingProgress>,
+    loaders: Query<(Entity, &ChunkLoaderStats), Without<ChunkLoaderDisabled>>,
+    config: Res<ChunkVisualizerConfig>,
+    #[cfg(feature = "chunk_picking")] selected: Option<Res<crate::chunk_picking::SelectedChunk>>,
+    mut panels: Query<&mut Text, With<ChunkDebugUiPanel>>,
+) {
+    // The enabled loader spawned first
+    let primary_loader = loaders
+        .iter()
+        .min_by_key(|(entity, _)| *entity)
+        .map(|(_, loader)| loader);
+
+    let mut text = String::new();
+    let _ = writeln!(text, "chunks loaded: {}", stats.loaded);
+    let _ = writeln!(text, "pending: {}", progress.pending_spawn);
+    let _ = writeln!(text, "generating: {}", progress.generating);
+    let _ = writeln!(text, "spawned this frame: {}", stats.spawned_this_frame);
+    let _ = match primary_loader {
+        Some(loader) => write!(
+            text,
+            "loader chunk: {} (+{})",
+            loader.center_chunk, loader.spawned_last_pass
+        ),
+        None => write!(text, "loader chunk: none"),
+    };
+    #[cfg(feature = "chunk_picking")]
+    if let Some(chunk_pos) = selected.and_then(|selected| selected.0) {
+        let _ = write!(text, "\nselected chunk: {chunk_pos}");
+    }
+    if let Some(y_slice) = &config.y_slice {
+        let _ = write!(text, "\ny slice: {} ..= {}", y_slice.start(), y_slice.end());
+    }
+    if config.draw_mode == DrawMode::Heatmap {
+        let _ = write!(
+            text,
+            "\nheatmap: blue {} .. red {} sources",
+            config.heatmap_min, config.heatmap_max
+        );
+    }
+
+    for mut panel in panels.iter_mut() {
+        if panel.0 != text {
+            panel.0.clone_from(&text);
+        }
+    }
+}
+
+/// Shows the panel while the visualizer is on
+fn show_chunk_debug_ui(
+    state: Option<Res<State<ChunkBoundryVisualizer>>>,
+    enabled: Option<Res<ChunkVisualizerEnabled>>,
+    mut panels: Query<&mut Visibility, With<ChunkDebugUiPanel>>,
+) {
+    let visibility = if visualizer_enabled(state, enabled) {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut panel in panels.iter_mut() {
+        panel.set_if_neq(visibility);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chunk, ChunkPos, ChunkyPlugin, chunk_loader::ChunkLoader, tests::app};
+
+    #[test]
+    fn the_panel_shows_the_stats() {
+        let mut app = app((ChunkyPlugin::default(), ChunkDebugUiPlugin));
+        app.insert_resource(ChunkVisualizerEnabled(true));
+        app.world_mut().spawn((
+            Transform::from_xyz(15.0, 0.0, 0.0),
+            GlobalTransform::from_xyz(15.0, 0.0, 0.0),
+            ChunkLoader(IVec3::new(1, 0, 0)),
+        ));
+        app.world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::new(-5, 0, 0))));
+        app.update();
+
+        let world = app.world_mut();
+        let stats = *world.resource::<ChunkStats>();
+        let progress = world.resource::<ChunkStreamingProgress>().clone();
+        let loader = *world.query::<&ChunkLoaderStats>().single(world).unwrap();
+        let (text, visibility) = world
+            .query_filtered::<(&Text, &Visibility), With<ChunkDebugUiPanel>>()
+            .single(world)
+            .unwrap();
+        assert_eq!(*visibility, Visibility::Inherited);
+        assert_eq!(
+            text.0,
+            format!(
+                "chunks loaded: {}\npending: {}\ngenerating: {}\nspawned this frame: {}\n\
+                 loader chunk: {} (+{})",
+                stats.loaded,
+                progress.pending_spawn,
+                progress.generating,
+                stats.spawned_this_frame,
+                loader.center_chunk,
+                loader.spawned_last_pass,
+            )
+        );
+        assert_eq!(stats.loaded, 4);
+        assert_eq!(stats.spawned_this_frame, 4);
+        assert_eq!(loader.center_chunk, IVec3::new(1, 0, 0));
+        assert_eq!(loader.spawned_last_pass, 3);
+    }
+}
@@ -0,0 +1,1926 @@
+//! Asynchronous chunk generation.
+//!
+//! Implement [`ChunkGenerator`] for your world generator and add a
+//! [`ChunkGenerationPlugin`]. Every chunk in the `Requested` state gets a task on the
+//! [`AsyncComputeTaskPool`]; once it finishes, the result is inserted as
+//! [`ChunkData`] and the chunk's [`ChunkLoadState`] becomes `Ready`. With
+//! [`GenerationMode::Immediate`], the generator runs on the main thread instead.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! struct FlatGenerator;
+//!
+//! impl ChunkGenerator for FlatGenerator {
+//!     type Output = Vec<u8>;
+//!
+//!     fn generate(&self, pos: IVec3, _ctx: GenerationContext) -> GenerationResult<Self::Output> {
+//!         Ok(if pos.y < 0 { vec![1; 1000] } else { vec![0; 1000] })
+//!     }
+//! }
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(ChunkyPlugin::default())
+//!     .add_plugins(ChunkGenerationPlugin::new(FlatGenerator));
+//! ```
+
+use std::{
+    any::Any,
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+    error::Error,
+    fmt,
+    marker::PhantomData,
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::Arc,
+    time::Instant,
+};
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, futures::check_ready},
+};
+
+use crate::{
+    Chunk, ChunkLayer, ChunkLoadState, ChunkLoadStateCommands, ChunkManager, ChunkPos,
+    ChunkySystems, FailedChunks, NEIGHBORS_26,
+    chunk_prewarm::ChunkPrewarmPumps,
+    chunk_stages::{
+        ChunkStage, ChunkStageCompleted, GenerationStage, GenerationStages, StageNeighborhood,
+        advance_chunk_stages, run_chunk_stages,
+    },
+};
+
+#[cfg(feature = "chunk_persistence")]
+use crate::ChunkPersistent;
+#[cfg(feature = "chunk_loader")]
+use crate::chunk_loader::ChunkLoader;
+#[cfg(feature = "chunk_persistence")]
+use crate::chunk_persistence::{
+    ChunkDirty, ChunkLoadCorrupt, ChunkMigrationFailed, ChunkPersistence, CorruptChunkPolicy,
+    LoadingFromDisk, StoredChunk,
+};
+
+/// Adds asynchronous generation for the chunks using generator `G`.
+pub struct ChunkGenerationPlugin<G: ChunkGenerator> {
+    generator: Arc<G>,
+    seed: u64,
+    mode: GenerationMode,
+    snapshot: Option<SnapshotFn<G::Output>>,
+    stages: Vec<Arc<dyn GenerationStage<G::Output>>>,
+    stage_neighborhood: StageNeighborhood,
+}
+
+impl<G: ChunkGenerator> ChunkGenerationPlugin<G> {
+    /// Creates a generation plugin driven by the given generator
+    pub fn new(generator: G) -> Self {
+        Self {
+            generator: Arc::new(generator),
+            seed: 0,
+            mode: GenerationMode::Async,
+            snapshot: None,
+            stages: Vec::new(),
+            stage_neighborhood: StageNeighborhood::Faces,
+        }
+    }
+
+    /// Sets the initial [`WorldSeed`]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the initial [`GenerationMode`]
+    pub fn with_mode(mut self, mode: GenerationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Adds a [`GenerationStage`] after the generator and any stages added
+    /// before it.
+    ///
+    /// The generator output counts as stage `0`. Stage `k` of a chunk runs once
+    /// every neighbor in the [`StageNeighborhood`] completed stage `k - 1`, and
+    /// the chunk stays `Generating` until its last stage ran.
+    pub fn with_stage(mut self, stage: impl GenerationStage<G::Output>) -> Self {
+        self.stages.push(Arc::new(stage));
+        self
+    }
+
+    /// Sets which neighbors must complete a stage before the next one runs
+    pub fn with_stage_neighborhood(mut self, neighborhood: StageNeighborhood) -> Self {
+        self.stage_neighborhood = neighborhood;
+        self
+    }
+}
+
+impl<G: ChunkGenerator> ChunkGenerationPlugin<G>
+where
+    G::Output: Clone,
+{
+    /// Makes the data of already generated neighbors available through
+    /// [`GenerationContext::neighbor_data`].
+    ///
+    /// Every neighbor payload is cloned once per frame in which a chunk next to
+    /// it starts generating, so keep payloads cheap to clone.
+    pub fn with_neighbor_data(mut self) -> Self {
+        self.snapshot = Some(|data| Arc::new(data.clone()));
+        self
+    }
+}
+
+impl<G: ChunkGenerator> Plugin for ChunkGenerationPlugin<G> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ActiveGenerator {
+            generator: self.generator.clone(),
+            snapshot: self.snapshot,
+        })
+        .insert_resource(WorldSeed(self.seed))
+        .insert_resource(self.mode)
+        .init_resource::<GenerationBudget>()
+        .init_resource::<GenerationQueue<G>>()
+        .init_resource::<GeneratorRegistered>()
+        .add_message::<ChunkAborted>()
+        .add_message::<ChunkGenerationFailed>()
+        .add_message::<ChunkStageCompleted>()
+        .register_diagnostic(Diagnostic::new(GENERATION_QUEUE_LENGTH))
+        .register_diagnostic(Diagnostic::new(GENERATION_IN_FLIGHT))
+        .add_systems(
+            Update,
+            (
+                queue_chunk_generation::<G>,
+                start_chunk_generation::<G>,
+                poll_chunk_generation::<G>,
+            )
+                .chain()
+                .in_set(ChunkySystems::Generate),
+        );
+        if !self.stages.is_empty() {
+            app.insert_resource(GenerationStages {
+                stages: self.stages.clone(),
+                neighborhood: self.stage_neighborhood,
+            })
+            .add_systems(
+                Update,
+                advance_chunk_stages::<G::Output>
+                    .after(poll_chunk_generation::<G>)
+                    .in_set(ChunkySystems::Generate),
+            );
+        }
+        // Retries and regeneration are shared by all generators, so only
+        // schedule them once
+        if !app.world().contains_resource::<GenerationRetryPolicy>() {
+            app.init_resource::<GenerationRetryPolicy>()
+                .add_message::<RegenerateChunk>()
+                .add_systems(
+                    Update,
+                    (retry_failed_generation, regenerate_chunks).in_set(ChunkySystems::Generate),
+                );
+        }
+        #[cfg(feature = "chunk_persistence")]
+        app.add_message::<ChunkLoadCorrupt>()
+            .add_message::<ChunkMigrationFailed>()
+            .add_systems(
+                Update,
+                poll_chunk_loads::<G>
+                    .after(queue_chunk_generation::<G>)
+                    .after(regenerate_chunks)
+                    .before(start_chunk_generation::<G>)
+                    .in_set(ChunkySystems::Generate),
+            );
+        app.world_mut()
+            .get_resource_or_init::<ChunkPrewarmPumps>()
+            .0
+            .push(pump_chunk_generation::<G>);
+        #[cfg(feature = "reflect")]
+        app.register_type::<WorldSeed>()
+            .register_type::<GenerationMode>()
+            .register_type::<GenerationRetryPolicy>()
+            .register_type::<ChunkGenerationRetry>()
+            .register_type::<ChunkStage>();
+    }
+}
+
+/// Marks that a generation plugin is registered, so chunks move on from
+/// `Requested`
+#[derive(Resource, Default)]
+pub(crate) struct GeneratorRegistered;
+
+/// Produces the contents of a chunk.
+///
+/// `generate` runs on the [`AsyncComputeTaskPool`], so it must not access the
+/// ECS. Returning an error, or panicking, marks the chunk as
+/// [`ChunkLoadState::Failed`] and fires [`ChunkGenerationFailed`]; the chunk is
+/// then retried according to the [`GenerationRetryPolicy`].
+pub trait ChunkGenerator: Send + Sync + 'static {
+    /// User-defined chunk payload, inserted as [`ChunkData<Self::Output>`]
+    type Output: Send + Sync + 'static;
+
+    /// Generates the payload for the chunk at `pos`
+    fn generate(&self, pos: IVec3, ctx: GenerationContext) -> GenerationResult<Self::Output>;
+}
+
+/// The result of [`ChunkGenerator::generate`]
+pub type GenerationResult<T> = Result<T, GenerationError>;
+
+/// Error returned by a [`ChunkGenerator`] that could not generate a chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationError(pub String);
+
+impl GenerationError {
+    /// Creates an error with the given message
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for GenerationError {}
+
+/// Fired every time generating a chunk fails.
+#[derive(Message, Debug, Clone)]
+pub struct ChunkGenerationFailed {
+    /// Position of the chunk in chunk coordinates
+    pub pos: IVec3,
+    /// Why the generator failed
+    pub error: GenerationError,
+}
+
+/// How often failed chunks are generated again before giving up.
+///
+/// The n-th retry waits `backoff_frames * 2^(n - 1)` frames after the failure.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn setup(mut commands: Commands) {
+///     // Retry after 10, 20 and 40 frames
+///     commands.insert_resource(GenerationRetryPolicy {
+///         retries: 3,
+///         backoff_frames: 10,
+///     });
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct GenerationRetryPolicy {
+    /// Number of retries after the first failure
+    pub retries: u8,
+    /// Frames to wait before the first retry
+    pub backoff_frames: u32,
+}
+
+impl Default for GenerationRetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            backoff_frames: 8,
+        }
+    }
+}
+
+/// Tracks the failed generation attempts of a chunk.
+///
+/// Inserted on the first failure and removed once the chunk becomes `Ready`.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkGenerationRetry {
+    /// Number of failed attempts so far
+    pub failures: u8,
+    /// Frames left until the next retry, `None` once given up
+    pub frames_left: Option<u32>,
+}
+
+/// Information about the chunk being generated.
+#[derive(Debug, Clone)]
+pub struct GenerationContext {
+    /// Position of the chunk in chunk coordinates
+    pub chunk_pos: IVec3,
+    /// The [`ChunkLayer`] of the chunk, 0 for the default layer
+    pub layer: i32,
+    /// Size of chunks in world units
+    pub chunk_size: Vec3,
+    /// World position of the chunk's origin corner
+    pub origin: Vec3,
+    /// The [`WorldSeed`] at the time generation started
+    pub seed: u64,
+    neighbors: HashMap<IVec3, Arc<dyn Any + Send + Sync>>,
+}
+
+impl GenerationContext {
+    /// Returns a random number generator unique to this chunk and seed.
+    ///
+    /// The same chunk always gets the same sequence, regardless of load order.
+    /// See [`chunk_seed`] for the stable seeding scheme.
+    pub fn chunk_rng(&self) -> ChunkRng {
+        ChunkRng::new(chunk_seed(self.seed, self.chunk_pos))
+    }
+
+    /// Returns a read-only copy of the neighbor's data at `offset`, e.g.
+    /// `IVec3::NEG_X`, on the same [`ChunkLayer`], as it was when generation
+    /// started.
+    ///
+    /// Requires [`ChunkGenerationPlugin::with_neighbor_data`], and `T` must be
+    /// the generator's output. Neighbors that are not loaded or not generated
+    /// yet return `None`, so structures crossing chunk borders must tolerate
+    /// missing neighbors, e.g. by letting whichever side generates second
+    /// continue them.
+    pub fn neighbor_data<T: Send + Sync + 'static>(&self, offset: IVec3) -> Option<&T> {
+        self.neighbors.get(&offset)?.downcast_ref()
+    }
+}
+
+/// The seed shared by every generator invocation.
+///
+/// Set initially through [`ChunkGenerationPlugin::with_seed`]. Changing it only
+/// affects chunks generated afterwards.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deref)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct WorldSeed(pub u64);
+
+/// Derives the seed of a chunk from the world seed.
+///
+/// This scheme is stable across versions: starting with `h = seed`, each of
+/// `x`, `y` and `z` (as their 32-bit two's complement pattern) is mixed in with
+/// `h = splitmix64(h ^ c)`.
+pub fn chunk_seed(seed: u64, chunk_pos: IVec3) -> u64 {
+    [chunk_pos.x, chunk_pos.y, chunk_pos.z]
+        .into_iter()
+        .fold(seed, |h, c| splitmix64(h ^ c as u32 as u64))
+}
+
+/// The SplitMix64 finalizer
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// A small deterministic random number generator (SplitMix64).
+///
+/// Its output for a given seed is stable across versions. Use [`ChunkRng::seed`]
+/// to seed a different RNG instead.
+#[derive(Debug, Clone)]
+pub struct ChunkRng {
+    seed: u64,
+    state: u64,
+}
+
+impl ChunkRng {
+    /// Creates a generator starting from `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    /// The seed this generator started from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the next random `u64`
+    pub fn next_u64(&mut self) -> u64 {
+        let value = splitmix64(self.state);
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        value
+    }
+
+    /// Returns the next random `u32`
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns a random `f32` in `[0, 1)`
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Limits how many generation tasks run at once and how much main-thread time
+/// is spent applying finished results each frame.
+///
+/// Requested chunks are dispatched nearest to any [`ChunkLoader`] first, as
+/// long as fewer than `max_concurrent_tasks` are in flight. Once either apply
+/// limit is reached, the remaining results wait for the next frame, again
+/// nearest first. A value of zero disables the corresponding limit (default).
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn setup(mut commands: Commands) {
+///     commands.insert_resource(GenerationBudget {
+///         max_concurrent_tasks: 64,
+///         max_apply_per_frame: 16,
+///         max_apply_millis: 2.0,
+///     });
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct GenerationBudget {
+    /// Maximum number of generation tasks in flight
+    pub max_concurrent_tasks: usize,
+    /// Maximum number of results applied per frame
+    pub max_apply_per_frame: usize,
+    /// Maximum time in milliseconds spent applying results per frame
+    pub max_apply_millis: f32,
+}
+
+impl GenerationBudget {
+    /// Checks if the budget is used up after applying `applied` results since `start`
+    pub(crate) fn is_exhausted(&self, applied: usize, start: Instant) -> bool {
+        (self.max_apply_per_frame > 0 && applied >= self.max_apply_per_frame)
+            || (self.max_apply_millis > 0.0
+                && start.elapsed().as_secs_f32() * 1000.0 >= self.max_apply_millis)
+    }
+}
+
+/// Diagnostic measuring the number of chunks waiting for a generation task
+pub const GENERATION_QUEUE_LENGTH: DiagnosticPath =
+    DiagnosticPath::const_new("chunky/generation_queue_length");
+
+/// Diagnostic measuring the number of generation tasks in flight
+pub const GENERATION_IN_FLIGHT: DiagnosticPath =
+    DiagnosticPath::const_new("chunky/generation_in_flight");
+
+/// Where [`ChunkGenerator::generate`] runs.
+///
+/// Set initially through [`ChunkGenerationPlugin::with_mode`] and switchable at
+/// runtime. Both modes go through the same load states, messages and
+/// [`GenerationBudget`].
+///
+/// On `wasm32`, or with the `immediate_generation` feature, `Async` behaves like
+/// `Immediate`, so no code has to change for targets without threads.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub enum GenerationMode {
+    /// Generate on the [`AsyncComputeTaskPool`] where threads are available
+    /// (default)
+    #[default]
+    Async,
+    /// Generate on the main thread while applying results, for trivial
+    /// generators or targets without threads
+    Immediate,
+}
+
+/// The generated payload of a chunk.
+#[derive(Component, Debug, Clone, Deref, DerefMut)]
+pub struct ChunkData<T: Send + Sync + 'static>(pub T);
+
+/// Holds the pending generation of a chunk.
+///
+/// Removed once generation completes. Removing it earlier, e.g. by despawning
+/// the chunk, drops and thereby cancels the task and fires [`ChunkAborted`].
+#[derive(Component)]
+#[component(on_remove = on_remove_generating_chunk::<T>)]
+pub struct GeneratingChunk<T: Send + Sync + 'static>(GenerationJob<T>);
+
+/// The work behind a GeneratingChunk
+enum GenerationJob<T> {
+    /// Running on the AsyncComputeTaskPool
+    Task(Task<GenerationResult<T>>),
+    /// Waiting to run on the main thread
+    Immediate(GenerationContext),
+    /// The result was taken
+    Done,
+}
+
+/// Fired when a chunk's generation is cancelled before its result was applied.
+#[derive(Message, Debug, Clone)]
+pub struct ChunkAborted {
+    /// Position of the chunk in chunk coordinates
+    pub pos: IVec3,
+}
+
+/// Fires ChunkAborted if the task had not completed yet
+fn on_remove_generating_chunk<T: Send + Sync + 'static>(
+    mut world: DeferredWorld,
+    HookContext { entity, .. }: HookContext,
+) {
+    let completed = world
+        .get::<GeneratingChunk<T>>(entity)
+        .is_none_or(|generating| matches!(generating.0, GenerationJob::Done));
+    if completed {
+        return;
+    }
+    if let Some(pos) = world.get::<ChunkPos>(entity).map(|pos| pos.0) {
+        world.write_message(ChunkAborted { pos });
+    }
+}
+
+/// The generator shared with generation tasks
+#[derive(Resource)]
+struct ActiveGenerator<G: ChunkGenerator> {
+    generator: Arc<G>,
+    snapshot: Option<SnapshotFn<G::Output>>,
+}
+
+/// Copies chunk data for neighbor access
+type SnapshotFn<T> = fn(&T) -> Arc<dyn Any + Send + Sync>;
+
+/// Chunks waiting for a generation task, nearest to a loader first
+#[derive(Resource)]
+struct GenerationQueue<G: ChunkGenerator> {
+    heap: BinaryHeap<QueuedChunk>,
+    queued: HashSet<Entity>,
+    next_order: u64,
+    /// The loader chunks the distances in the heap were measured from
+    loader_chunks: Vec<IVec3>,
+    _generator: PhantomData<G>,
+}
+
+impl<G: ChunkGenerator> Default for GenerationQueue<G> {
+    fn default() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            queued: HashSet::new(),
+            next_order: 0,
+            loader_chunks: Vec::new(),
+            _generator: PhantomData,
+        }
+    }
+}
+
+impl<G: ChunkGenerator> GenerationQueue<G> {
+    /// Queues a chunk unless it is queued already
+    fn push(&mut self, entity: Entity, pos: IVec3, distance: f32) {
+        if !self.queued.insert(entity) {
+            return;
+        }
+        self.heap.push(QueuedChunk {
+            distance,
+            order: self.next_order,
+            entity,
+            pos,
+        });
+        self.next_order += 1;
+    }
+
+    /// Measures the distances again if a loader moved to another chunk since
+    /// they were measured, as chunks may have come closer or moved away
+    fn reprioritize(&mut self, loader_chunks: &[IVec3]) {
+        if self.loader_chunks == loader_chunks {
+            return;
+        }
+        let mut entries = std::mem::take(&mut self.heap).into_vec();
+        for entry in &mut entries {
+            entry.distance = nearest_loader_distance(entry.pos, loader_chunks);
+        }
+        self.heap = entries.into();
+        self.loader_chunks = loader_chunks.to_vec();
+    }
+}
+
+/// An entry of the GenerationQueue; the distance is measured again when
+/// loaders change chunks
+struct QueuedChunk {
+    distance: f32,
+    order: u64,
+    entity: Entity,
+    pos: IVec3,
+}
+
+impl Ord for QueuedChunk {
+    // Reversed, so the nearest and then oldest entry is on top of the max-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+impl PartialOrd for QueuedChunk {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueuedChunk {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedChunk {}
+
+/// Queues every chunk that entered the `Requested` state
+fn queue_chunk_generation<G: ChunkGenerator>(
+    mut queue: ResMut<GenerationQueue<G>>,
+    chunks: Query<
+        (Entity, &ChunkPos, Option<&ChunkLoadState>),
+        (
+            With<Chunk>,
+            Without<GeneratingChunk<G::Output>>,
+            Or<(Added<Chunk>, Changed<ChunkLoadState>)>,
+        ),
+    >,
+    #[cfg(feature = "chunk_loader")] loaders: Query<&GlobalTransform, With<ChunkLoader>>,
+    #[cfg(feature = "chunk_loader")] chunk_manager: Res<ChunkManager>,
+    #[cfg(feature = "chunk_persistence")] loading: Query<(), With<LoadingFromDisk<G::Output>>>,
+) {
+    #[cfg(feature = "chunk_loader")]
+    let loader_chunks = loader_chunks(&loaders, &chunk_manager);
+    #[cfg(not(feature = "chunk_loader"))]
+    let loader_chunks: Vec<IVec3> = Vec::new();
+
+    for (entity, ChunkPos(chunk_pos), load_state) in chunks.iter() {
+        if load_state.is_some_and(|state| *state != ChunkLoadState::Requested) {
+            continue;
+        }
+        #[cfg(feature = "chunk_persistence")]
+        if loading.contains(entity) {
+            continue;
+        }
+        queue.push(
+            entity,
+            *chunk_pos,
+            nearest_loader_distance(*chunk_pos, &loader_chunks),
+        );
+    }
+}
+
+/// Applies stored data whose read completed, and queues the chunks without
+/// usable stored data for generation
+#[cfg(feature = "chunk_persistence")]
+fn poll_chunk_loads<G: ChunkGenerator>(
+    mut commands: Commands,
+    mut queue: ResMut<GenerationQueue<G>>,
+    mut chunks: Query<(
+        Entity,
+        &ChunkPos,
+        Option<&ChunkLoadState>,
+        &mut LoadingFromDisk<G::Output>,
+        Has<GenerateFresh>,
+    )>,
+    mut corrupt: MessageWriter<ChunkLoadCorrupt>,
+    mut migration_failed: MessageWriter<ChunkMigrationFailed>,
+    stages: Option<Res<GenerationStages<G::Output>>>,
+    persistence: Option<Res<ChunkPersistence<G::Output>>>,
+    #[cfg(feature = "chunk_loader")] loaders: Query<&GlobalTransform, With<ChunkLoader>>,
+    #[cfg(feature = "chunk_loader")] chunk_manager: Res<ChunkManager>,
+) {
+    #[cfg(feature = "chunk_loader")]
+    let loader_chunks = loader_chunks(&loaders, &chunk_manager);
+    #[cfg(not(feature = "chunk_loader"))]
+    let loader_chunks: Vec<IVec3> = Vec::new();
+    let policy = persistence.map_or_else(default, |persistence| persistence.corrupt_policy);
+
+    for (entity, ChunkPos(chunk_pos), load_state, mut loading, fresh) in chunks.iter_mut() {
+        let Some(stored) = loading.0.poll() else {
+            continue;
+        };
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.try_remove::<LoadingFromDisk<G::Output>>();
+        if load_state.is_some_and(|state| *state != ChunkLoadState::Requested) {
+            continue;
+        }
+        match stored {
+            // Regeneration was requested while reading
+            _ if fresh => {}
+            StoredChunk::Loaded(data) => {
+                entity_commands
+                    .try_insert((ChunkData(data), ChunkPersistent))
+                    .try_remove::<ChunkDirty>();
+                // Stored data already went through every stage
+                if let Some(stages) = &stages {
+                    entity_commands.try_insert(ChunkStage(stages.stages.len() as u8));
+                }
+                entity_commands.set_load_state(ChunkLoadState::Ready);
+                continue;
+            }
+            // The generated data replaces the unusable stored bytes
+            StoredChunk::Corrupt { checksums } => {
+                corrupt.write(ChunkLoadCorrupt {
+                    pos: *chunk_pos,
+                    expected: checksums.map(|(expected, _)| expected),
+                    found: checksums.map(|(_, found)| found),
+                });
+                if policy == CorruptChunkPolicy::Fail {
+                    entity_commands.set_load_state(ChunkLoadState::Failed);
+                    continue;
+                }
+                entity_commands.try_insert(ChunkDirty);
+            }
+            StoredChunk::MigrationFailed { version } => {
+                migration_failed.write(ChunkMigrationFailed {
+                    pos: *chunk_pos,
+                    version,
+                });
+                entity_commands.try_insert(ChunkDirty);
+            }
+            StoredChunk::Missing => {}
+        }
+        entity_commands.try_insert(GenerateFresh);
+        queue.push(
+            entity,
+            *chunk_pos,
+            nearest_loader_distance(*chunk_pos, &loader_chunks),
+        );
+    }
+}
+
+/// Whether GenerationMode::Async spawns tasks, otherwise it falls back to
+/// immediate generation
+const ASYNC_GENERATION: bool = cfg!(not(any(
+    target_arch = "wasm32",
+    feature = "immediate_generation"
+)));
+
+/// Starts generation tasks for queued chunks, nearest to a loader first, up to
+/// the concurrency limit of the [`GenerationBudget`]
+fn start_chunk_generation<G: ChunkGenerator>(
+    mut commands: Commands,
+    mut queue: ResMut<GenerationQueue<G>>,
+    mut diagnostics: Diagnostics,
+    generator: Res<ActiveGenerator<G>>,
+    seed: Res<WorldSeed>,
+    mode: Res<GenerationMode>,
+    budget: Res<GenerationBudget>,
+    chunk_manager: Res<ChunkManager>,
+    in_flight: Query<(), With<GeneratingChunk<G::Output>>>,
+    chunks: Query<
+        (Option<&ChunkLoadState>, Option<&ChunkLayer>),
+        (With<Chunk>, Without<GeneratingChunk<G::Output>>),
+    >,
+    chunk_data: Query<&ChunkData<G::Output>>,
+    #[cfg(feature = "chunk_loader")] loaders: Query<&GlobalTransform, With<ChunkLoader>>,
+    #[cfg(feature = "chunk_persistence")] mut persistence: Option<
+        ResMut<ChunkPersistence<G::Output>>,
+    >,
+    #[cfg(feature = "chunk_persistence")] fresh: Query<(), With<GenerateFresh>>,
+) {
+    #[cfg(feature = "chunk_loader")]
+    let loader_chunks = loader_chunks(&loaders, &chunk_manager);
+    #[cfg(not(feature = "chunk_loader"))]
+    let loader_chunks: Vec<IVec3> = Vec::new();
+
+    let chunk_size = chunk_manager.get_size();
+    let mut in_flight = in_flight.iter().count();
+    let queue = queue.as_mut();
+    // Snapshots taken this frame, shared by all chunks next to them
+    let mut snapshots = HashMap::new();
+
+    // Loaders may have moved since the chunks were queued
+    queue.reprioritize(&loader_chunks);
+    while budget.max_concurrent_tasks == 0 || in_flight < budget.max_concurrent_tasks {
+        let Some(next) = queue.heap.pop() else {
+            break;
+        };
+        queue.queued.remove(&next.entity);
+
+        let QueuedChunk { entity, pos, .. } = next;
+        let Ok((load_state, layer)) = chunks.get(entity) else {
+            continue;
+        };
+        if load_state.is_some_and(|state| *state != ChunkLoadState::Requested) {
+            continue;
+        }
+        let layer = layer.map_or(0, |layer| layer.0);
+        // Skip chunks that lost the registration to an existing one
+        if chunk_manager.get_chunk_in_layer(&pos, layer) != Some(entity) {
+            continue;
+        }
+
+        // Stores are keyed by position only, so other layers always generate
+        #[cfg(feature = "chunk_persistence")]
+        if let Some(persistence) = &mut persistence
+            && layer == 0
+            && !fresh.contains(entity)
+        {
+            commands
+                .entity(entity)
+                .insert(LoadingFromDisk(persistence.load(pos)));
+            continue;
+        }
+
+        let mut neighbors = HashMap::new();
+        if let Some(snapshot) = generator.snapshot {
+            for offset in NEIGHBORS_26 {
+                let Some(neighbor) = chunk_manager.get_chunk_in_layer(&(pos + offset), layer)
+                else {
+                    continue;
+                };
+                let Ok(ChunkData(data)) = chunk_data.get(neighbor) else {
+                    continue;
+                };
+                let data = snapshots.entry(neighbor).or_insert_with(|| snapshot(data));
+                neighbors.insert(offset, data.clone());
+            }
+        }
+
+        let ctx = GenerationContext {
+            chunk_pos: pos,
+            layer,
+            chunk_size,
+            origin: pos.as_vec3() * chunk_size,
+            seed: seed.0,
+            neighbors,
+        };
+        let job = match *mode {
+            GenerationMode::Async if ASYNC_GENERATION => {
+                let generator = generator.generator.clone();
+                GenerationJob::Task(
+                    AsyncComputeTaskPool::get()
+                        .spawn(async move { run_generator(&*generator, ctx) }),
+                )
+            }
+            _ => GenerationJob::Immediate(ctx),
+        };
+
+        commands
+            .entity(entity)
+            .insert(GeneratingChunk::<G::Output>(job))
+            .remove::<(ChunkStage, GenerateFresh)>()
+            .set_load_state(ChunkLoadState::Generating);
+        in_flight += 1;
+    }
+
+    diagnostics.add_measurement(&GENERATION_QUEUE_LENGTH, || queue.heap.len() as f64);
+    diagnostics.add_measurement(&GENERATION_IN_FLIGHT, || in_flight as f64);
+}
+
+/// Calls the generator, turning a panic into an error
+fn run_generator<G: ChunkGenerator>(
+    generator: &G,
+    ctx: GenerationContext,
+) -> GenerationResult<G::Output> {
+    catch_unwind(AssertUnwindSafe(|| generator.generate(ctx.chunk_pos, ctx)))
+        .unwrap_or_else(|_| Err(GenerationError::new("generator panicked")))
+}
+
+/// Applies the results of finished generation tasks and runs immediate
+/// generation, nearest chunks first, within the [`GenerationBudget`]
+fn poll_chunk_generation<G: ChunkGenerator>(
+    mut commands: Commands,
+    generator: Res<ActiveGenerator<G>>,
+    mut chunks: Query<(
+        Entity,
+        &ChunkPos,
+        &mut GeneratingChunk<G::Output>,
+        Option<&ChunkGenerationRetry>,
+    )>,
+    mut failed: MessageWriter<ChunkGenerationFailed>,
+    mut stage_completed: MessageWriter<ChunkStageCompleted>,
+    budget: Res<GenerationBudget>,
+    retry_policy: Res<GenerationRetryPolicy>,
+    stages: Option<Res<GenerationStages<G::Output>>>,
+    #[cfg(feature = "chunk_loader")] loaders: Query<&GlobalTransform, With<ChunkLoader>>,
+    #[cfg(feature = "chunk_loader")] chunk_manager: Res<ChunkManager>,
+) {
+    let start = Instant::now();
+
+    #[cfg(feature = "chunk_loader")]
+    let loader_chunks = loader_chunks(&loaders, &chunk_manager);
+    #[cfg(not(feature = "chunk_loader"))]
+    let loader_chunks: Vec<IVec3> = Vec::new();
+
+    let mut pending: Vec<_> = chunks
+        .iter()
+        .map(|(entity, pos, ..)| (entity, pos.0))
+        .collect();
+    pending.sort_by(|(_, a), (_, b)| {
+        nearest_loader_distance(*a, &loader_chunks)
+            .total_cmp(&nearest_loader_distance(*b, &loader_chunks))
+    });
+
+    let mut applied = 0;
+    for (entity, chunk_pos) in pending {
+        if budget.is_exhausted(applied, start) {
+            break;
+        }
+        let Ok((_, _, mut generating, retry)) = chunks.get_mut(entity) else {
+            continue;
+        };
+        let result = match &mut generating.0 {
+            GenerationJob::Task(task) => check_ready(task),
+            GenerationJob::Immediate(ctx) => {
+                Some(run_generator(&*generator.generator, ctx.clone()))
+            }
+            GenerationJob::Done => None,
+        };
+        let Some(result) = result else {
+            continue;
+        };
+        generating.0 = GenerationJob::Done;
+        applied += 1;
+
+        // The chunk may be despawned before these commands apply, so nothing
+        // must be inserted on a dead or recycled entity
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.try_remove::<GeneratingChunk<G::Output>>();
+        match result {
+            Ok(data) => {
+                entity_commands
+                    .try_insert(ChunkData(data))
+                    .try_remove::<ChunkGenerationRetry>();
+                // With stages the chunk stays Generating until the last one ran
+                if stages.is_some() {
+                    entity_commands.try_insert(ChunkStage(0));
+                    stage_completed.write(ChunkStageCompleted {
+                        entity,
+                        pos: chunk_pos,
+                        stage: 0,
+                    });
+                } else {
+                    entity_commands.set_load_state(ChunkLoadState::Ready);
+                }
+            }
+            Err(error) => {
+                fail_generation(
+                    &mut entity_commands,
+                    retry,
+                    &retry_policy,
+                    chunk_pos,
+                    &error,
+                );
+                failed.write(ChunkGenerationFailed {
+                    pos: chunk_pos,
+                    error,
+                });
+            }
+        }
+    }
+}
+
+/// Marks a chunk as failed and schedules its retry according to the
+/// [`GenerationRetryPolicy`]
+pub(crate) fn fail_generation(
+    entity_commands: &mut EntityCommands,
+    retry: Option<&ChunkGenerationRetry>,
+    retry_policy: &GenerationRetryPolicy,
+    chunk_pos: IVec3,
+    error: &GenerationError,
+) {
+    warn!("Generation of chunk at pos:{} failed: {}", chunk_pos, error);
+    let failures = retry.map_or(0, |retry| retry.failures).saturating_add(1);
+    let frames_left = (failures <= retry_policy.retries).then(|| {
+        retry_policy
+            .backoff_frames
+            .saturating_mul(1 << (failures - 1).min(31))
+    });
+    entity_commands
+        .try_insert(ChunkGenerationRetry {
+            failures,
+            frames_left,
+        })
+        .try_remove::<ChunkStage>()
+        .set_load_state(ChunkLoadState::Failed);
+}
+
+/// Requests generating a loaded chunk again, e.g. after the player edited it.
+///
+/// On completion the chunk's [`ChunkData`] is replaced and
+/// [`ChunkReady`](crate::ChunkReady) fires again. Requests for a chunk that is
+/// already being regenerated are coalesced into a single extra run once the
+/// current one finishes. Only chunks on the default [`ChunkLayer`] can be
+/// regenerated.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn on_terrain_edit(mut regenerate: MessageWriter<RegenerateChunk>) {
+///     regenerate.write(RegenerateChunk {
+///         pos: IVec3::new(3, 0, -2),
+///         keep_entity: true,
+///     });
+/// }
+/// ```
+#[derive(Message, Debug, Clone)]
+pub struct RegenerateChunk {
+    /// Position of the chunk on the default layer, in chunk coordinates
+    pub pos: IVec3,
+    /// Regenerates on the existing entity if true, otherwise the chunk is
+    /// despawned and a fresh entity is spawned in its place
+    pub keep_entity: bool,
+}
+
+/// Makes the chunk run the generator even if its data could be loaded from a
+/// store
+#[derive(Component)]
+pub(crate) struct GenerateFresh;
+
+/// Marks a chunk to be regenerated once its current generation finishes
+#[derive(Component)]
+struct PendingRegeneration;
+
+/// Handles RegenerateChunk requests
+fn regenerate_chunks(
+    mut commands: Commands,
+    mut requests: MessageReader<RegenerateChunk>,
+    chunk_manager: Res<ChunkManager>,
+    chunks: Query<Option<&ChunkLoadState>, With<Chunk>>,
+    pending: Query<(Entity, &ChunkLoadState), With<PendingRegeneration>>,
+) {
+    let mut handled = HashSet::new();
+    for &RegenerateChunk { pos, keep_entity } in requests.read() {
+        let Some(entity) = chunk_manager.get_chunk(&pos) else {
+            continue;
+        };
+        if !handled.insert(pos) {
+            continue;
+        }
+
+        let entity = if keep_entity {
+            entity
+        } else {
+            commands.entity(entity).despawn();
+            commands
+                .spawn((
+                    Chunk,
+                    ChunkPos(pos),
+                    ChunkLoadState::Requested,
+                    GenerateFresh,
+                ))
+                .id()
+        };
+        // The regenerated data replaces whatever is stored
+        #[cfg(feature = "chunk_persistence")]
+        commands.entity(entity).insert(ChunkDirty);
+        if !keep_entity {
+            continue;
+        }
+        match chunks.get(entity) {
+            // Already waiting for a task, which will see the latest state
+            Ok(Some(ChunkLoadState::Requested)) => {
+                commands.entity(entity).insert(GenerateFresh);
+            }
+            Ok(Some(ChunkLoadState::Generating)) => {
+                commands.entity(entity).insert(PendingRegeneration);
+            }
+            Ok(_) => {
+                commands
+                    .entity(entity)
+                    .insert(GenerateFresh)
+                    .set_load_state(ChunkLoadState::Requested);
+            }
+            Err(_) => {}
+        }
+    }
+
+    for (entity, load_state) in pending.iter() {
+        if matches!(load_state, ChunkLoadState::Ready | ChunkLoadState::Failed) {
+            commands
+                .entity(entity)
+                .remove::<PendingRegeneration>()
+                .insert(GenerateFresh)
+                .set_load_state(ChunkLoadState::Requested);
+        }
+    }
+}
+
+/// Moves failed chunks back to `Requested` once their backoff has passed
+fn retry_failed_generation(
+    mut commands: Commands,
+    mut chunks: Query<(Entity, &mut ChunkGenerationRetry), FailedChunks>,
+) {
+    for (entity, mut retry) in chunks.iter_mut() {
+        let Some(frames_left) = retry.frames_left else {
+            continue;
+        };
+        if frames_left > 0 {
+            retry.frames_left = Some(frames_left - 1);
+            continue;
+        }
+        retry.frames_left = None;
+        commands
+            .entity(entity)
+            .set_load_state(ChunkLoadState::Requested);
+    }
+}
+
+/// Runs one dispatch and apply step, used to prewarm regions
+fn pump_chunk_generation<G: ChunkGenerator>(world: &mut World) {
+    let _ = world.run_system_cached(queue_chunk_generation::<G>);
+    #[cfg(feature = "chunk_persistence")]
+    let _ = world.run_system_cached(poll_chunk_loads::<G>);
+    let _ = world.run_system_cached(start_chunk_generation::<G>);
+    let _ = world.run_system_cached(poll_chunk_generation::<G>);
+    while run_chunk_stages::<G::Output>(world) {}
+}
+
+/// The chunk positions of all loaders
+#[cfg(feature = "chunk_loader")]
+fn loader_chunks(
+    loaders: &Query<&GlobalTransform, With<ChunkLoader>>,
+    chunk_manager: &ChunkManager,
+) -> Vec<IVec3> {
+    loaders
+        .iter()
+        .map(|transform| chunk_manager.get_chunk_pos(&transform.translation()))
+        .collect()
+}
+
+/// Squared distance in chunks from `chunk_pos` to the nearest loader chunk
+pub(crate) fn nearest_loader_distance(chunk_pos: IVec3, loader_chunks: &[IVec3]) -> f32 {
+    loader_chunks
+        .iter()
+        .map(|loader_chunk| chunk_pos.as_vec3().distance_squared(loader_chunk.as_vec3()))
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ChunkReady, ChunkyPlugin,
+        chunk_stages::StageContext,
+        tests::{app, update_until},
+    };
+    use std::{
+        sync::atomic::{self, AtomicU32},
+        time::Duration,
+    };
+
+    /// Generates the layer of each chunk
+    struct LayerGenerator;
+
+    impl ChunkGenerator for LayerGenerator {
+        type Output = i32;
+
+        fn generate(&self, _pos: IVec3, ctx: GenerationContext) -> GenerationResult<i32> {
+            Ok(ctx.layer)
+        }
+    }
+
+    /// Generates the first number of each chunk's RNG
+    struct SeededGenerator;
+
+    impl ChunkGenerator for SeededGenerator {
+        type Output = u64;
+
+        fn generate(&self, _pos: IVec3, ctx: GenerationContext) -> GenerationResult<u64> {
+            Ok(ctx.chunk_rng().next_u64())
+        }
+    }
+
+    /// The states each chunk went through, in order
+    #[derive(Resource, Default)]
+    struct StateLog(HashMap<IVec3, Vec<ChunkLoadState>>);
+
+    /// What generating a 3x3 patch of chunks in `mode` produced: the data and
+    /// the states of each chunk, and the positions of all ChunkReady messages
+    type PatchOutcome = (
+        Vec<(IVec3, u64)>,
+        HashMap<IVec3, Vec<ChunkLoadState>>,
+        Vec<IVec3>,
+    );
+
+    fn generate_patch(mode: GenerationMode) -> PatchOutcome {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(SeededGenerator)
+                .with_seed(7)
+                .with_mode(mode),
+        ));
+        app.init_resource::<StateLog>().add_observer(
+            |insert: On<Insert, ChunkLoadState>,
+             chunks: Query<(&ChunkPos, &ChunkLoadState)>,
+             mut log: ResMut<StateLog>| {
+                let (ChunkPos(pos), state) = chunks.get(insert.entity).unwrap();
+                log.0.entry(*pos).or_default().push(*state);
+            },
+        );
+        for x in -1..=1 {
+            for z in -1..=1 {
+                app.world_mut().spawn((
+                    Chunk,
+                    ChunkPos(IVec3::new(x, 0, z)),
+                    ChunkLoadState::Requested,
+                ));
+            }
+        }
+
+        let mut ready = Vec::new();
+        for _ in 0..100 {
+            app.update();
+            ready.extend(
+                app.world_mut()
+                    .resource_mut::<Messages<ChunkReady>>()
+                    .drain()
+                    .map(|ready| ready.pos),
+            );
+            if ready.len() >= 9 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        ready.sort_by_key(|pos| (pos.x, pos.z));
+
+        let world = app.world_mut();
+        let mut data: Vec<_> = world
+            .query::<(&ChunkPos, &ChunkData<u64>)>()
+            .iter(world)
+            .map(|(pos, data)| (pos.0, data.0))
+            .collect();
+        data.sort_by_key(|(pos, _)| (pos.x, pos.z));
+        let states = world.remove_resource::<StateLog>().unwrap().0;
+        (data, states, ready)
+    }
+
+    // With immediate_generation, Async falls back to Immediate, which
+    // `the_fallback_matches_recorded_async_results` covers instead
+    #[cfg(not(feature = "immediate_generation"))]
+    #[test]
+    fn async_and_immediate_generation_produce_the_same_chunks() {
+        let (data, states, ready) = generate_patch(GenerationMode::Async);
+        assert_eq!(data.len(), 9);
+        assert_eq!(ready.len(), 9);
+        use ChunkLoadState::*;
+        assert!(
+            states
+                .values()
+                .all(|states| *states == [Requested, Generating, Ready])
+        );
+
+        assert_eq!(
+            generate_patch(GenerationMode::Immediate),
+            (data, states, ready)
+        );
+    }
+
+    #[cfg(feature = "immediate_generation")]
+    #[test]
+    fn the_fallback_matches_recorded_async_results() {
+        // Recorded from `GenerationMode::Async` without immediate_generation
+        let recorded = [
+            ((-1, -1), 14357326501295437234),
+            ((-1, 0), 14359982377249937114),
+            ((-1, 1), 12720117000795514110),
+            ((0, -1), 1420032566194789888),
+            ((0, 0), 3167992149630043406),
+            ((0, 1), 319611946110763692),
+            ((1, -1), 17457849588102987629),
+            ((1, 0), 8271452263531832740),
+            ((1, 1), 9504718809853881387),
+        ]
+        .map(|((x, z), data)| (IVec3::new(x, 0, z), data));
+
+        let (data, states, ready) = generate_patch(GenerationMode::Async);
+        assert_eq!(data, recorded);
+        assert_eq!(ready, recorded.map(|(pos, _)| pos));
+        use ChunkLoadState::*;
+        assert!(
+            states
+                .values()
+                .all(|states| *states == [Requested, Generating, Ready])
+        );
+        assert_eq!(
+            generate_patch(GenerationMode::Immediate),
+            (data, states, ready)
+        );
+
+        // No task is spawned, so the data is there in the spawn frame
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(SeededGenerator).with_mode(GenerationMode::Async),
+        ));
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLoadState::Requested))
+            .id();
+        app.update();
+        assert!(app.world().entity(chunk).contains::<ChunkData<u64>>());
+    }
+
+    #[test]
+    fn reloaded_chunks_generate_bit_identical_data() {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(SeededGenerator)
+                .with_seed(7)
+                .with_mode(GenerationMode::Immediate),
+        ));
+        let generate = |app: &mut App, pos: IVec3| {
+            let chunk = app
+                .world_mut()
+                .spawn((Chunk, ChunkPos(pos), ChunkLoadState::Requested))
+                .id();
+            app.update();
+            let data = app.world().get::<ChunkData<u64>>(chunk).unwrap().0;
+            app.world_mut().despawn(chunk);
+            data
+        };
+        let pos = IVec3::new(3, -1, 2);
+        let first = generate(&mut app, pos);
+        // A different load order must not matter
+        generate(&mut app, IVec3::ZERO);
+        assert_eq!(generate(&mut app, pos), first);
+        assert_eq!(first, ChunkRng::new(chunk_seed(7, pos)).next_u64());
+    }
+
+    #[test]
+    fn regenerating_with_a_new_seed_keeps_the_entity() {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(SeededGenerator)
+                .with_seed(7)
+                .with_mode(GenerationMode::Immediate),
+        ));
+        let pos = IVec3::new(2, 0, -1);
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(pos), ChunkLoadState::Requested))
+            .id();
+        app.update();
+        let data = |app: &App| app.world().get::<ChunkData<u64>>(chunk).map(|data| data.0);
+        assert_eq!(
+            data(&app),
+            Some(ChunkRng::new(chunk_seed(7, pos)).next_u64())
+        );
+
+        app.insert_resource(WorldSeed(8));
+        app.world_mut().write_message(RegenerateChunk {
+            pos,
+            keep_entity: true,
+        });
+        app.world_mut()
+            .resource_mut::<Messages<ChunkReady>>()
+            .clear();
+        for _ in 0..3 {
+            app.update();
+        }
+
+        assert_eq!(
+            data(&app),
+            Some(ChunkRng::new(chunk_seed(8, pos)).next_u64())
+        );
+        assert_eq!(
+            app.world().resource::<ChunkManager>().get_chunk(&pos),
+            Some(chunk)
+        );
+        let ready: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Messages<ChunkReady>>()
+            .drain()
+            .map(|ready| ready.entity)
+            .collect();
+        assert_eq!(ready, [chunk]);
+    }
+
+    #[test]
+    fn post_generate_sees_each_generation_once() {
+        #[derive(Resource, Default)]
+        struct PostProcessed(Vec<u64>);
+
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(SeededGenerator)
+                .with_seed(7)
+                .with_mode(GenerationMode::Immediate),
+        ));
+        app.init_resource::<PostProcessed>().add_systems(
+            Update,
+            (|chunks: Query<&ChunkData<u64>, crate::NewlyReadyChunks>,
+              mut processed: ResMut<PostProcessed>| {
+                processed.0.extend(chunks.iter().map(|data| data.0));
+            })
+            .in_set(ChunkySystems::PostGenerate),
+        );
+        let pos = IVec3::new(1, 0, -2);
+        let spawn = |app: &mut App| {
+            let chunk = app
+                .world_mut()
+                .spawn((Chunk, ChunkPos(pos), ChunkLoadState::Requested))
+                .id();
+            for _ in 0..3 {
+                app.update();
+            }
+            chunk
+        };
+        let first = ChunkRng::new(chunk_seed(7, pos)).next_u64();
+
+        let chunk = spawn(&mut app);
+        assert_eq!(app.world().resource::<PostProcessed>().0, [first]);
+
+        // Unloading and loading the chunk again generates it again
+        app.world_mut().despawn(chunk);
+        spawn(&mut app);
+        assert_eq!(app.world().resource::<PostProcessed>().0, [first; 2]);
+
+        app.insert_resource(WorldSeed(8));
+        app.world_mut().write_message(RegenerateChunk {
+            pos,
+            keep_entity: true,
+        });
+        for _ in 0..3 {
+            app.update();
+        }
+        let second = ChunkRng::new(chunk_seed(8, pos)).next_u64();
+        assert_eq!(
+            app.world().resource::<PostProcessed>().0,
+            [first, first, second]
+        );
+    }
+
+    #[test]
+    fn the_seeding_scheme_is_pinned() {
+        assert_eq!(chunk_seed(0, IVec3::ZERO), 2558736989570252433);
+        assert_eq!(chunk_seed(7, IVec3::new(1, -2, 3)), 17701216066292292568);
+        let mut rng = ChunkRng::new(chunk_seed(7, IVec3::ZERO));
+        assert_eq!(rng.next_u64(), 3167992149630043406);
+    }
+
+    #[test]
+    fn chunks_on_other_layers_are_generated() {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(LayerGenerator).with_mode(GenerationMode::Immediate),
+        ));
+        let chunk = app
+            .world_mut()
+            .spawn((
+                Chunk,
+                ChunkPos(IVec3::ZERO),
+                ChunkLayer(4),
+                ChunkLoadState::Requested,
+            ))
+            .id();
+        app.update();
+        app.update();
+
+        let chunk = app.world().entity(chunk);
+        assert_eq!(chunk.get::<ChunkLoadState>(), Some(&ChunkLoadState::Ready));
+        assert_eq!(chunk.get::<ChunkData<i32>>().map(|data| data.0), Some(4));
+    }
+
+    #[test]
+    fn despawning_a_generating_chunk_aborts_it() {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(LayerGenerator),
+        ));
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::ONE), ChunkLoadState::Requested))
+            .id();
+        // Start the task without polling it
+        let world = app.world_mut();
+        world
+            .run_system_cached(queue_chunk_generation::<LayerGenerator>)
+            .unwrap();
+        world
+            .run_system_cached(start_chunk_generation::<LayerGenerator>)
+            .unwrap();
+        assert!(world.entity(chunk).contains::<GeneratingChunk<i32>>());
+
+        world.despawn(chunk);
+        let aborted: Vec<_> = world
+            .resource_mut::<Messages<ChunkAborted>>()
+            .drain()
+            .map(|aborted| aborted.pos)
+            .collect();
+        assert_eq!(aborted, [IVec3::ONE]);
+
+        app.update();
+        assert!(app.world().get_entity(chunk).is_err());
+    }
+
+    #[cfg(feature = "chunk_loader")]
+    #[test]
+    fn dispatch_follows_a_moving_loader() {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(LayerGenerator).with_mode(GenerationMode::Immediate),
+        ));
+        app.insert_resource(GenerationBudget {
+            max_concurrent_tasks: 1,
+            ..default()
+        });
+        let loader = app
+            .world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)))
+            .id();
+        for x in -3..=3 {
+            app.world_mut().spawn((
+                Chunk,
+                ChunkPos(IVec3::new(x, 0, 0)),
+                ChunkLoadState::Requested,
+            ));
+        }
+        let generated = |app: &mut App| {
+            app.update();
+            app.world_mut()
+                .resource_mut::<Messages<crate::ChunkReady>>()
+                .drain()
+                .map(|ready| ready.pos.x)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(generated(&mut app), [0]);
+
+        // Chunks near the new position were queued far away from the loader
+        app.world_mut().entity_mut(loader).insert((
+            Transform::from_xyz(35.0, 0.0, 0.0),
+            GlobalTransform::from_xyz(35.0, 0.0, 0.0),
+        ));
+        let order: Vec<_> = (0..6).flat_map(|_| generated(&mut app)).collect();
+        assert_eq!(order, [3, 2, 1, -1, -2, -3]);
+    }
+
+    #[cfg(feature = "chunk_loader")]
+    #[test]
+    fn the_budget_spreads_generation_over_frames_nearest_first() {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(LayerGenerator).with_mode(GenerationMode::Immediate),
+        ));
+        app.insert_resource(GenerationBudget {
+            max_apply_per_frame: 10,
+            ..default()
+        });
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)));
+        for x in (0..100).rev() {
+            app.world_mut().spawn((
+                Chunk,
+                ChunkPos(IVec3::new(x, 0, 0)),
+                ChunkLoadState::Requested,
+            ));
+        }
+
+        for frame in 0..10 {
+            app.update();
+            let mut generated: Vec<_> = app
+                .world_mut()
+                .resource_mut::<Messages<crate::ChunkReady>>()
+                .drain()
+                .map(|ready| ready.pos.x)
+                .collect();
+            generated.sort();
+            assert_eq!(generated, Vec::from_iter(frame * 10..frame * 10 + 10));
+            // Every chunk was dispatched at once, only applying is spread out
+            let in_flight = app
+                .world_mut()
+                .query::<&GeneratingChunk<i32>>()
+                .iter(app.world())
+                .count();
+            assert_eq!(in_flight, 90 - frame as usize * 10);
+        }
+        app.update();
+        assert!(
+            app.world()
+                .resource::<Messages<crate::ChunkReady>>()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn the_apply_budget_stops_at_either_limit() {
+        let start = Instant::now();
+        let long_ago = start.checked_sub(Duration::from_millis(10)).unwrap();
+        let budget = GenerationBudget {
+            max_apply_per_frame: 3,
+            max_apply_millis: 5.0,
+            ..default()
+        };
+        assert!(!budget.is_exhausted(2, start));
+        assert!(budget.is_exhausted(3, start));
+        assert!(budget.is_exhausted(0, long_ago));
+        assert!(!GenerationBudget::default().is_exhausted(usize::MAX, long_ago));
+    }
+
+    /// Continues the east border of the -X neighbor on the west border, if
+    /// that neighbor is there
+    struct BorderGenerator;
+
+    impl ChunkGenerator for BorderGenerator {
+        /// The west and east border values
+        type Output = (i32, i32);
+
+        fn generate(&self, pos: IVec3, ctx: GenerationContext) -> GenerationResult<(i32, i32)> {
+            let west = ctx
+                .neighbor_data::<(i32, i32)>(IVec3::NEG_X)
+                .map_or(-1, |(_, east)| *east);
+            Ok((west, pos.x * 10))
+        }
+    }
+
+    #[test]
+    fn generators_see_the_borders_of_generated_neighbors() {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(BorderGenerator)
+                .with_mode(GenerationMode::Immediate)
+                .with_neighbor_data(),
+        ));
+        let mut generate = |x: i32| {
+            let chunk = app
+                .world_mut()
+                .spawn((
+                    Chunk,
+                    ChunkPos(IVec3::new(x, 0, 0)),
+                    ChunkLoadState::Requested,
+                ))
+                .id();
+            app.update();
+            app.world().get::<ChunkData<(i32, i32)>>(chunk).unwrap().0
+        };
+
+        // No -X neighbor yet, then one whose data is there, then one on +X only
+        assert_eq!(generate(3), (-1, 30));
+        assert_eq!(generate(4), (30, 40));
+        assert_eq!(generate(2), (-1, 20));
+    }
+
+    /// Panics for every chunk
+    struct PanickingGenerator;
+
+    impl ChunkGenerator for PanickingGenerator {
+        type Output = u32;
+
+        fn generate(&self, _pos: IVec3, _ctx: GenerationContext) -> GenerationResult<u32> {
+            panic!("the generator broke");
+        }
+    }
+
+    #[test]
+    fn a_panicking_generator_fails_the_chunk() {
+        for mode in [GenerationMode::Immediate, GenerationMode::Async] {
+            let mut app = app((
+                ChunkyPlugin::default(),
+                ChunkGenerationPlugin::new(PanickingGenerator).with_mode(mode),
+            ));
+            app.insert_resource(GenerationRetryPolicy {
+                retries: 0,
+                backoff_frames: 0,
+            });
+            let chunk = app
+                .world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLoadState::Requested))
+                .id();
+            update_until(&mut app, |world| {
+                world.get::<ChunkLoadState>(chunk) == Some(&ChunkLoadState::Failed)
+            });
+
+            let failed: Vec<_> = app
+                .world_mut()
+                .resource_mut::<Messages<ChunkGenerationFailed>>()
+                .drain()
+                .map(|failed| failed.error.0)
+                .collect();
+            assert_eq!(failed, ["generator panicked"], "{mode:?}");
+            assert!(!app.world().entity(chunk).contains::<ChunkData<u32>>());
+        }
+    }
+
+    // The fallback of immediate_generation would block on the gate
+    #[cfg(not(feature = "immediate_generation"))]
+    #[test]
+    fn async_data_arrives_after_the_spawn_frame() {
+        use std::sync::atomic::AtomicBool;
+
+        /// Generates the x coordinate of each chunk once the gate opens
+        struct GatedGenerator(Arc<AtomicBool>);
+
+        impl ChunkGenerator for GatedGenerator {
+            type Output = i32;
+
+            fn generate(&self, pos: IVec3, _ctx: GenerationContext) -> GenerationResult<i32> {
+                while !self.0.load(atomic::Ordering::Acquire) {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                Ok(pos.x)
+            }
+        }
+
+        let gate = Arc::new(AtomicBool::new(false));
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(GatedGenerator(gate.clone())),
+        ));
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::X), ChunkLoadState::Requested))
+            .id();
+        app.update();
+        let entity = app.world().entity(chunk);
+        assert!(!entity.contains::<ChunkData<i32>>());
+        assert_eq!(
+            entity.get::<ChunkLoadState>(),
+            Some(&ChunkLoadState::Generating)
+        );
+
+        gate.store(true, atomic::Ordering::Release);
+        for _ in 0..2 {
+            std::thread::sleep(Duration::from_millis(20));
+            app.update();
+        }
+        let entity = app.world().entity(chunk);
+        assert_eq!(entity.get::<ChunkData<i32>>().map(|data| data.0), Some(1));
+        assert_eq!(entity.get::<ChunkLoadState>(), Some(&ChunkLoadState::Ready));
+    }
+
+    #[cfg(all(feature = "chunk_loader", feature = "chunk_unloader"))]
+    #[test]
+    fn chunks_pooled_while_generating_get_no_results() {
+        use crate::prelude::{
+            ChunkLoaderSettings, ChunkUnloadByDistance, ChunkUnloadRadius, PooledChunk,
+        };
+
+        /// Generates the position of each chunk
+        struct PosGenerator;
+
+        impl ChunkGenerator for PosGenerator {
+            type Output = IVec3;
+
+            fn generate(&self, pos: IVec3, _ctx: GenerationContext) -> GenerationResult<IVec3> {
+                Ok(pos)
+            }
+        }
+
+        let mut app = app((
+            ChunkyPlugin::default()
+                .with_schedule(Update)
+                .with_loader_settings(ChunkLoaderSettings {
+                    pool_size: 4,
+                    ..default()
+                }),
+            ChunkGenerationPlugin::new(PosGenerator).with_mode(GenerationMode::Immediate),
+        ));
+        app.insert_resource(ChunkUnloadByDistance)
+            .insert_resource(GenerationBudget {
+                max_apply_per_frame: 1,
+                ..default()
+            });
+        let loader = app
+            .world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::new(1, 0, 0))))
+            .id();
+        // Only the center chunk is applied, its two neighbors keep generating
+        app.update();
+        let world = app.world_mut();
+        let generating = world
+            .query_filtered::<(), crate::GeneratingChunks>()
+            .iter(world)
+            .count();
+        assert_eq!(generating, 2);
+
+        // Both neighbors are out of range in the frame one of them is applied
+        world
+            .entity_mut(loader)
+            .insert(ChunkUnloadRadius(IVec3::ZERO));
+        app.update();
+        let world = app.world_mut();
+        let pooled: Vec<_> = world
+            .query_filtered::<Entity, With<PooledChunk>>()
+            .iter(world)
+            .collect();
+        assert_eq!(pooled.len(), 2);
+        for pooled in pooled {
+            assert!(!world.entity(pooled).contains::<ChunkData<IVec3>>());
+            assert!(!world.entity(pooled).contains::<ChunkLoadState>());
+        }
+
+        // Reused entities only get the data of their new position
+        for _ in 0..3 {
+            app.update();
+        }
+        let world = app.world_mut();
+        for (ChunkPos(pos), ChunkData(data)) in
+            world.query::<(&ChunkPos, &ChunkData<IVec3>)>().iter(world)
+        {
+            assert_eq!(pos, data);
+        }
+        assert!(
+            world
+                .query_filtered::<(), (With<PooledChunk>, With<ChunkData<IVec3>>)>()
+                .iter(world)
+                .next()
+                .is_none()
+        );
+    }
+
+    /// Fails the first two attempts, counting every attempt
+    struct FlakyGenerator(Arc<AtomicU32>);
+
+    impl ChunkGenerator for FlakyGenerator {
+        type Output = u32;
+
+        fn generate(&self, _pos: IVec3, _ctx: GenerationContext) -> GenerationResult<u32> {
+            match self.0.fetch_add(1, atomic::Ordering::Relaxed) {
+                attempt @ 0..2 => Err(GenerationError::new(format!("attempt {attempt}"))),
+                attempt => Ok(attempt),
+            }
+        }
+    }
+
+    #[test]
+    fn failed_chunks_are_retried_until_ready() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(FlakyGenerator(attempts.clone()))
+                .with_mode(GenerationMode::Immediate),
+        ));
+        app.insert_resource(GenerationRetryPolicy {
+            retries: 3,
+            backoff_frames: 1,
+        });
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLoadState::Requested))
+            .id();
+
+        let mut failures = 0;
+        for _ in 0..10 {
+            app.update();
+            failures += app
+                .world_mut()
+                .resource_mut::<Messages<ChunkGenerationFailed>>()
+                .drain()
+                .count();
+        }
+
+        assert_eq!(failures, 2);
+        assert_eq!(attempts.load(atomic::Ordering::Relaxed), 3);
+        let chunk = app.world().entity(chunk);
+        assert_eq!(chunk.get::<ChunkLoadState>(), Some(&ChunkLoadState::Ready));
+        assert_eq!(chunk.get::<ChunkData<u32>>().map(|data| data.0), Some(2));
+        assert!(!chunk.contains::<ChunkGenerationRetry>());
+    }
+
+    /// A stage that leaves the payload unchanged
+    struct NoopStage;
+
+    impl GenerationStage<i32> for NoopStage {
+        fn run(&self, _data: &mut i32, _ctx: &StageContext<i32>) -> GenerationResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn the_center_chunk_reaches_the_last_stage_after_its_neighbors() {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(LayerGenerator)
+                .with_mode(GenerationMode::Immediate)
+                .with_stage(NoopStage)
+                .with_stage(NoopStage)
+                .with_stage_neighborhood(StageNeighborhood::All),
+        ));
+        let mut center = None;
+        for x in -2..=2 {
+            for y in -2..=2 {
+                for z in -2..=2 {
+                    let chunk = app
+                        .world_mut()
+                        .spawn((
+                            Chunk,
+                            ChunkPos(IVec3::new(x, y, z)),
+                            ChunkLoadState::Requested,
+                        ))
+                        .id();
+                    if (x, y, z) == (0, 0, 0) {
+                        center = Some(chunk);
+                    }
+                }
+            }
+        }
+
+        let mut completed = Vec::new();
+        for _ in 0..5 {
+            app.update();
+            completed.extend(
+                app.world_mut()
+                    .resource_mut::<Messages<ChunkStageCompleted>>()
+                    .drain()
+                    .filter(|completed| completed.stage > 0)
+                    .map(|completed| (completed.pos, completed.stage)),
+            );
+        }
+
+        // Stage 1 needs all 26 neighbors, so only the inner 3x3x3 chunks run it
+        let (last, earlier) = completed.split_last().unwrap();
+        assert_eq!(*last, (IVec3::ZERO, 2));
+        assert_eq!(earlier.len(), 27);
+        assert!(
+            earlier
+                .iter()
+                .all(|(pos, stage)| { *stage == 1 && pos.abs().max_element() <= 1 })
+        );
+        let center = app.world().entity(center.unwrap());
+        assert_eq!(center.get::<ChunkLoadState>(), Some(&ChunkLoadState::Ready));
+        assert_eq!(center.get::<ChunkStage>(), Some(&ChunkStage(2)));
+    }
+}
@@ -1,12 +1,65 @@
-use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
 
-use crate::{Chunk, ChunkManager, ChunkPos};
-pub struct ChunkLoaderPlugin;
+use bevy::{
+    ecs::{lifecycle::HookContext, schedule::InternedScheduleLabel, world::DeferredWorld},
+    prelude::*,
+};
+
+use crate::{
+    Chunk, ChunkLayer, ChunkLoadState, ChunkManager, ChunkPos, ChunkySystems, RejectedChunk,
+    helpers::line_chunk_positions,
+};
+
+#[cfg(feature = "chunk_generation")]
+use crate::chunk_generation::GeneratorRegistered;
+pub struct ChunkLoaderPlugin {
+    pub continuous: bool,
+    pub schedule: InternedScheduleLabel,
+}
 impl Plugin for ChunkLoaderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, chunk_loader);
+        app.add_message::<ChunkLoadTrigger>()
+            .add_message::<ChunkRegionSettled>()
+            .init_resource::<ChunkLoaderSettings>()
+            .init_resource::<UnloadCooldowns>()
+            .init_resource::<ChunkPool>()
+            .init_resource::<LoaderReport>()
+            .add_observer(start_unload_cooldown)
+            .add_systems(
+                self.schedule,
+                (
+                    update_loader_report.in_set(ChunkySystems::Load),
+                    // Generation only runs in `Update`, so elsewhere this
+                    // sees the results of the last frame
+                    send_region_settled
+                        .after(ChunkySystems::Load)
+                        .after(ChunkySystems::PostGenerate),
+                    warn_soft_limit
+                        .after(ChunkySystems::Load)
+                        .after(ChunkySystems::PostGenerate),
+                ),
+            );
+        // Cooldowns count load passes, so they tick only when the loader runs
+        let load_pass = (tick_unload_cooldowns, chunk_loader)
+            .chain()
+            .in_set(ChunkySystems::Load);
+        if self.continuous {
+            app.add_systems(self.schedule, load_pass);
+        } else {
+            app.add_systems(
+                self.schedule,
+                load_pass.run_if(on_message::<ChunkLoadTrigger>),
+            );
+        }
         #[cfg(feature = "reflect")]
-        app.register_type::<ChunkLoader>();
+        app.register_type::<ChunkLoader>()
+            .register_type::<ChunkLoaderDisabled>()
+            .register_type::<ChunkLoaderSweep>()
+            .register_type::<ChunkLoaderInterval>()
+            .register_type::<PooledChunk>()
+            .register_type::<ChunkLoaderSettings>()
+            .register_type::<ChunkLoaderStats>()
+            .register_type::<LoaderReport>();
     }
 }
 /// Automatically loads chunks around the entity.
@@ -40,29 +93,1204 @@ impl Plugin for ChunkLoaderPlugin {
 ///     ));
 /// }
 /// ```
-#[derive(Component, Default, Debug)]
+#[derive(Component, Default, Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[require(ChunkLoaderStats)]
 pub struct ChunkLoader(pub IVec3);
 
+/// What a [`ChunkLoader`] is doing, added to every loader.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn log_player_loader(player: Single<&ChunkLoaderStats>) {
+///     info!("{} new chunks around {}", player.spawned_last_pass, player.center_chunk);
+/// }
+/// ```
+#[derive(Component, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkLoaderStats {
+    /// The chunk the loader is in, updated whenever the loaders run
+    pub center_chunk: IVec3,
+    /// Chunks the loader spawned in its last load pass, counting each chunk
+    /// only for the first loader that wanted it.
+    ///
+    /// Kept until the loader runs its next pass, so between the passes of a
+    /// [`ChunkLoaderInterval`] and while [`ChunkLoaderDisabled`] it still
+    /// holds the count of the last pass the loader ran.
+    pub spawned_last_pass: usize,
+}
+
+/// Temporarily stops a [`ChunkLoader`] from loading chunks.
+///
+/// Cheaper and clearer than removing and re-inserting the loader, e.g. for a
+/// paused NPC. Disabled loaders still count for distance-based unloading, so
+/// the chunks around them stay loaded.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn pause_npc(mut commands: Commands, npc: Single<Entity, With<ChunkLoader>>) {
+///     commands.entity(*npc).insert(ChunkLoaderDisabled);
+/// }
+/// ```
+#[derive(Component, Default, Debug, Clone, Copy)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkLoaderDisabled;
+
+/// Makes a [`ChunkLoader`] load every chunk it passed since the last load
+/// pass, not only the ones around its current position.
+///
+/// When a fast loader moves several chunks in one frame, the regions around
+/// the chunks on the line from its previous to its current chunk are loaded
+/// as well, so aggressive unloading leaves no holes along its path.
+///
+/// Only the last [`MAX_TRAIL`](Self::MAX_TRAIL) chunks of the line are swept,
+/// so a loader teleported across the map doesn't load every region in
+/// between in a single pass.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn spawn_train(mut commands: Commands) {
+///     commands.spawn((Transform::default(), ChunkLoader(IVec3::ONE), ChunkLoaderSweep));
+/// }
+/// ```
+#[derive(Component, Default, Debug, Clone, Copy)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkLoaderSweep;
+
+impl ChunkLoaderSweep {
+    /// The most chunks of a loader's path, counted back from its current
+    /// chunk, that one load pass loads the regions around
+    pub const MAX_TRAIL: usize = 16;
+}
+
+/// Makes a [`ChunkLoader`] load chunks only each time the timer finishes,
+/// instead of every load pass.
+///
+/// Saves work for loaders that don't need to keep up closely, e.g. distant
+/// NPCs. The first pass after the interval is added loads right away, later
+/// ones wait for the timer. The timer is ticked by the loader system, so with
+/// [`continuous`](crate::ChunkyPlugin::with_continuous_loading) loading off it
+/// only advances on passes sent by [`ChunkLoadTrigger`]. Use a repeating timer,
+/// as a finished one-shot timer never fires again.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn spawn_villager(mut commands: Commands) {
+///     commands.spawn((
+///         Transform::default(),
+///         ChunkLoader(IVec3::ONE),
+///         // Catch up with the villager's position once per second
+///         ChunkLoaderInterval::from_seconds(1.0),
+///     ));
+/// }
+/// ```
+#[derive(Component, Debug, Clone)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+#[component(on_add = on_add_loader_interval)]
+pub struct ChunkLoaderInterval(pub Timer);
+
+impl ChunkLoaderInterval {
+    /// Loads right away, then every `seconds`
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, TimerMode::Repeating))
+    }
+}
+
+/// Runs out the timer of a new interval, so its loader doesn't wait a full
+/// interval before loading anything
+fn on_add_loader_interval(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let mut interval = world.get_mut::<ChunkLoaderInterval>(entity).unwrap();
+    let duration = interval.0.duration();
+    interval.0.set_elapsed(duration);
+}
+
+/// Settings of the [`ChunkLoader`]s.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugins(ChunkyPlugin::default())
+///     .insert_resource(ChunkLoaderSettings {
+///         unload_cooldown_frames: 5,
+///         ..default()
+///     });
+/// ```
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkLoaderSettings {
+    /// Number of load passes after a chunk is despawned during which loaders
+    /// won't spawn it again.
+    ///
+    /// Keeps a chunk at the edge of a loader's radius from being unloaded and
+    /// loaded every frame while the loader moves back and forth. A pass runs
+    /// every frame with continuous loading, otherwise once per
+    /// [`ChunkLoadTrigger`]. Passes count even for loaders waiting for their
+    /// [`ChunkLoaderInterval`]. Chunks spawned directly are not affected.
+    pub unload_cooldown_frames: u32,
+    /// Number of unloaded chunk entities kept hidden as [`PooledChunk`]s for
+    /// loaders to reuse, instead of despawning them and spawning new ones.
+    ///
+    /// Saves entity churn when chunks cycle rapidly at a loader's boundary.
+    /// Only chunks unloaded by the `chunk_unloader` are pooled. `0` turns
+    /// pooling off (default).
+    pub pool_size: usize,
+    /// Number of loaded chunks above which a warning is logged, as a heads-up
+    /// that loaders are too aggressive.
+    ///
+    /// The warning is logged once each time the number of chunks in the
+    /// [`ChunkManager`] rises above it, at most once per second. It is checked
+    /// whenever the loaders run. A crossing
+    /// within that second is warned about once it is up, if the number of
+    /// chunks is still above the limit. Set it below
+    /// the `max_chunks` of the `ChunkUnloadLimit` to hear about it before
+    /// chunks get evicted. `0` turns the warning off (default).
+    pub soft_limit: usize,
+}
+
+/// An unloaded chunk entity waiting to be reused by a [`ChunkLoader`], see
+/// [`ChunkLoaderSettings::pool_size`].
+///
+/// Pooled entities are hidden and keep only their transform, visibility and
+/// parent, so none of the chunk components or the [`ChunkManager`] see them.
+/// Their children, e.g. meshes or labels, are despawned when they are pooled.
+/// Despawning one simply takes it out of the pool.
+#[derive(Component, Default, Debug, Clone, Copy)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct PooledChunk;
+
+/// Unloaded chunk entities kept for reuse, most recently pooled last
+#[derive(Resource, Default)]
+struct ChunkPool(Vec<Entity>);
+
+/// Moves an unloaded chunk into the [`ChunkPool`] if it has room, despawning
+/// it otherwise.
+///
+/// Removing the chunk's other components fires the same hooks and observers
+/// as despawning it, e.g. unregistering it and saving its data.
+#[cfg(feature = "chunk_unloader")]
+pub(crate) fn pool_or_despawn(world: &mut World, entity: Entity) {
+    let pool_size = world
+        .get_resource::<ChunkLoaderSettings>()
+        .map_or(0, |settings| settings.pool_size);
+    world.resource_scope(|world, mut pool: Mut<ChunkPool>| {
+        // Entities despawned while pooled leave room for others
+        pool.0
+            .retain(|&pooled| world.get::<PooledChunk>(pooled).is_some());
+        let Ok(mut chunk) = world.get_entity_mut(entity) else {
+            return;
+        };
+        if pool.0.len() >= pool_size {
+            chunk.despawn();
+            return;
+        }
+        // Children would otherwise follow the entity to its next position
+        chunk
+            .despawn_related::<Children>()
+            .retain::<(
+                Transform,
+                GlobalTransform,
+                Visibility,
+                InheritedVisibility,
+                ViewVisibility,
+                ChildOf,
+            )>()
+            .insert((PooledChunk, Visibility::Hidden));
+        pool.0.push(entity);
+    });
+}
+
+/// The chunk and radius of every [`ChunkLoader`], updated whenever the loaders
+/// run, every frame unless set otherwise with
+/// [`ChunkyPlugin::with_schedule`](crate::ChunkyPlugin::with_schedule).
+///
+/// Saves e.g. a minimap from querying the loaders and converting their
+/// positions to chunks itself.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn draw_minimap(report: Res<LoaderReport>) {
+///     for loader in report.loaders.iter().filter(|loader| !loader.disabled) {
+///         info!("{} loads {} around {}", loader.entity, loader.radius, loader.center_chunk);
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct LoaderReport {
+    /// One entry per loader with a [`GlobalTransform`], disabled ones
+    /// included, in no particular order
+    pub loaders: Vec<LoaderRegion>,
+}
+
+/// A loader in the [`LoaderReport`].
+///
+/// There is no shape to report, as loaders always load the box of chunks
+/// within `radius` of `center_chunk` on each axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct LoaderRegion {
+    /// The loader entity
+    pub entity: Entity,
+    /// The chunk the loader is in
+    pub center_chunk: IVec3,
+    /// See [`ChunkLoader`]
+    pub radius: IVec3,
+    /// Whether the loader has [`ChunkLoaderDisabled`]
+    pub disabled: bool,
+}
+
+/// Layers and positions of recently despawned chunks, with the frames left
+/// until loaders may spawn them again
+#[derive(Resource, Default)]
+struct UnloadCooldowns(HashMap<(i32, IVec3), u32>);
+
+/// Runs one load pass for all loaders.
+///
+/// Only needed when continuous loading is turned off with
+/// [`ChunkyPlugin::with_continuous_loading`](crate::ChunkyPlugin::with_continuous_loading),
+/// e.g. in a turn-based game where the world only changes on a move.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn end_turn(mut trigger: MessageWriter<ChunkLoadTrigger>) {
+///     trigger.write(ChunkLoadTrigger);
+/// }
+/// ```
+#[derive(Message, Debug, Clone, Copy, Default)]
+pub struct ChunkLoadTrigger;
+
+/// Fired once streaming caught up: every chunk within the radius of each
+/// enabled [`ChunkLoader`] is registered and none of them is still
+/// `Requested` or `Generating`.
+///
+/// Fires once, and again after new chunks were requested, e.g. because a
+/// loader moved, and those are loaded as well. Without a
+/// `ChunkGenerationPlugin`, chunks stay `Requested`, so then every registered
+/// chunk counts as loaded.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn hide_loading_spinner(mut settled: MessageReader<ChunkRegionSettled>) {
+///     if settled.read().next().is_some() {
+///         info!("All chunks around the loaders are loaded");
+///     }
+/// }
+/// ```
+#[derive(Message, Debug, Clone, Copy, Default)]
+pub struct ChunkRegionSettled;
+
 /// Load Chunks Around ChunkLoader
 fn chunk_loader(
-    chunks: Query<(&ChunkLoader, &GlobalTransform)>,
+    mut chunks: Query<
+        (
+            Entity,
+            &ChunkLoader,
+            &GlobalTransform,
+            &mut ChunkLoaderStats,
+            Has<ChunkLoaderSweep>,
+            Option<&mut ChunkLoaderInterval>,
+        ),
+        Without<ChunkLoaderDisabled>,
+    >,
     chunk_manager: Res<ChunkManager>,
+    time: Res<Time>,
+    cooldowns: Res<UnloadCooldowns>,
+    mut pool: ResMut<ChunkPool>,
+    pooled: Query<(), With<PooledChunk>>,
     mut commands: Commands,
+    // The chunk of every sweeping loader at the last load pass
+    mut swept_from: Local<HashMap<Entity, IVec3>>,
 ) {
-    for (ChunkLoader(loading_radius), g_transform) in chunks.iter() {
-        let translation = g_transform.translation();
-        let in_chunk = chunk_manager.get_chunk_pos(&translation);
-        for x in -loading_radius.x..=loading_radius.x {
-            for y in -loading_radius.y..=loading_radius.y {
-                for z in -loading_radius.z..=loading_radius.z {
-                    let target_chunk = in_chunk + ivec3(x, y, z);
-                    if !chunk_manager.is_loaded(&target_chunk) {
-                        commands.spawn((Chunk, ChunkPos(target_chunk)));
+    // Chunks spawned in this pass, as the ChunkManager only sees them later
+    let mut spawned = HashSet::new();
+    // Returns how many chunks were spawned
+    let mut load_around = |center: IVec3, loading_radius: IVec3| {
+        let spawned_before = spawned.len();
+        for target_chunk in chunks_to_load(center, loading_radius, &chunk_manager) {
+            // Loaders only spawn chunks on the default layer
+            if !cooldowns.0.contains_key(&(0, target_chunk)) && spawned.insert(target_chunk) {
+                let chunk = (Chunk, ChunkPos(target_chunk), ChunkLoadState::Requested);
+                // Entities despawned while pooled are skipped
+                let reused =
+                    std::iter::from_fn(|| pool.0.pop()).find(|&entity| pooled.contains(entity));
+                match reused {
+                    Some(entity) => {
+                        commands
+                            .entity(entity)
+                            .remove::<PooledChunk>()
+                            .insert((chunk, Visibility::Inherited));
+                    }
+                    None => {
+                        commands.spawn(chunk);
                     }
                 }
             }
         }
+        spawned.len() - spawned_before
+    };
+
+    swept_from.retain(|entity, _| chunks.get(*entity).is_ok_and(|(.., sweep, _)| sweep));
+    for (entity, ChunkLoader(loading_radius), g_transform, mut stats, sweep, interval) in
+        chunks.iter_mut()
+    {
+        if let Some(mut interval) = interval
+            && !interval.0.tick(time.delta()).just_finished()
+        {
+            continue;
+        }
+        let translation = g_transform.translation();
+        let in_chunk = chunk_manager.get_chunk_pos(&translation);
+        stats.spawned_last_pass = if sweep {
+            let from = swept_from.insert(entity, in_chunk).unwrap_or(in_chunk);
+            let path = line_chunk_positions(from, in_chunk);
+            let skipped = path.len().saturating_sub(ChunkLoaderSweep::MAX_TRAIL);
+            path[skipped..]
+                .iter()
+                .map(|center| load_around(*center, *loading_radius))
+                .sum()
+        } else {
+            load_around(in_chunk, *loading_radius)
+        };
+    }
+}
+
+/// The chunk positions a loader in chunk `in_chunk` would spawn: the ones in
+/// the box reaching `radius` chunks out along each axis that aren't loaded
+/// yet, in the order they are spawned.
+///
+/// Chunks still in their unload cooldown and chunks spawned by other loaders
+/// in the same pass are left to the loader system.
+pub(crate) fn chunks_to_load(
+    in_chunk: IVec3,
+    radius: IVec3,
+    chunk_manager: &ChunkManager,
+) -> Vec<IVec3> {
+    loader_region(in_chunk, radius)
+        .filter(|chunk_pos| !chunk_manager.is_loaded(chunk_pos))
+        .collect()
+}
+
+/// Every chunk position in the box of a loader in chunk `in_chunk`
+fn loader_region(in_chunk: IVec3, radius: IVec3) -> impl Iterator<Item = IVec3> {
+    (-radius.x..=radius.x).flat_map(move |x| {
+        (-radius.y..=radius.y)
+            .flat_map(move |y| (-radius.z..=radius.z).map(move |z| in_chunk + ivec3(x, y, z)))
+    })
+}
+
+/// Warns when the number of loaded chunks rises above the
+/// [`soft_limit`](ChunkLoaderSettings::soft_limit), throttled to once per
+/// second
+fn warn_soft_limit(
+    chunk_manager: Res<ChunkManager>,
+    settings: Res<ChunkLoaderSettings>,
+    time: Res<Time>,
+    // Whether the chunk count stayed above the limit since the last warning
+    mut warned: Local<bool>,
+    mut last_warning: Local<Option<f32>>,
+) {
+    let limit = settings.soft_limit;
+    if limit == 0 || chunk_manager.len() <= limit {
+        *warned = false;
+        return;
+    }
+    let now = time.elapsed_secs();
+    // A throttled crossing stays pending until the second is up
+    if *warned || last_warning.is_some_and(|last_warning| now - last_warning < 1.0) {
+        return;
+    }
+    *warned = true;
+    *last_warning = Some(now);
+    warn!(
+        "{} chunks are loaded, above the soft limit of {}",
+        chunk_manager.len(),
+        limit
+    );
+}
+
+/// Sends [`ChunkRegionSettled`] when the regions of all loaders become
+/// loaded, re-arming when a loader changes region or any of them is not.
+///
+/// The regions are only checked again once a loader changed chunks or radius,
+/// or chunks were registered, unregistered or changed their load state.
+fn send_region_settled(
+    loaders: Query<(&ChunkLoader, &GlobalTransform), Without<ChunkLoaderDisabled>>,
+    chunk_manager: Res<ChunkManager>,
+    #[cfg(feature = "chunk_generation")] generator: Option<Res<GeneratorRegistered>>,
+    load_states: Query<&ChunkLoadState>,
+    changed_load_states: Query<(), Changed<ChunkLoadState>>,
+    mut settled: MessageWriter<ChunkRegionSettled>,
+    // The chunk and radius of every loader at the last check
+    mut checked: Local<Vec<(IVec3, IVec3)>>,
+    mut sent: Local<bool>,
+) {
+    // Load states only move on with a generator registered
+    #[cfg(feature = "chunk_generation")]
+    let generating = generator.is_some();
+    #[cfg(not(feature = "chunk_generation"))]
+    let generating = false;
+    let regions: Vec<_> = loaders
+        .iter()
+        .map(|(ChunkLoader(radius), transform)| {
+            (
+                chunk_manager.get_chunk_pos(&transform.translation()),
+                *radius,
+            )
+        })
+        .collect();
+    if regions == *checked
+        && !chunk_manager.is_changed()
+        && (!generating || changed_load_states.is_empty())
+    {
+        return;
+    }
+    if regions != *checked {
+        // A loader moved, so its new chunks settle again
+        *sent = false;
+        *checked = regions;
+    }
+    if checked.is_empty() {
+        return;
+    }
+    let loaded = |pos: IVec3| match chunk_manager.get_chunk(&pos) {
+        None => false,
+        Some(entity) => {
+            !generating
+                || !load_states.get(entity).is_ok_and(|state| {
+                    matches!(
+                        state,
+                        ChunkLoadState::Requested | ChunkLoadState::Generating
+                    )
+                })
+        }
+    };
+    let all_loaded = checked
+        .iter()
+        .all(|(center, radius)| loader_region(*center, *radius).all(loaded));
+    if !all_loaded {
+        *sent = false;
+    } else if !*sent {
+        *sent = true;
+        settled.write(ChunkRegionSettled);
+    }
+}
+
+/// Refills the LoaderReport from the current loaders and updates their
+/// chunks in the ChunkLoaderStats
+fn update_loader_report(
+    mut loaders: Query<(
+        Entity,
+        &ChunkLoader,
+        &GlobalTransform,
+        Has<ChunkLoaderDisabled>,
+        &mut ChunkLoaderStats,
+    )>,
+    chunk_manager: Res<ChunkManager>,
+    mut report: ResMut<LoaderReport>,
+) {
+    report.loaders.clear();
+    report.loaders.extend(loaders.iter_mut().map(
+        |(entity, ChunkLoader(radius), transform, disabled, mut stats)| {
+            let center_chunk = chunk_manager.get_chunk_pos(&transform.translation());
+            if stats.center_chunk != center_chunk {
+                stats.center_chunk = center_chunk;
+            }
+            LoaderRegion {
+                entity,
+                center_chunk,
+                radius: *radius,
+                disabled,
+            }
+        },
+    ));
+}
+
+/// Starts the cooldown of a despawned chunk's position on its layer.
+///
+/// Chunks rejected by the collision policy never held their position, so they
+/// don't start one.
+fn start_unload_cooldown(
+    remove: On<Remove, ChunkPos>,
+    settings: Res<ChunkLoaderSettings>,
+    chunks: Query<(&ChunkPos, Option<&ChunkLayer>), Without<RejectedChunk>>,
+    mut cooldowns: ResMut<UnloadCooldowns>,
+) {
+    if settings.unload_cooldown_frames == 0 {
+        return;
+    }
+    if let Ok((ChunkPos(pos), layer)) = chunks.get(remove.entity) {
+        let layer = layer.map_or(0, |layer| layer.0);
+        cooldowns
+            .0
+            .insert((layer, *pos), settings.unload_cooldown_frames);
+    }
+}
+
+/// Counts down the cooldowns, once per load pass
+fn tick_unload_cooldowns(mut cooldowns: ResMut<UnloadCooldowns>) {
+    cooldowns.0.retain(|_, frames| {
+        let cooling = *frames > 0;
+        *frames = frames.saturating_sub(1);
+        cooling
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_zero_radius_loads_the_loader_chunk() {
+        let chunk_manager = ChunkManager::new(Vec3::ONE);
+        let in_chunk = IVec3::new(4, -2, 7);
+        assert_eq!(
+            chunks_to_load(in_chunk, IVec3::ZERO, &chunk_manager),
+            [in_chunk]
+        );
+    }
+
+    #[test]
+    fn each_axis_has_its_own_radius() {
+        let chunk_manager = ChunkManager::new(Vec3::ONE);
+        let chunks = chunks_to_load(IVec3::ONE, IVec3::new(2, 0, 1), &chunk_manager);
+        assert_eq!(chunks.len(), 5 * 3);
+        assert!(chunks.iter().all(|chunk_pos| chunk_pos.y == 1));
+        assert!(chunks.contains(&IVec3::new(3, 1, 2)));
+        assert!(chunks.contains(&IVec3::new(-1, 1, 0)));
+        assert!(!chunks.contains(&IVec3::new(1, 1, 3)));
+    }
+
+    #[test]
+    fn a_z_up_flat_loader_loads_the_x_y_plane() {
+        let mut app =
+            crate::tests::app(crate::ChunkyPlugin::default().with_up_axis(crate::ChunkUpAxis::Z));
+        app.world_mut().spawn((
+            Transform::from_xyz(5.0, 5.0, 35.0),
+            ChunkLoader(crate::ChunkUpAxis::Z.flat(2)),
+        ));
+        app.update();
+
+        let chunk_manager = app.world().resource::<ChunkManager>();
+        assert_eq!(chunk_manager.len(), 5 * 5);
+        for x in -2..=2 {
+            for y in -2..=2 {
+                assert!(chunk_manager.is_loaded(&IVec3::new(x, y, 3)));
+            }
+        }
+    }
+
+    #[test]
+    fn loaded_chunks_are_skipped() {
+        let mut chunk_manager = ChunkManager::new(Vec3::ONE);
+        chunk_manager.insert(IVec3::ZERO, Entity::from_raw_u32(1).unwrap());
+        chunk_manager.insert(IVec3::X, Entity::from_raw_u32(2).unwrap());
+        let chunks = chunks_to_load(IVec3::ZERO, IVec3::ONE, &chunk_manager);
+        assert_eq!(chunks.len(), 27 - 2);
+        assert!(!chunks.contains(&IVec3::ZERO));
+        assert!(!chunks.contains(&IVec3::X));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn loader_types_round_trip_through_json() {
+        use crate::tests::json_round_trip;
+
+        let loader = ChunkLoader(IVec3::new(4, 1, 4));
+        assert_eq!(json_round_trip(&loader), loader);
+        let settings = ChunkLoaderSettings {
+            unload_cooldown_frames: 5,
+            pool_size: 64,
+            soft_limit: 10_000,
+        };
+        assert_eq!(json_round_trip(&settings), settings);
+    }
+
+    #[test]
+    fn the_loader_report_follows_loaders() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        let loader = app
+            .world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::new(2, 0, 2))))
+            .id();
+        app.update();
+        let report = app.world().resource::<LoaderReport>();
+        assert_eq!(
+            report.loaders,
+            [LoaderRegion {
+                entity: loader,
+                center_chunk: IVec3::ZERO,
+                radius: IVec3::new(2, 0, 2),
+                disabled: false,
+            }]
+        );
+
+        app.world_mut().entity_mut(loader).insert((
+            Transform::from_xyz(-30.0, 0.0, 45.0),
+            GlobalTransform::from_xyz(-30.0, 0.0, 45.0),
+            ChunkLoaderDisabled,
+        ));
+        app.update();
+        let report = app.world().resource::<LoaderReport>();
+        assert_eq!(report.loaders[0].center_chunk, IVec3::new(-3, 0, 4));
+        assert!(report.loaders[0].disabled);
+    }
+
+    #[test]
+    fn disabled_loaders_load_nothing() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        app.world_mut().spawn((
+            Transform::from_xyz(30.0, 0.0, 0.0),
+            ChunkLoader(IVec3::new(1, 0, 1)),
+            ChunkLoaderDisabled,
+        ));
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::new(1, 0, 1))));
+        app.update();
+
+        let chunks = app.world().resource::<ChunkManager>();
+        assert_eq!(chunks.len(), 9);
+        assert!(chunks.iter().all(|(pos, _)| pos.x.abs() <= 1));
+    }
+
+    #[test]
+    fn loader_stats_count_the_chunks_of_each_pass() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        let loader = app
+            .world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::new(1, 0, 1))))
+            .id();
+        let stats = |app: &App| *app.world().get::<ChunkLoaderStats>(loader).unwrap();
+        app.update();
+        assert_eq!(
+            stats(&app),
+            ChunkLoaderStats {
+                center_chunk: IVec3::ZERO,
+                spawned_last_pass: 9,
+            }
+        );
+
+        app.update();
+        assert_eq!(stats(&app).spawned_last_pass, 0);
+
+        app.world_mut().entity_mut(loader).insert((
+            Transform::from_xyz(10.0, 0.0, 0.0),
+            GlobalTransform::from_xyz(10.0, 0.0, 0.0),
+        ));
+        app.update();
+        assert_eq!(
+            stats(&app),
+            ChunkLoaderStats {
+                center_chunk: IVec3::X,
+                spawned_last_pass: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn interval_loaders_load_right_away_then_once_per_interval() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        app.insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            std::time::Duration::from_millis(100),
+        ));
+        let loader = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                ChunkLoader(IVec3::ZERO),
+                ChunkLoaderInterval::from_seconds(1.0),
+            ))
+            .id();
+        app.update();
+        assert!(
+            app.world()
+                .resource::<ChunkManager>()
+                .is_loaded(&IVec3::ZERO)
+        );
+
+        app.world_mut().entity_mut(loader).insert((
+            Transform::from_xyz(10.0, 0.0, 0.0),
+            GlobalTransform::from_xyz(10.0, 0.0, 0.0),
+        ));
+        for frame in 1..=5 {
+            app.update();
+            assert_eq!(
+                app.world().resource::<ChunkManager>().len(),
+                1,
+                "frame {frame}"
+            );
+            // Skipped frames keep the count of the first pass
+            let stats = app.world().get::<ChunkLoaderStats>(loader).unwrap();
+            assert_eq!(stats.spawned_last_pass, 1, "frame {frame}");
+        }
+        for _ in 0..10 {
+            app.update();
+        }
+        assert!(app.world().resource::<ChunkManager>().is_loaded(&IVec3::X));
+    }
+
+    #[test]
+    fn without_continuous_loading_only_triggers_load() {
+        let mut app =
+            crate::tests::app(crate::ChunkyPlugin::default().with_continuous_loading(false));
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::new(1, 0, 1))));
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(app.world().resource::<ChunkManager>().is_empty());
+
+        app.world_mut().write_message(ChunkLoadTrigger);
+        app.update();
+        assert_eq!(app.world().resource::<ChunkManager>().len(), 9);
+    }
+
+    #[cfg(feature = "chunk_unloader")]
+    #[test]
+    fn unloaded_chunks_are_reused_from_the_pool() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default().with_loader_settings(
+            ChunkLoaderSettings {
+                pool_size: 4,
+                ..default()
+            },
+        ));
+        app.insert_resource(crate::prelude::ChunkUnloadByDistance);
+        let far = IVec3::new(10, 0, 0);
+        let loader = app
+            .world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)))
+            .id();
+        let move_loader = |app: &mut App, x: f32| {
+            app.world_mut().entity_mut(loader).insert((
+                Transform::from_xyz(x, 0.0, 0.0),
+                GlobalTransform::from_xyz(x, 0.0, 0.0),
+            ));
+            app.update();
+        };
+        app.update();
+        let first = app
+            .world()
+            .resource::<ChunkManager>()
+            .get_chunk(&IVec3::ZERO)
+            .unwrap();
+        let child = app.world_mut().spawn(ChildOf(first)).id();
+
+        move_loader(&mut app, 100.0);
+        let second = app
+            .world()
+            .resource::<ChunkManager>()
+            .get_chunk(&far)
+            .unwrap();
+        assert_ne!(second, first);
+        assert!(app.world().get::<PooledChunk>(first).is_some());
+        assert!(app.world().get_entity(child).is_err());
+
+        move_loader(&mut app, 0.0);
+        let chunk_manager = app.world().resource::<ChunkManager>();
+        assert_eq!(chunk_manager.get_chunk(&IVec3::ZERO), Some(first));
+        assert_eq!(chunk_manager.len(), 1);
+        assert!(app.world().get::<PooledChunk>(first).is_none());
+        assert!(app.world().get::<PooledChunk>(second).is_some());
+
+        move_loader(&mut app, 100.0);
+        let chunk_manager = app.world().resource::<ChunkManager>();
+        assert_eq!(chunk_manager.get_chunk(&far), Some(second));
+    }
+
+    #[cfg(feature = "chunk_unloader")]
+    #[test]
+    fn sweeping_loaders_load_along_their_path() {
+        #[derive(Resource, Default)]
+        struct Spawned(Vec<IVec3>);
+
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        app.insert_resource(crate::prelude::ChunkUnloadByDistance)
+            .init_resource::<Spawned>()
+            .add_observer(
+                |add: On<Add, Chunk>, chunks: Query<&ChunkPos>, mut spawned: ResMut<Spawned>| {
+                    spawned.0.push(chunks.get(add.entity).unwrap().0);
+                },
+            );
+        let loader = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                ChunkLoader(IVec3::ZERO),
+                ChunkLoaderSweep,
+            ))
+            .id();
+        let teleport = |app: &mut App, x: f32| {
+            app.world_mut().entity_mut(loader).insert((
+                Transform::from_xyz(x, 0.0, 0.0),
+                GlobalTransform::from_xyz(x, 0.0, 0.0),
+            ));
+            app.update();
+            std::mem::take(&mut app.world_mut().resource_mut::<Spawned>().0)
+        };
+        assert_eq!(teleport(&mut app, 5.0), [IVec3::ZERO]);
+
+        assert_eq!(
+            teleport(&mut app, 55.0),
+            (1..=5).map(|x| IVec3::X * x).collect::<Vec<_>>()
+        );
+        // Only the chunk the loader ended up in stays loaded
+        let chunk_manager = app.world().resource::<ChunkManager>();
+        assert_eq!(chunk_manager.len(), 1);
+        assert!(chunk_manager.is_loaded(&IVec3::new(5, 0, 0)));
+
+        // A teleport across the map only sweeps the end of the path
+        let trail = ChunkLoaderSweep::MAX_TRAIL as i32;
+        let spawned = teleport(&mut app, 1005.0);
+        assert_eq!(
+            spawned,
+            (101 - trail..=100)
+                .map(|x| IVec3::X * x)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn moving_a_loader_settles_its_region_again() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        let loader = app
+            .world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::new(1, 0, 0))))
+            .id();
+        let settled = |app: &mut App| {
+            app.update();
+            app.world_mut()
+                .resource_mut::<Messages<ChunkRegionSettled>>()
+                .drain()
+                .count()
+        };
+        assert_eq!(settled(&mut app), 1);
+        assert_eq!(settled(&mut app), 0);
+
+        app.world_mut()
+            .entity_mut(loader)
+            .insert(Transform::from_xyz(50.0, 0.0, 0.0));
+        // The move reaches the `GlobalTransform` one frame later
+        assert_eq!(settled(&mut app) + settled(&mut app), 1);
+        assert_eq!(settled(&mut app), 0);
+        assert!(
+            app.world()
+                .resource::<ChunkManager>()
+                .is_loaded(&IVec3::new(6, 0, 0))
+        );
+    }
+
+    #[test]
+    fn systems_after_load_see_the_chunks_spawned_that_frame() {
+        #[derive(Resource, Default)]
+        struct Seen(Vec<(usize, usize)>);
+
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        app.init_resource::<Seen>().add_systems(
+            Update,
+            (|chunks: Query<(), With<Chunk>>,
+              chunk_manager: Res<ChunkManager>,
+              mut seen: ResMut<Seen>| {
+                seen.0.push((chunks.iter().count(), chunk_manager.len()));
+            })
+            .after(ChunkySystems::Load),
+        );
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ONE)));
+        app.update();
+
+        assert_eq!(app.world().resource::<Seen>().0, [(27, 27)]);
+    }
+
+    #[test]
+    fn a_shifted_origin_keeps_loaders_in_their_chunk() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        let loader = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(25.0, 5.0, 5.0),
+                ChunkLoader(IVec3::ZERO),
+            ))
+            .id();
+        app.update();
+        let chunk = app
+            .world()
+            .resource::<ChunkManager>()
+            .get_chunk(&(IVec3::X * 2))
+            .unwrap();
+        app.world_mut().despawn(chunk);
+
+        // Shift the world and the loader, whose global transform would only
+        // follow in PostUpdate, and respawn the chunk
+        let delta = Vec3::new(-1000.0, 0.0, 500.0);
+        app.world_mut()
+            .resource_mut::<ChunkManager>()
+            .set_world_offset(delta);
+        let moved = Transform::from_translation(Vec3::new(25.0, 5.0, 5.0) + delta);
+        app.world_mut()
+            .entity_mut(loader)
+            .insert((moved, GlobalTransform::from(moved)));
+        app.update();
+
+        let chunk_manager = app.world().resource::<ChunkManager>();
+        assert_eq!(
+            chunk_manager.get_chunk_pos(&(Vec3::new(25.0, 5.0, 5.0) + delta)),
+            IVec3::X * 2
+        );
+        assert_eq!(chunk_manager.len(), 1);
+        let chunk = chunk_manager.get_chunk(&(IVec3::X * 2)).unwrap();
+        assert_eq!(
+            app.world().get::<Transform>(chunk).unwrap().translation,
+            Vec3::new(20.0, 0.0, 0.0) + delta
+        );
+    }
+
+    #[test]
+    fn the_report_and_settling_follow_the_loader_schedule() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default().with_schedule(PreUpdate));
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)));
+        app.world_mut().run_schedule(PreUpdate);
+
+        assert_eq!(app.world().resource::<LoaderReport>().loaders.len(), 1);
+        let settled = app
+            .world_mut()
+            .resource_mut::<Messages<ChunkRegionSettled>>()
+            .drain()
+            .count();
+        assert_eq!(settled, 1);
+    }
+
+    #[test]
+    fn loaders_in_fixed_update_run_at_the_fixed_rate() {
+        #[derive(Resource, Default)]
+        struct FixedRuns(usize);
+
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default().with_schedule(FixedUpdate));
+        app.insert_resource(Time::<Fixed>::from_seconds(0.25))
+            .insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+                std::time::Duration::from_millis(100),
+            ))
+            .init_resource::<FixedRuns>()
+            .add_systems(FixedUpdate, |mut runs: ResMut<FixedRuns>| runs.0 += 1);
+        let loader = app
+            .world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)))
+            .id();
+        // The first frame has no delta, the others add up to 1s
+        for frame in 0..11 {
+            let x = frame as f32 * 10.0 + 5.0;
+            app.world_mut().entity_mut(loader).insert((
+                Transform::from_xyz(x, 0.0, 0.0),
+                GlobalTransform::from_xyz(x, 0.0, 0.0),
+            ));
+            app.update();
+        }
+
+        // A loader moving one chunk per frame only loads where it was at
+        // each fixed step
+        assert_eq!(app.world().resource::<FixedRuns>().0, 4);
+        assert_eq!(app.world().resource::<ChunkManager>().len(), 4);
+    }
+
+    #[test]
+    fn requested_chunks_count_as_loaded_without_a_generator() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)));
+        app.update();
+
+        let chunk = app
+            .world()
+            .resource::<ChunkManager>()
+            .get_chunk(&IVec3::ZERO)
+            .unwrap();
+        assert_eq!(
+            app.world().get::<ChunkLoadState>(chunk),
+            Some(&ChunkLoadState::Requested)
+        );
+        let settled = app
+            .world_mut()
+            .resource_mut::<Messages<ChunkRegionSettled>>()
+            .drain()
+            .count();
+        assert_eq!(settled, 1);
+    }
+
+    fn cooldown_app() -> App {
+        let mut app = crate::tests::app(
+            crate::ChunkyPlugin::default()
+                .with_duplicate_policy(crate::ChunkCollisionPolicy::DespawnNew),
+        );
+        app.insert_resource(ChunkLoaderSettings {
+            unload_cooldown_frames: 5,
+            ..default()
+        });
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)));
+        app.update();
+        app
+    }
+
+    #[test]
+    fn unloaded_chunks_wait_for_their_cooldown() {
+        let mut app = cooldown_app();
+        let chunk_at_origin = |app: &App| {
+            app.world()
+                .resource::<ChunkManager>()
+                .get_chunk(&IVec3::ZERO)
+        };
+        let chunk = chunk_at_origin(&app).unwrap();
+
+        app.world_mut().despawn(chunk);
+        for frame in 1..=5 {
+            app.update();
+            assert_eq!(chunk_at_origin(&app), None, "frame {frame}");
+        }
+        app.update();
+        assert!(chunk_at_origin(&app).is_some());
+    }
+
+    #[test]
+    fn cooldowns_count_triggered_passes_only() {
+        let mut app =
+            crate::tests::app(crate::ChunkyPlugin::default().with_continuous_loading(false));
+        app.insert_resource(ChunkLoaderSettings {
+            unload_cooldown_frames: 2,
+            ..default()
+        });
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)));
+        let load_pass = |app: &mut App| {
+            app.world_mut().write_message(ChunkLoadTrigger);
+            app.update();
+            app.world()
+                .resource::<ChunkManager>()
+                .get_chunk(&IVec3::ZERO)
+        };
+        let chunk = load_pass(&mut app).unwrap();
+
+        app.world_mut().despawn(chunk);
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(load_pass(&mut app), None);
+        assert_eq!(load_pass(&mut app), None);
+        assert!(load_pass(&mut app).is_some());
+    }
+
+    #[test]
+    fn cooldowns_skip_rejected_chunks_and_keep_layers_apart() {
+        let mut app = cooldown_app();
+        let rejected = app.world_mut().spawn((Chunk, ChunkPos(IVec3::ZERO))).id();
+        let layered = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::X), ChunkLayer(2)))
+            .id();
+        app.update();
+        assert!(app.world().get_entity(rejected).is_err());
+
+        app.world_mut().despawn(layered);
+        let cooldowns: Vec<_> = app
+            .world()
+            .resource::<UnloadCooldowns>()
+            .0
+            .keys()
+            .copied()
+            .collect();
+        assert_eq!(cooldowns, [(2, IVec3::X)]);
+    }
+
+    #[test]
+    fn the_soft_limit_warns_once_when_crossed() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        app.insert_resource(ChunkLoaderSettings {
+            soft_limit: 2,
+            ..default()
+        });
+        let world = app.world_mut();
+        // Run on this thread, where the warnings are captured
+        let warnings = crate::tests::logged_warnings(|| {
+            for x in 0..5 {
+                world.spawn((Chunk, ChunkPos(IVec3::X * x)));
+                world.run_system_cached(warn_soft_limit).unwrap();
+            }
+        });
+
+        let [warning] = &warnings[..] else {
+            panic!("expected one warning, got {warnings:?}");
+        };
+        assert!(
+            warning.contains("3 chunks are loaded, above the soft limit of 2"),
+            "{warning}"
+        );
+
+        // Crossing again within the second is only warned about once it's up
+        let chunks: Vec<_> = world
+            .query_filtered::<Entity, With<Chunk>>()
+            .iter(world)
+            .collect();
+        let warnings = crate::tests::logged_warnings(|| {
+            for &chunk in &chunks[2..] {
+                world.despawn(chunk);
+            }
+            world.run_system_cached(warn_soft_limit).unwrap();
+            world.spawn((Chunk, ChunkPos(IVec3::Y)));
+            world.run_system_cached(warn_soft_limit).unwrap();
+            world
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_millis(1500));
+            for _ in 0..3 {
+                world.run_system_cached(warn_soft_limit).unwrap();
+            }
+        });
+        let [warning] = &warnings[..] else {
+            panic!("expected one warning, got {warnings:?}");
+        };
+        assert!(
+            warning.contains("3 chunks are loaded, above the soft limit of 2"),
+            "{warning}"
+        );
     }
 }
@@ -0,0 +1,386 @@
+//! A top-down minimap of the loaded chunks.
+//!
+//! [`ChunkMinimapPlugin`] keeps an [`Image`] with one pixel per chunk column,
+//! colored by the load states of the chunks in it, and shows it in a UI panel.
+//! The view follows the entity with [`ChunkMinimapFocus`], or else the first
+//! [`ChunkLoader`], whose chunks are marked as well. Only columns whose chunks
+//! changed are repainted, unless the view scrolls.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(ChunkyPlugin::default())
+//!     .add_plugins(ChunkMinimapPlugin);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    color::palettes::tailwind,
+    image::ImageSampler,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    Chunk, ChunkLoadState, ChunkManager, ChunkPos, ChunkUpAxis, ChunkySystems,
+    chunk_loader::ChunkLoader,
+};
+
+/// Shows a minimap of the loaded chunks, see the [module docs](self).
+///
+/// Needs the `Image` asset, e.g. from `DefaultPlugins`.
+pub struct ChunkMinimapPlugin;
+
+impl Plugin for ChunkMinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChunkMinimapConfig>()
+            .init_resource::<MinimapColumns>()
+            .add_systems(Startup, setup_chunk_minimap)
+            .add_systems(
+                Update,
+                update_chunk_minimap.after(ChunkySystems::PostGenerate),
+            );
+        #[cfg(feature = "reflect")]
+        app.register_type::<ChunkMinimapConfig>()
+            .register_type::<ChunkMinimapFocus>()
+            .register_type::<ChunkMinimapPanel>();
+    }
+}
+
+/// Settings of the chunk minimap, read every frame.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn bigger_minimap(mut config: ResMut<ChunkMinimapConfig>) {
+///     config.extent = UVec2::splat(128);
+///     config.scale = 2.0;
+///     config.palette.ready = Color::WHITE;
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct ChunkMinimapConfig {
+    /// Number of chunk columns shown along each axis, one pixel each. Columns
+    /// outside are cut off. (default: 64x64)
+    pub extent: UVec2,
+    /// Size of a pixel in the panel, in logical pixels (default: 3)
+    pub scale: f32,
+    /// Colors of the chunk columns by load state
+    pub palette: MinimapPalette,
+}
+
+impl Default for ChunkMinimapConfig {
+    fn default() -> Self {
+        Self {
+            extent: UVec2::splat(64),
+            scale: 3.0,
+            palette: MinimapPalette::default(),
+        }
+    }
+}
+
+/// Colors of the chunk minimap. A column with chunks in several load states
+/// shows the first of failed, generating, requested, ready and stateless.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct MinimapPalette {
+    /// Columns without chunks (default: dark gray)
+    pub background: Color,
+    /// Chunks without a [`ChunkLoadState`] (default: green)
+    pub base: Color,
+    /// `Requested` chunks (default: yellow)
+    pub requested: Color,
+    /// `Generating` chunks (default: orange)
+    pub generating: Color,
+    /// `Ready` chunks (default: green)
+    pub ready: Color,
+    /// `Failed` chunks (default: red)
+    pub failed: Color,
+    /// The chunks of loaders (default: white)
+    pub loader: Color,
+}
+
+impl Default for MinimapPalette {
+    fn default() -> Self {
+        Self {
+            background: tailwind::GRAY_900.into(),
+            base: tailwind::GREEN_500.into(),
+            requested: tailwind::YELLOW_500.into(),
+            generating: tailwind::ORANGE_500.into(),
+            ready: tailwind::GREEN_500.into(),
+            failed: tailwind::RED_500.into(),
+            loader: Color::WHITE,
+        }
+    }
+}
+
+/// Centers the chunk minimap on this entity instead of the first
+/// [`ChunkLoader`].
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkMinimapFocus;
+
+/// Marks the UI node showing the chunk minimap, in the top right corner.
+///
+/// Restyle its `Node`, or despawn it and show the [`ChunkMinimapImage`]
+/// elsewhere.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkMinimapPanel;
+
+/// The image of the chunk minimap, with one pixel per chunk column
+#[derive(Resource, Debug, Clone)]
+pub struct ChunkMinimapImage(pub Handle<Image>);
+
+/// Load states in the order columns show them, stateless chunks last
+const STATE_PRIORITY: [Option<ChunkLoadState>; 5] = [
+    Some(ChunkLoadState::Failed),
+    Some(ChunkLoadState::Generating),
+    Some(ChunkLoadState::Requested),
+    Some(ChunkLoadState::Ready),
+    None,
+];
+
+/// The chunks of every column, as tracked from the changes of chunks
+#[derive(Resource, Default)]
+struct MinimapColumns {
+    /// Column and slot in [`STATE_PRIORITY`] of every tracked chunk
+    chunks: HashMap<Entity, (IVec2, usize)>,
+    /// Number of chunks per slot in [`STATE_PRIORITY`] of every column
+    columns: HashMap<IVec2, [u32; 5]>,
+    /// Columns whose pixel is outdated
+    dirty: HashSet<IVec2>,
+    /// The column at the center of the image when it was painted
+    center: Option<IVec2>,
+    /// The columns marked as loaders when the image was painted
+    loaders: Vec<IVec2>,
+}
+
+impl MinimapColumns {
+    fn insert(&mut self, entity: Entity, column: IVec2, slot: usize) {
+        self.remove(entity);
+        self.chunks.insert(entity, (column, slot));
+        self.columns.entry(column).or_default()[slot] += 1;
+        self.dirty.insert(column);
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        let Some((column, slot)) = self.chunks.remove(&entity) else {
+            return;
+        };
+        if let Some(counts) = self.columns.get_mut(&column) {
+            counts[slot] -= 1;
+            if counts.iter().all(|&count| count == 0) {
+                self.columns.remove(&column);
+            }
+        }
+        self.dirty.insert(column);
+    }
+
+    fn color(&self, column: IVec2, palette: &MinimapPalette) -> Color {
+        let Some(counts) = self.columns.get(&column) else {
+            return palette.background;
+        };
+        let slot = counts.iter().position(|&count| count > 0).unwrap_or(4);
+        match STATE_PRIORITY[slot] {
+            Some(ChunkLoadState::Failed) => palette.failed,
+            Some(ChunkLoadState::Generating) => palette.generating,
+            Some(ChunkLoadState::Requested) => palette.requested,
+            Some(ChunkLoadState::Ready) => palette.ready,
+            None => palette.base,
+        }
+    }
+}
+
+/// Creates the minimap image and its panel
+fn setup_chunk_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    config: Res<ChunkMinimapConfig>,
+) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: config.extent.x,
+            height: config.extent.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &config.palette.background.to_srgba().to_u8_array(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    let image = images.add(image);
+    commands.insert_resource(ChunkMinimapImage(image.clone()));
+    commands.spawn((
+        ChunkMinimapPanel,
+        ImageNode::new(image),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            right: Val::Px(12.0),
+            width: Val::Px(config.extent.x as f32 * config.scale),
+            height: Val::Px(config.extent.y as f32 * config.scale),
+            ..default()
+        },
+    ));
+}
+
+/// Tracks the changed chunks and repaints their columns, or the whole image
+/// once the view scrolled or the config changed
+fn update_chunk_minimap(
+    chunk_manager: Res<ChunkManager>,
+    config: Res<ChunkMinimapConfig>,
+    up_axis: Option<Res<ChunkUpAxis>>,
+    image: Option<Res<ChunkMinimapImage>>,
+    mut images: ResMut<Assets<Image>>,
+    mut columns: ResMut<MinimapColumns>,
+    changed: Query<
+        (Entity, &ChunkPos, Option<&ChunkLoadState>),
+        (With<Chunk>, Or<(Added<Chunk>, Changed<ChunkLoadState>)>),
+    >,
+    mut removed: RemovedComponents<Chunk>,
+    focus: Query<&GlobalTransform, With<ChunkMinimapFocus>>,
+    loaders: Query<(Entity, &GlobalTransform), With<ChunkLoader>>,
+    mut panels: Query<&mut Node, With<ChunkMinimapPanel>>,
+) {
+    let up_axis = up_axis.map_or(ChunkUpAxis::Y, |up_axis| *up_axis);
+    let column_of = |translation: Vec3| up_axis.ground(chunk_manager.get_chunk_pos(&translation));
+
+    for entity in removed.read() {
+        columns.remove(entity);
+    }
+    for (entity, ChunkPos(chunk_pos), load_state) in changed.iter() {
+        let slot = STATE_PRIORITY
+            .iter()
+            .position(|state| state.as_ref() == load_state)
+            .unwrap();
+        columns.insert(entity, up_axis.ground(*chunk_pos), slot);
+    }
+
+    let mut loader_columns: Vec<_> = loaders.iter().collect();
+    loader_columns.sort_by_key(|(entity, _)| *entity);
+    let loader_columns: Vec<_> = loader_columns
+        .into_iter()
+        .map(|(_, transform)| column_of(transform.translation()))
+        .collect();
+    let center = focus
+        .iter()
+        .next()
+        .map(|transform| column_of(transform.translation()))
+        .or(loader_columns.first().copied())
+        .unwrap_or(IVec2::ZERO);
+
+    let Some(image) = image.and_then(|image| images.get_mut(&image.0)) else {
+        return;
+    };
+    let extent = config.extent;
+    let repaint = columns.center != Some(center) || config.is_changed() || image.size() != extent;
+    if !repaint && columns.dirty.is_empty() && columns.loaders == loader_columns {
+        return;
+    }
+
+    if image.size() != extent {
+        image.resize(Extent3d {
+            width: extent.x,
+            height: extent.y,
+            depth_or_array_layers: 1,
+        });
+    }
+    if config.is_changed() {
+        for mut node in panels.iter_mut() {
+            node.width = Val::Px(extent.x as f32 * config.scale);
+            node.height = Val::Px(extent.y as f32 * config.scale);
+        }
+    }
+
+    // Column shown at the top left pixel
+    let corner = center - (extent / 2).as_ivec2();
+    let mut paint = |column: IVec2, color: Color| {
+        let pixel = column - corner;
+        if pixel.cmpge(IVec2::ZERO).all() && pixel.cmplt(extent.as_ivec2()).all() {
+            let pixel = pixel.as_uvec2();
+            let _ = image.set_color_at(pixel.x, pixel.y, color);
+        }
+    };
+    let columns = &mut *columns;
+    if repaint {
+        for y in 0..extent.y as i32 {
+            for x in 0..extent.x as i32 {
+                let column = corner + ivec2(x, y);
+                paint(column, columns.color(column, &config.palette));
+            }
+        }
+    } else {
+        for &column in columns.dirty.iter().chain(columns.loaders.iter()) {
+            paint(column, columns.color(column, &config.palette));
+        }
+    }
+    for &column in loader_columns.iter() {
+        paint(column, config.palette.loader);
+    }
+    columns.dirty.clear();
+    columns.center = Some(center);
+    columns.loaders = loader_columns;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChunkyPlugin, tests::app};
+
+    /// The color of a pixel of the minimap, as stored
+    fn pixel(app: &App, x: u32, y: u32) -> [u8; 4] {
+        let image = &app.world().resource::<ChunkMinimapImage>().0;
+        let image = app.world().resource::<Assets<Image>>().get(image).unwrap();
+        image.get_color_at(x, y).unwrap().to_srgba().to_u8_array()
+    }
+
+    #[test]
+    fn loading_chunks_paints_their_columns() {
+        let mut app = app((ChunkyPlugin::default(), ChunkMinimapPlugin));
+        app.init_asset::<Image>()
+            .insert_resource(ChunkMinimapConfig {
+                extent: UVec2::splat(8),
+                ..default()
+            });
+        app.update();
+        let palette = MinimapPalette::default();
+        let background = palette.background.to_srgba().to_u8_array();
+        assert_eq!(pixel(&app, 5, 6), background);
+        assert_eq!(pixel(&app, 2, 3), background);
+
+        // The view is centered on column (0, 0), at pixel (4, 4)
+        let ready = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::new(1, 0, 2)), ChunkLoadState::Ready))
+            .id();
+        app.world_mut().spawn((
+            Chunk,
+            ChunkPos(IVec3::new(-2, 3, -1)),
+            ChunkLoadState::Requested,
+        ));
+        app.update();
+        assert_eq!(pixel(&app, 5, 6), palette.ready.to_srgba().to_u8_array());
+        assert_eq!(
+            pixel(&app, 2, 3),
+            palette.requested.to_srgba().to_u8_array()
+        );
+
+        app.world_mut().despawn(ready);
+        app.update();
+        assert_eq!(pixel(&app, 5, 6), background);
+    }
+}
@@ -0,0 +1,177 @@
+//! An in-memory cache of the stored data of recently unloaded chunks.
+//!
+//! Set up with `ChunkPersistencePlugin::with_payload_cache`, the cache keeps
+//! the bytes saved for a chunk, or encoded for a clean chunk as it is unloaded,
+//! so a chunk requested again soon after is decoded without reading the
+//! `ChunkStore`. Entries hold the stored bytes with their payload version, and
+//! are dropped once the chunk is regenerated.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use bevy::prelude::*;
+
+#[cfg(doc)]
+use crate::chunk_persistence::ChunkPersistencePlugin;
+
+/// How much a [`ChunkPayloadCache`] holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum CacheCapacity {
+    /// At most this many chunks
+    Entries(usize),
+    /// At most this many stored bytes, summed over all chunks
+    Bytes(usize),
+}
+
+/// Which chunk a full [`ChunkPayloadCache`] drops first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum CacheEviction {
+    /// The chunk used least recently, counting both saves and loads
+    #[default]
+    Lru,
+    /// The chunk saved first
+    Fifo,
+}
+
+/// Settings of the cache keeping the data of recently unloaded chunks in
+/// memory, see [`ChunkPersistencePlugin::with_payload_cache`].
+///
+/// # Example
+///
+/// ```no_run
+/// use chunky_bevy::prelude::*;
+///
+/// // Keep up to 64 MiB of chunk data, dropping the oldest saves first
+/// let cache = ChunkPayloadCache::new(CacheCapacity::Bytes(64 << 20))
+///     .with_eviction(CacheEviction::Fifo);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct ChunkPayloadCache {
+    pub capacity: CacheCapacity,
+    pub eviction: CacheEviction,
+}
+
+impl ChunkPayloadCache {
+    /// Creates cache settings holding up to `capacity`, evicting the least
+    /// recently used chunk first
+    pub fn new(capacity: CacheCapacity) -> Self {
+        Self {
+            capacity,
+            eviction: CacheEviction::default(),
+        }
+    }
+
+    /// Sets which chunk is dropped first once the cache is full
+    pub fn with_eviction(mut self, eviction: CacheEviction) -> Self {
+        self.eviction = eviction;
+        self
+    }
+}
+
+/// The cached chunks
+pub(crate) struct PayloadCache {
+    settings: ChunkPayloadCache,
+    entries: HashMap<IVec3, CacheEntry>,
+    /// Cached positions by the stamp of their entry, evicted from the front
+    order: BTreeMap<u64, IVec3>,
+    next_stamp: u64,
+    /// Size of all cached bytes
+    bytes: usize,
+    /// Lookups answered by the cache since the last stats update
+    pub(crate) hits: u64,
+    /// Lookups that went to the store since the last stats update
+    pub(crate) misses: u64,
+}
+
+struct CacheEntry {
+    bytes: Arc<[u8]>,
+    stamp: u64,
+}
+
+impl PayloadCache {
+    pub(crate) fn new(settings: ChunkPayloadCache) -> Self {
+        Self {
+            settings,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            next_stamp: 0,
+            bytes: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Caches the stored bytes of the chunk at `pos`, evicting chunks until
+    /// they fit. Bytes larger than the whole cache are not cached.
+    pub(crate) fn insert(&mut self, pos: IVec3, bytes: Arc<[u8]>) {
+        self.remove(pos);
+        let fits = match self.settings.capacity {
+            CacheCapacity::Entries(entries) => entries > 0,
+            CacheCapacity::Bytes(capacity) => bytes.len() <= capacity,
+        };
+        if !fits {
+            return;
+        }
+        while self.is_full(bytes.len()) {
+            let Some((_, evicted)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&evicted) {
+                self.bytes -= entry.bytes.len();
+            }
+        }
+        let stamp = self.stamp();
+        self.order.insert(stamp, pos);
+        self.bytes += bytes.len();
+        self.entries.insert(pos, CacheEntry { bytes, stamp });
+    }
+
+    /// Returns the cached bytes of the chunk at `pos`, counting the lookup
+    pub(crate) fn get(&mut self, pos: IVec3) -> Option<Arc<[u8]>> {
+        let stamp = self.stamp();
+        let Some(entry) = self.entries.get_mut(&pos) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        if self.settings.eviction == CacheEviction::Lru {
+            self.order.remove(&entry.stamp);
+            self.order.insert(stamp, pos);
+            entry.stamp = stamp;
+        }
+        Some(entry.bytes.clone())
+    }
+
+    /// Drops the cached bytes of the chunk at `pos`
+    pub(crate) fn remove(&mut self, pos: IVec3) {
+        if let Some(entry) = self.entries.remove(&pos) {
+            self.order.remove(&entry.stamp);
+            self.bytes -= entry.bytes.len();
+        }
+    }
+
+    /// Drops all cached chunks
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+
+    /// Checks if adding `len` bytes goes over the capacity
+    fn is_full(&self, len: usize) -> bool {
+        match self.settings.capacity {
+            CacheCapacity::Entries(entries) => self.entries.len() >= entries,
+            CacheCapacity::Bytes(capacity) => self.bytes + len > capacity,
+        }
+    }
+
+    fn stamp(&mut self) -> u64 {
+        self.next_stamp += 1;
+        self.next_stamp
+    }
+}
@@ -0,0 +1,2007 @@
+//! Saving chunk data when chunks are unloaded and loading it back.
+//!
+//! Add a [`ChunkPersistencePlugin`] with a [`ChunkStore`] and a codec for the
+//! generator's output. Mark chunks whose [`ChunkData`] was edited with
+//! [`ChunkDirtyCommands::mark_dirty`]. Whenever a dirty `Ready` chunk is
+//! despawned, e.g. by the unloader, its data is encoded and saved; all dirty
+//! ready chunks are also saved on [`AppExit`]. Chunks that were only loaded or
+//! generated are skipped, as generating them again gives the same data, unless
+//! [`ChunkPersistencePlugin::with_force_save_generated`] is set. Before
+//! generating a chunk, the store is checked and stored data is used instead.
+//! [`RegenerateChunk`] always runs the generator, and marks the chunk dirty.
+//!
+//! Store reads and writes run on the [`IoTaskPool`], so the main thread never
+//! waits for the disk, except on exit. Data is encoded while the chunk is
+//! despawned, and until its write completes, a chunk requested at the same
+//! position gets that data instead of reading the store. A requested chunk
+//! carries [`LoadingFromDisk`] while the store is read. If the store fails to
+//! save, the encoded data is kept in memory and saving is retried on exit.
+//!
+//! Stores are keyed by chunk position only, so chunks on a [`ChunkLayer`] other
+//! than the default one are neither saved nor loaded and always generate.
+//!
+//! With [`ChunkPersistencePlugin::with_payload_cache`], the data of recently
+//! unloaded chunks is also kept in memory, so returning to them doesn't read
+//! the store.
+//!
+//! Encoded data can be compressed before it is stored, see
+//! [`ChunkPersistencePlugin::with_compression`]. Each stored chunk records how
+//! it was compressed, so changing the setting keeps older chunks readable. The
+//! achieved ratio is measured by the [`PERSISTENCE_COMPRESSION_RATIO`]
+//! diagnostic.
+//!
+//! Each stored chunk also records the payload version set with
+//! [`ChunkPersistencePlugin::with_payload_version`]. Chunks saved with an older
+//! version go through [`ChunkPersistencePlugin::with_migration`] before they
+//! are decoded, so saves stay readable as the chunk data type changes.
+//!
+//! Dirty chunks can also be saved periodically with
+//! [`ChunkPersistencePlugin::with_autosave`], so a crash doesn't lose edits of
+//! chunks that were never unloaded. [`ChunkPersistenceStats`] counts the saved
+//! and skipped chunks.
+//!
+//! With the `serde` feature, [`ChunkPersistencePlugin::serde`] encodes chunk
+//! data with a [`SerdeFormat`], such as [`Ron`] with the `ron` feature or the
+//! compact [`Bincode`] with the `bincode` feature. Stored
+//! bytes that fail to decode, or don't match the CRC32 checksum saved with
+//! them, fire [`ChunkLoadCorrupt`] and the chunk is generated instead.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! struct FlatGenerator;
+//!
+//! impl ChunkGenerator for FlatGenerator {
+//!     type Output = Vec<u8>;
+//!
+//!     fn generate(&self, _pos: IVec3, _ctx: GenerationContext) -> GenerationResult<Vec<u8>> {
+//!         Ok(vec![1; 1000])
+//!     }
+//! }
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(ChunkyPlugin::default())
+//!     .add_plugins(ChunkGenerationPlugin::new(FlatGenerator))
+//!     .add_plugins(ChunkPersistencePlugin::new(
+//!         FilesystemChunkStore::new("saves/world"),
+//!         |data: &Vec<u8>| Ok(data.clone()),
+//!         |bytes: &[u8]| Ok(bytes.to_vec()),
+//!     ));
+//!
+//! fn dig(mut commands: Commands, mut chunks: Query<(Entity, &mut ChunkData<Vec<u8>>)>) {
+//!     for (entity, mut data) in chunks.iter_mut() {
+//!         data.0[0] = 0;
+//!         commands.entity(entity).mark_dirty();
+//!     }
+//! }
+//! ```
+
+#[cfg(feature = "zstd")]
+use std::io::Read;
+#[cfg(feature = "serde")]
+use std::marker::PhantomData;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+    tasks::{IoTaskPool, Task, block_on, futures::check_ready},
+};
+
+#[cfg(doc)]
+use crate::chunk_generation::RegenerateChunk;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    Chunk, ChunkLayer, ChunkPersistent, ChunkPos, ReadyChunks,
+    chunk_generation::{ChunkData, GenerateFresh, GenerationError, GenerationResult},
+    chunk_payload_cache::{ChunkPayloadCache, PayloadCache},
+};
+
+/// Storage for encoded chunk data, keyed by chunk position.
+pub trait ChunkStore: Send + Sync + 'static {
+    /// Stores the data of the chunk at `pos`, replacing any previous data
+    fn save(&self, pos: IVec3, bytes: &[u8]) -> io::Result<()>;
+
+    /// Returns the stored data of the chunk at `pos`, if any.
+    ///
+    /// Returning an [`io::ErrorKind::InvalidData`] error for damaged data
+    /// fires [`ChunkLoadCorrupt`], like data that fails to decode.
+    fn load(&self, pos: IVec3) -> io::Result<Option<Vec<u8>>>;
+
+    /// Checks if data for the chunk at `pos` is stored
+    fn exists(&self, pos: IVec3) -> bool;
+
+    /// The directory the store keeps its data in, if it has one
+    fn root(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Returns a store like this one keeping its data in `root` instead, used
+    /// by [`switch_save_slot`]. Stores without a directory return `None`.
+    fn relocate(&self, _root: &Path) -> Option<Arc<dyn ChunkStore>> {
+        None
+    }
+}
+
+/// A [`ChunkStore`] writing one file per chunk into a directory.
+///
+/// The directory is created on the first save.
+#[derive(Debug, Clone)]
+pub struct FilesystemChunkStore {
+    dir: PathBuf,
+}
+
+impl FilesystemChunkStore {
+    /// Creates a store that keeps its files in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The directory the chunk files are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The file holding the chunk at `pos`
+    fn chunk_path(&self, pos: IVec3) -> PathBuf {
+        self.dir
+            .join(format!("{}_{}_{}.chunk", pos.x, pos.y, pos.z))
+    }
+}
+
+impl ChunkStore for FilesystemChunkStore {
+    fn save(&self, pos: IVec3, bytes: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.chunk_path(pos), bytes)
+    }
+
+    fn load(&self, pos: IVec3) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.chunk_path(pos)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn exists(&self, pos: IVec3) -> bool {
+        self.chunk_path(pos).is_file()
+    }
+
+    fn root(&self) -> Option<&Path> {
+        Some(&self.dir)
+    }
+
+    fn relocate(&self, root: &Path) -> Option<Arc<dyn ChunkStore>> {
+        Some(Arc::new(Self::new(root)))
+    }
+}
+
+/// Turns chunk data into bytes for a [`ChunkStore`]
+pub type EncodeFn<T> = fn(&T) -> GenerationResult<Vec<u8>>;
+
+/// Turns bytes from a [`ChunkStore`] back into chunk data
+pub type DecodeFn<T> = fn(&[u8]) -> GenerationResult<T>;
+
+/// Turns bytes encoded by an older payload version, passed first, into bytes
+/// of the current payload version
+pub type MigrateFn = fn(u32, &[u8]) -> GenerationResult<Vec<u8>>;
+
+/// How encoded chunk data is compressed before it is stored.
+///
+/// Stored chunks record their compression, so they can be read regardless of
+/// the current setting, as long as the cargo feature of their compression is
+/// enabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum ChunkCompression {
+    /// Stores the encoded bytes as they are
+    #[default]
+    None,
+    /// Fast [LZ4](https://lz4.org) block compression, needs the `lz4` feature
+    #[cfg(feature = "lz4")]
+    Lz4,
+    /// [Zstandard](https://facebook.github.io/zstd/) compression, smaller but
+    /// slower than LZ4, needs the `zstd` feature
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl ChunkCompression {
+    /// The byte recording the compression of a stored chunk
+    fn tag(self) -> u8 {
+        match self {
+            ChunkCompression::None => 0,
+            #[cfg(feature = "lz4")]
+            ChunkCompression::Lz4 => 1,
+            #[cfg(feature = "zstd")]
+            ChunkCompression::Zstd => 2,
+        }
+    }
+
+    /// Appends the compressed bytes to `out`
+    fn compress(self, bytes: &[u8], out: &mut Vec<u8>) {
+        match self {
+            ChunkCompression::None => out.extend_from_slice(bytes),
+            #[cfg(feature = "lz4")]
+            ChunkCompression::Lz4 => out.extend(lz4_flex::compress_prepend_size(bytes)),
+            #[cfg(feature = "zstd")]
+            ChunkCompression::Zstd => out.extend(ruzstd::encoding::compress_to_vec(
+                bytes,
+                ruzstd::encoding::CompressionLevel::Fastest,
+            )),
+        }
+    }
+}
+
+/// Decompresses bytes compressed with the compression recorded as `tag`
+fn decompress(tag: u8, data: &[u8]) -> io::Result<Cow<'_, [u8]>> {
+    match tag {
+        0 => Ok(Cow::Borrowed(data)),
+        #[cfg(feature = "lz4")]
+        1 => lz4_flex::decompress_size_prepended(data)
+            .map(Cow::Owned)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+        #[cfg(feature = "zstd")]
+        2 => {
+            let mut decoder = ruzstd::decoding::StreamingDecoder::new(data)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            let mut decompressed = Vec::new();
+            decoder.read_to_end(&mut decompressed)?;
+            Ok(Cow::Owned(decompressed))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown or disabled compression {tag}"),
+        )),
+    }
+}
+
+/// Size of the header in front of the data of a stored chunk: the compression
+/// tag and the little-endian payload version
+const RECORD_HEADER_LEN: usize = 5;
+
+/// Set in the compression tag of records followed by a checksum. Records saved
+/// before checksums were added don't have it and load unchecked.
+const CHECKSUM_FLAG: u8 = 0x80;
+
+/// Size of the little-endian CRC32 after the header, covering the payload
+/// version and the compressed data
+const CHECKSUM_LEN: usize = 4;
+
+/// Compresses encoded chunk data and puts the header and checksum in front of
+/// it
+fn pack_record(compression: ChunkCompression, payload_version: u32, encoded: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + CHECKSUM_LEN + encoded.len());
+    record.push(compression.tag() | CHECKSUM_FLAG);
+    record.extend_from_slice(&payload_version.to_le_bytes());
+    record.extend_from_slice(&[0; CHECKSUM_LEN]);
+    compression.compress(encoded, &mut record);
+    let checksum = record_checksum(
+        &record[1..RECORD_HEADER_LEN],
+        &record[RECORD_HEADER_LEN + CHECKSUM_LEN..],
+    );
+    record[RECORD_HEADER_LEN..RECORD_HEADER_LEN + CHECKSUM_LEN]
+        .copy_from_slice(&checksum.to_le_bytes());
+    record
+}
+
+/// The CRC32 of the payload version and the compressed data of a record
+fn record_checksum(version: &[u8], data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(version);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Stored bytes not matching the checksum saved with them, carried by
+/// [`io::ErrorKind::InvalidData`] errors
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChecksumMismatch {
+    pub(crate) expected: u32,
+    pub(crate) found: u32,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "chunk data has checksum {:08x}, expected {:08x}",
+            self.found, self.expected
+        )
+    }
+}
+
+impl Error for ChecksumMismatch {}
+
+impl From<ChecksumMismatch> for io::Error {
+    fn from(mismatch: ChecksumMismatch) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, mismatch)
+    }
+}
+
+/// The expected and found checksums, if `error` is a [`ChecksumMismatch`]
+fn checksums(error: &io::Error) -> Option<(u32, u32)> {
+    let mismatch = error.get_ref()?.downcast_ref::<ChecksumMismatch>()?;
+    Some((mismatch.expected, mismatch.found))
+}
+
+/// Splits a stored chunk into its payload version and decompressed data,
+/// verifying its checksum first
+fn unpack_record(record: &[u8]) -> io::Result<(u32, Cow<'_, [u8]>)> {
+    let Some((header, mut data)) = record.split_first_chunk::<RECORD_HEADER_LEN>() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "chunk data is shorter than its header",
+        ));
+    };
+    let [tag, version @ ..] = *header;
+    if tag & CHECKSUM_FLAG != 0 {
+        let Some((checksum, rest)) = data.split_first_chunk::<CHECKSUM_LEN>() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "chunk data is shorter than its checksum",
+            ));
+        };
+        let expected = u32::from_le_bytes(*checksum);
+        let found = record_checksum(&version, rest);
+        if expected != found {
+            return Err(ChecksumMismatch { expected, found }.into());
+        }
+        data = rest;
+    }
+    Ok((
+        u32::from_le_bytes(version),
+        decompress(tag & !CHECKSUM_FLAG, data)?,
+    ))
+}
+
+/// Diagnostic measuring how many times larger encoded chunk data is than the
+/// bytes stored for it, over the chunks saved in a frame
+pub const PERSISTENCE_COMPRESSION_RATIO: DiagnosticPath =
+    DiagnosticPath::const_new("chunky/persistence_compression_ratio");
+
+/// Persists the [`ChunkData<T>`] of chunks in a [`ChunkStore`].
+pub struct ChunkPersistencePlugin<T: Send + Sync + 'static> {
+    store: Arc<dyn ChunkStore>,
+    encode: EncodeFn<T>,
+    decode: DecodeFn<T>,
+    compression: ChunkCompression,
+    payload_version: u32,
+    migrate: Option<MigrateFn>,
+    autosave: Option<Autosave>,
+    force_save_generated: bool,
+    payload_cache: Option<ChunkPayloadCache>,
+    corrupt_policy: CorruptChunkPolicy,
+}
+
+impl<T: Send + Sync + 'static> ChunkPersistencePlugin<T> {
+    /// Creates a persistence plugin saving to `store` with the given codec
+    pub fn new(store: impl ChunkStore, encode: EncodeFn<T>, decode: DecodeFn<T>) -> Self {
+        Self {
+            store: Arc::new(store),
+            encode,
+            decode,
+            compression: default(),
+            payload_version: 0,
+            migrate: None,
+            autosave: None,
+            force_save_generated: false,
+            payload_cache: None,
+            corrupt_policy: default(),
+        }
+    }
+
+    /// Sets how chunk data is compressed before it is stored. Defaults to
+    /// [`ChunkCompression::None`].
+    pub fn with_compression(mut self, compression: ChunkCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the version of the chunk data's encoding, recorded with every
+    /// saved chunk. Defaults to 0.
+    ///
+    /// Bump it whenever the encoding changes, and handle older versions with
+    /// [`with_migration`](Self::with_migration).
+    pub fn with_payload_version(mut self, payload_version: u32) -> Self {
+        self.payload_version = payload_version;
+        self
+    }
+
+    /// Sets the function upgrading chunks saved with an older payload version
+    /// before they are decoded.
+    ///
+    /// Chunks of an older version that fail to migrate, or if no migration is
+    /// set, and chunks of a newer version fire [`ChunkMigrationFailed`] and are
+    /// generated instead.
+    pub fn with_migration(mut self, migrate: MigrateFn) -> Self {
+        self.migrate = Some(migrate);
+        self
+    }
+
+    /// Saves the ready chunks marked with [`ChunkDirty`] every `autosave_every`
+    /// and removes the marker. Off by default, so chunks are only saved when
+    /// they are unloaded and on exit.
+    ///
+    /// An autosave pass saves at most `max_chunks_per_pass` chunks a frame,
+    /// continuing in the following frames until no dirty chunk is left. A
+    /// `max_chunks_per_pass` of 0 saves every dirty chunk in one frame.
+    pub fn with_autosave(mut self, autosave_every: Duration, max_chunks_per_pass: usize) -> Self {
+        self.autosave = Some(Autosave {
+            every: autosave_every,
+            max_chunks_per_pass: match max_chunks_per_pass {
+                0 => usize::MAX,
+                max => max,
+            },
+            elapsed: Duration::ZERO,
+        });
+        self
+    }
+
+    /// Saves every ready chunk when it is unloaded and on exit, not only the
+    /// dirty ones. Defaults to false.
+    ///
+    /// Set it if the generator doesn't give the same data every time, so a
+    /// chunk looks the same when the player returns.
+    pub fn with_force_save_generated(mut self, force_save_generated: bool) -> Self {
+        self.force_save_generated = force_save_generated;
+        self
+    }
+
+    /// Keeps the data of recently unloaded chunks in memory, so a chunk
+    /// requested again soon after doesn't read the store. Off by default.
+    ///
+    /// Clean chunks are encoded for the cache as they are unloaded, without
+    /// being saved. Hits and misses are counted in [`ChunkPersistenceStats`].
+    pub fn with_payload_cache(mut self, payload_cache: ChunkPayloadCache) -> Self {
+        self.payload_cache = Some(payload_cache);
+        self
+    }
+
+    /// Sets what happens to chunks whose stored data is corrupt. Defaults to
+    /// [`CorruptChunkPolicy::Regenerate`].
+    ///
+    /// Every saved chunk records a CRC32 of its stored bytes, after
+    /// compression, which is verified before the data is decompressed.
+    pub fn with_corrupt_policy(mut self, corrupt_policy: CorruptChunkPolicy) -> Self {
+        self.corrupt_policy = corrupt_policy;
+        self
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Serialize + DeserializeOwned + Send + Sync + 'static> ChunkPersistencePlugin<T> {
+    /// Creates a persistence plugin encoding chunk data with the serde format
+    /// `F`, e.g. [`Ron`]
+    pub fn serde<F: SerdeFormat>(store: impl ChunkStore) -> Self {
+        Self::new(
+            store,
+            SerdeCodec::<T, F>::encode,
+            SerdeCodec::<T, F>::decode,
+        )
+    }
+}
+
+impl<T: Send + Sync + 'static> Plugin for ChunkPersistencePlugin<T> {
+    fn build(&self, app: &mut App) {
+        let mut store = self.store.clone();
+        match app.world().get_resource::<ChunkStorePath>() {
+            Some(ChunkStorePath(path)) => match store.relocate(path) {
+                Some(relocated) => store = relocated,
+                None => warn!(
+                    "The chunk store can't be moved to {}, keeping its data",
+                    path.display()
+                ),
+            },
+            None => {
+                if let Some(root) = store.root() {
+                    app.insert_resource(ChunkStorePath(root.to_path_buf()));
+                }
+            }
+        }
+        app.init_resource::<SaveSlotSwitchers>()
+            .world_mut()
+            .resource_mut::<SaveSlotSwitchers>()
+            .0
+            .push(switch_store::<T>);
+        app.insert_resource(ChunkPersistence {
+            store,
+            encode: self.encode,
+            decoder: ChunkDecoder {
+                decode: self.decode,
+                payload_version: self.payload_version,
+                migrate: self.migrate,
+            },
+            compression: self.compression,
+            payload_version: self.payload_version,
+            writes: HashMap::new(),
+            encoded_bytes: 0,
+            stored_bytes: 0,
+            autosave: self.autosave,
+            force_save_generated: self.force_save_generated,
+            cache: self.payload_cache.map(PayloadCache::new),
+            corrupt_policy: self.corrupt_policy,
+        })
+        .init_resource::<ChunkPersistenceStats>()
+        .register_diagnostic(Diagnostic::new(PERSISTENCE_COMPRESSION_RATIO))
+        .add_observer(save_removed_chunk::<T>)
+        .add_observer(invalidate_regenerated_chunk::<T>)
+        .add_systems(
+            Last,
+            (
+                autosave_dirty_chunks::<T>.run_if(autosave_enabled::<T>),
+                poll_chunk_writes::<T>,
+                save_chunks_on_exit::<T>.run_if(on_message::<AppExit>),
+            )
+                .chain(),
+        );
+        #[cfg(feature = "reflect")]
+        app.register_type::<ChunkDirty>()
+            .register_type::<ChunkPersistenceStats>();
+    }
+}
+
+/// The store and codec used for `ChunkData<T>`, checked by the generation
+/// plugin before running the generator
+#[derive(Resource)]
+pub(crate) struct ChunkPersistence<T: Send + Sync + 'static> {
+    store: Arc<dyn ChunkStore>,
+    encode: EncodeFn<T>,
+    decoder: ChunkDecoder<T>,
+    compression: ChunkCompression,
+    payload_version: u32,
+    /// Stored data not known to be in the store yet, newer than what it holds
+    writes: HashMap<IVec3, PendingWrite>,
+    /// Size of the chunk data saved since the last measurement, before
+    /// compression
+    encoded_bytes: usize,
+    /// Size of the chunk data saved since the last measurement, as stored
+    stored_bytes: usize,
+    autosave: Option<Autosave>,
+    /// Whether clean chunks are saved as well
+    force_save_generated: bool,
+    cache: Option<PayloadCache>,
+    pub(crate) corrupt_policy: CorruptChunkPolicy,
+}
+
+/// When and how many dirty chunks are saved
+#[derive(Clone, Copy)]
+struct Autosave {
+    every: Duration,
+    max_chunks_per_pass: usize,
+    /// Time since the last pass finished
+    elapsed: Duration,
+}
+
+/// The latest data of a chunk waiting to be written to the store
+struct PendingWrite {
+    bytes: Arc<[u8]>,
+    /// The write in flight, which may be writing older bytes
+    task: Option<Task<io::Result<()>>>,
+    /// Whether `bytes` changed since the write in flight started
+    stale: bool,
+}
+
+impl<T: Send + Sync + 'static> ChunkPersistence<T> {
+    /// Encodes and compresses the data of one chunk and queues writing it,
+    /// logging failures. Returns whether the data could be encoded.
+    fn save(&mut self, pos: IVec3, data: &T) -> bool {
+        let Some((encoded_len, bytes)) = self.encode_record(pos, data) else {
+            return false;
+        };
+        self.encoded_bytes += encoded_len;
+        self.stored_bytes += bytes.len();
+        if let Some(cache) = &mut self.cache {
+            cache.insert(pos, bytes.clone());
+        }
+        match self.writes.get_mut(&pos) {
+            // Writes of one chunk never overlap, so the newest bytes win
+            Some(write) if write.task.is_some() => {
+                write.bytes = bytes;
+                write.stale = true;
+            }
+            _ => {
+                let task = Some(spawn_write(&self.store, pos, bytes.clone()));
+                self.writes.insert(
+                    pos,
+                    PendingWrite {
+                        bytes,
+                        task,
+                        stale: false,
+                    },
+                );
+            }
+        }
+        true
+    }
+
+    /// Encodes and compresses the data of one chunk, logging failures. Returns
+    /// the encoded size along with the bytes to store.
+    fn encode_record(&self, pos: IVec3, data: &T) -> Option<(usize, Arc<[u8]>)> {
+        match (self.encode)(data) {
+            Ok(encoded) => {
+                let bytes = pack_record(self.compression, self.payload_version, &encoded);
+                Some((encoded.len(), bytes.into()))
+            }
+            Err(error) => {
+                warn!("Encoding chunk at pos:{} failed: {}", pos, error);
+                None
+            }
+        }
+    }
+
+    /// Saves a chunk that is unloaded or ready on exit, skipping it if it is
+    /// clean and clean chunks aren't forced to be saved. Returns whether it
+    /// was skipped.
+    fn save_if_dirty(
+        &mut self,
+        pos: IVec3,
+        data: &T,
+        dirty: bool,
+        stats: &mut ChunkPersistenceStats,
+    ) -> bool {
+        if !dirty && !self.force_save_generated {
+            stats.chunks_skipped += 1;
+            return true;
+        }
+        if self.save(pos, data) {
+            stats.chunks_written += 1;
+        }
+        false
+    }
+
+    /// Blocks until every pending write is done, writing the data again where
+    /// it changed meanwhile or the write failed
+    fn flush_writes(&mut self) {
+        for (pos, write) in self.writes.drain() {
+            let written = write.task.map(block_on);
+            if (write.stale || !matches!(written, Some(Ok(()))))
+                && let Err(error) = self.store.save(pos, &write.bytes)
+            {
+                warn!("Saving chunk at pos:{} failed: {}", pos, error);
+            }
+        }
+    }
+
+    /// Points the store to another directory, if it keeps its data in one
+    fn relocate(&mut self, root: &Path) {
+        match self.store.relocate(root) {
+            Some(store) => self.store = store,
+            None => warn!(
+                "The chunk store can't be moved to {}, keeping its data",
+                root.display()
+            ),
+        }
+    }
+
+    /// Drops the cached data of a chunk, e.g. because it is regenerated
+    fn invalidate_cached(&mut self, pos: IVec3) {
+        if let Some(cache) = &mut self.cache {
+            cache.remove(pos);
+        }
+    }
+
+    /// Reads and decodes the data of one chunk on the [`IoTaskPool`], logging
+    /// failures.
+    ///
+    /// Data still waiting to be written is decoded right away instead, so the
+    /// latest save is always seen, even while the write is queued behind others.
+    /// So is data in the payload cache.
+    pub(crate) fn load(&mut self, pos: IVec3) -> ChunkRead<T> {
+        if let Some(write) = self.writes.get(&pos) {
+            return ChunkRead::Done(Some(self.decoder.decode(pos, &write.bytes)));
+        }
+        if let Some(bytes) = self.cache.as_mut().and_then(|cache| cache.get(pos)) {
+            return ChunkRead::Done(Some(self.decoder.decode(pos, &bytes)));
+        }
+        let store = self.store.clone();
+        let decoder = self.decoder;
+        ChunkRead::Task(IoTaskPool::get().spawn(async move { read_chunk(&*store, pos, decoder) }))
+    }
+}
+
+/// A pending lookup of a chunk in the store
+pub(crate) enum ChunkRead<T> {
+    /// Reading on the IoTaskPool
+    Task(Task<StoredChunk<T>>),
+    /// Known without reading, until taken
+    Done(Option<StoredChunk<T>>),
+}
+
+impl<T> ChunkRead<T> {
+    /// Returns the outcome once the lookup completed
+    pub(crate) fn poll(&mut self) -> Option<StoredChunk<T>> {
+        match self {
+            ChunkRead::Task(task) => check_ready(task),
+            ChunkRead::Done(stored) => stored.take(),
+        }
+    }
+}
+
+/// Turns stored chunks back into chunk data
+struct ChunkDecoder<T> {
+    decode: DecodeFn<T>,
+    payload_version: u32,
+    migrate: Option<MigrateFn>,
+}
+
+impl<T> Clone for ChunkDecoder<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ChunkDecoder<T> {}
+
+impl<T> ChunkDecoder<T> {
+    /// Decompresses, migrates and decodes a stored chunk, logging failures
+    fn decode(&self, pos: IVec3, record: &[u8]) -> StoredChunk<T> {
+        let (version, mut bytes) = match unpack_record(record) {
+            Ok(unpacked) => unpacked,
+            Err(error) => {
+                warn!("Unpacking chunk at pos:{} failed: {}", pos, error);
+                return StoredChunk::Corrupt {
+                    checksums: checksums(&error),
+                };
+            }
+        };
+        if version != self.payload_version {
+            let migrated = match self.migrate {
+                Some(migrate) if version < self.payload_version => migrate(version, &bytes),
+                _ => Err(GenerationError::new(format!(
+                    "no migration from payload version {} to {}",
+                    version, self.payload_version
+                ))),
+            };
+            match migrated {
+                Ok(migrated) => bytes = Cow::Owned(migrated),
+                Err(error) => {
+                    warn!("Migrating chunk at pos:{} failed: {}", pos, error);
+                    return StoredChunk::MigrationFailed { version };
+                }
+            }
+        }
+        match (self.decode)(&bytes) {
+            Ok(data) => StoredChunk::Loaded(data),
+            Err(error) => {
+                warn!("Decoding chunk at pos:{} failed: {}", pos, error);
+                StoredChunk::Corrupt { checksums: None }
+            }
+        }
+    }
+}
+
+/// Writes bytes to the store on the [`IoTaskPool`]
+fn spawn_write(store: &Arc<dyn ChunkStore>, pos: IVec3, bytes: Arc<[u8]>) -> Task<io::Result<()>> {
+    let store = store.clone();
+    IoTaskPool::get().spawn(async move { store.save(pos, &bytes) })
+}
+
+/// Reads and decodes the stored data of one chunk, logging failures.
+///
+/// Stores report damaged data with [`io::ErrorKind::InvalidData`].
+fn read_chunk<T>(store: &dyn ChunkStore, pos: IVec3, decoder: ChunkDecoder<T>) -> StoredChunk<T> {
+    if !store.exists(pos) {
+        return StoredChunk::Missing;
+    }
+    match store.load(pos) {
+        Ok(Some(bytes)) => decoder.decode(pos, &bytes),
+        Ok(None) => StoredChunk::Missing,
+        Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+            warn!("Stored chunk at pos:{} is damaged: {}", pos, error);
+            StoredChunk::Corrupt {
+                checksums: checksums(&error),
+            }
+        }
+        Err(error) => {
+            warn!("Loading chunk at pos:{} failed: {}", pos, error);
+            StoredChunk::Missing
+        }
+    }
+}
+
+/// The outcome of looking up a chunk in the store
+pub(crate) enum StoredChunk<T> {
+    /// Nothing usable is stored
+    Missing,
+    /// The stored data was decoded
+    Loaded(T),
+    /// The stored bytes could not be decoded, with the expected and found
+    /// checksums if they didn't match
+    Corrupt { checksums: Option<(u32, u32)> },
+    /// The stored bytes have a payload version that could not be migrated
+    MigrationFailed { version: u32 },
+}
+
+/// A requested chunk whose stored data is being read.
+///
+/// The chunk stays `Requested` meanwhile. Once the read completes, the stored
+/// data is applied and the chunk becomes `Ready`, or it is generated if nothing
+/// usable was stored. Despawning the chunk cancels the read.
+#[derive(Component)]
+pub struct LoadingFromDisk<T: Send + Sync + 'static>(pub(crate) ChunkRead<T>);
+
+/// Fired when stored chunk data could not be decoded, or doesn't match the
+/// checksum saved with it.
+///
+/// By default the chunk is generated instead, and its new data replaces the
+/// stored bytes once it is saved, see [`CorruptChunkPolicy`].
+#[derive(Message, Debug, Clone)]
+pub struct ChunkLoadCorrupt {
+    /// Position of the chunk in chunk coordinates
+    pub pos: IVec3,
+    /// The checksum saved with the data, if the data failed the checksum check
+    pub expected: Option<u32>,
+    /// The checksum of the data as it was read, if it failed the checksum check
+    pub found: Option<u32>,
+}
+
+/// What happens to a chunk whose stored data is corrupt, see
+/// [`ChunkPersistencePlugin::with_corrupt_policy`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum CorruptChunkPolicy {
+    /// Generate the chunk instead, replacing the stored bytes once it is saved
+    #[default]
+    Regenerate,
+    /// Mark the chunk as [`ChunkLoadState::Failed`](crate::ChunkLoadState::Failed)
+    /// without retrying, keeping the stored bytes for inspection
+    Fail,
+}
+
+/// Fired when stored chunk data has a payload version that could not be
+/// migrated, see [`ChunkPersistencePlugin::with_migration`].
+///
+/// The chunk is generated instead, and its new data replaces the stored bytes
+/// once it is saved.
+#[derive(Message, Debug, Clone)]
+pub struct ChunkMigrationFailed {
+    /// Position of the chunk in chunk coordinates
+    pub pos: IVec3,
+    /// The payload version of the stored bytes
+    pub version: u32,
+}
+
+/// Marks a chunk whose data changed since it was loaded or last saved.
+///
+/// Insert it after editing the [`ChunkData`] of a chunk, e.g. with
+/// [`ChunkDirtyCommands::mark_dirty`]. Only dirty chunks are saved when they
+/// are unloaded, and autosaving, see
+/// [`ChunkPersistencePlugin::with_autosave`], saves the chunk and removes the
+/// marker. Chunks loaded from the store are clean.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkDirty;
+
+/// Extension methods for marking edited chunks
+pub trait ChunkDirtyCommands {
+    /// Inserts [`ChunkDirty`], so the chunk's data is saved
+    fn mark_dirty(&mut self) -> &mut Self;
+}
+
+impl ChunkDirtyCommands for EntityCommands<'_> {
+    fn mark_dirty(&mut self) -> &mut Self {
+        self.try_insert(ChunkDirty)
+    }
+}
+
+/// Counts the chunks saved and skipped by the persistence plugins
+#[derive(Resource, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct ChunkPersistenceStats {
+    /// Chunks whose data was encoded and queued for writing
+    pub chunks_written: u64,
+    /// Clean chunks that were unloaded, or ready on exit, without saving
+    pub chunks_skipped: u64,
+    /// Chunk lookups answered by the payload cache, see
+    /// [`ChunkPersistencePlugin::with_payload_cache`]
+    pub cache_hits: u64,
+    /// Chunk lookups that missed the payload cache and read the store
+    pub cache_misses: u64,
+}
+
+/// A data format for [`SerdeCodec`].
+#[cfg(feature = "serde")]
+pub trait SerdeFormat: Send + Sync + 'static {
+    /// Serializes a value into bytes
+    fn serialize<T: Serialize>(value: &T) -> GenerationResult<Vec<u8>>;
+
+    /// Deserializes a value from bytes
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> GenerationResult<T>;
+}
+
+/// The human-readable [RON](https://github.com/ron-rs/ron) format, handy for
+/// inspecting saved chunks.
+#[cfg(feature = "ron")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ron;
+
+#[cfg(feature = "ron")]
+impl SerdeFormat for Ron {
+    fn serialize<T: Serialize>(value: &T) -> GenerationResult<Vec<u8>> {
+        ron::to_string(value)
+            .map(String::into_bytes)
+            .map_err(|error| GenerationError::new(error.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> GenerationResult<T> {
+        ron::de::from_bytes(bytes).map_err(|error| GenerationError::new(error.to_string()))
+    }
+}
+
+/// The compact binary [bincode](https://github.com/bincode-org/bincode) format,
+/// for smaller saves.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl SerdeFormat for Bincode {
+    fn serialize<T: Serialize>(value: &T) -> GenerationResult<Vec<u8>> {
+        bincode::serialize(value).map_err(|error| GenerationError::new(error.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> GenerationResult<T> {
+        bincode::deserialize(bytes).map_err(|error| GenerationError::new(error.to_string()))
+    }
+}
+
+/// Encodes chunk data of type `T` with the serde format `F`.
+///
+/// Its functions fit [`ChunkPersistencePlugin::new`], or use
+/// [`ChunkPersistencePlugin::serde`] directly.
+#[cfg(feature = "serde")]
+pub struct SerdeCodec<T, F>(PhantomData<fn() -> (T, F)>);
+
+#[cfg(feature = "serde")]
+impl<T: Serialize + DeserializeOwned, F: SerdeFormat> SerdeCodec<T, F> {
+    /// Serializes chunk data
+    pub fn encode(data: &T) -> GenerationResult<Vec<u8>> {
+        F::serialize(data)
+    }
+
+    /// Deserializes chunk data
+    pub fn decode(bytes: &[u8]) -> GenerationResult<T> {
+        F::deserialize(bytes)
+    }
+}
+
+/// Saves the data of a dirty ready chunk that is being despawned or loses its
+/// data
+fn save_removed_chunk<T: Send + Sync + 'static>(
+    remove: On<Remove, ChunkData<T>>,
+    mut persistence: ResMut<ChunkPersistence<T>>,
+    mut stats: ResMut<ChunkPersistenceStats>,
+    chunks: Query<
+        (
+            &ChunkPos,
+            &ChunkData<T>,
+            Has<ChunkDirty>,
+            Option<&ChunkLayer>,
+        ),
+        ReadyChunks,
+    >,
+) {
+    let Ok((ChunkPos(pos), ChunkData(data), dirty, layer)) = chunks.get(remove.entity) else {
+        return;
+    };
+    if is_layered(layer) {
+        return;
+    }
+    let skipped = persistence.save_if_dirty(*pos, data, dirty, &mut stats);
+    if skipped
+        && persistence.cache.is_some()
+        && let Some((_, bytes)) = persistence.encode_record(*pos, data)
+        && let Some(cache) = &mut persistence.cache
+    {
+        cache.insert(*pos, bytes);
+    }
+}
+
+/// Whether a chunk is on a [`ChunkLayer`] other than the default one, which
+/// stores don't keep apart
+fn is_layered(layer: Option<&ChunkLayer>) -> bool {
+    layer.is_some_and(|layer| layer.0 != 0)
+}
+
+/// Drops the cached data of a chunk that is about to be generated again
+fn invalidate_regenerated_chunk<T: Send + Sync + 'static>(
+    insert: On<Insert, GenerateFresh>,
+    mut persistence: ResMut<ChunkPersistence<T>>,
+    chunks: Query<(&ChunkPos, Option<&ChunkLayer>)>,
+) {
+    if let Ok((ChunkPos(pos), layer)) = chunks.get(insert.entity)
+        && !is_layered(layer)
+    {
+        persistence.invalidate_cached(*pos);
+    }
+}
+
+/// Checks if autosaving is set up for `ChunkData<T>`
+fn autosave_enabled<T: Send + Sync + 'static>(persistence: Res<ChunkPersistence<T>>) -> bool {
+    persistence.autosave.is_some()
+}
+
+/// Saves dirty chunks once the autosave interval elapsed, up to the per-pass
+/// budget each frame.
+///
+/// A chunk still being written, e.g. after it was unloaded and requested again,
+/// is written again once that write completes, so older data never lands last.
+fn autosave_dirty_chunks<T: Send + Sync + 'static>(
+    mut commands: Commands,
+    mut persistence: ResMut<ChunkPersistence<T>>,
+    mut stats: ResMut<ChunkPersistenceStats>,
+    time: Res<Time>,
+    dirty: Query<
+        (Entity, &ChunkPos, &ChunkData<T>, Option<&ChunkLayer>),
+        (With<ChunkDirty>, ReadyChunks),
+    >,
+) {
+    let Some(mut autosave) = persistence.autosave else {
+        return;
+    };
+    autosave.elapsed += time.delta();
+    if autosave.elapsed >= autosave.every {
+        let mut saved = 0;
+        for (entity, ChunkPos(pos), ChunkData(data), layer) in dirty.iter() {
+            if is_layered(layer) {
+                continue;
+            }
+            if saved == autosave.max_chunks_per_pass {
+                break;
+            }
+            saved += 1;
+            if persistence.save(*pos, data) {
+                stats.chunks_written += 1;
+                commands
+                    .entity(entity)
+                    .remove::<ChunkDirty>()
+                    .insert(ChunkPersistent);
+            }
+        }
+        if saved < autosave.max_chunks_per_pass {
+            autosave.elapsed = Duration::ZERO;
+        }
+    }
+    persistence.autosave = Some(autosave);
+}
+
+/// Finishes completed writes and starts writing data saved while a previous
+/// write of the same chunk was in flight
+fn poll_chunk_writes<T: Send + Sync + 'static>(
+    mut persistence: ResMut<ChunkPersistence<T>>,
+    mut stats: ResMut<ChunkPersistenceStats>,
+    mut diagnostics: Diagnostics,
+) {
+    let persistence = persistence.as_mut();
+    if let Some(cache) = &mut persistence.cache {
+        stats.cache_hits += cache.hits;
+        stats.cache_misses += cache.misses;
+        cache.hits = 0;
+        cache.misses = 0;
+    }
+    if persistence.stored_bytes > 0 {
+        let ratio = persistence.encoded_bytes as f64 / persistence.stored_bytes as f64;
+        diagnostics.add_measurement(&PERSISTENCE_COMPRESSION_RATIO, || ratio);
+        persistence.encoded_bytes = 0;
+        persistence.stored_bytes = 0;
+    }
+    let mut written = Vec::new();
+    for (pos, write) in persistence.writes.iter_mut() {
+        let Some(result) = write.task.as_mut().and_then(check_ready) else {
+            continue;
+        };
+        write.task = None;
+        if write.stale {
+            write.stale = false;
+            write.task = Some(spawn_write(&persistence.store, *pos, write.bytes.clone()));
+            continue;
+        }
+        match result {
+            Ok(()) => written.push(*pos),
+            // Kept, so loads still see the data, and retried on exit
+            Err(error) => warn!("Saving chunk at pos:{} failed: {}", pos, error),
+        }
+    }
+    for pos in written {
+        persistence.writes.remove(&pos);
+    }
+}
+
+/// Saves every dirty ready chunk and blocks until all pending writes are done,
+/// as tasks still running when the app exits would be cancelled
+fn save_chunks_on_exit<T: Send + Sync + 'static>(
+    mut persistence: ResMut<ChunkPersistence<T>>,
+    mut stats: ResMut<ChunkPersistenceStats>,
+    chunks: Query<
+        (
+            &ChunkPos,
+            &ChunkData<T>,
+            Has<ChunkDirty>,
+            Option<&ChunkLayer>,
+        ),
+        ReadyChunks,
+    >,
+) {
+    for (ChunkPos(pos), ChunkData(data), dirty, layer) in chunks.iter() {
+        if !is_layered(layer) {
+            persistence.save_if_dirty(*pos, data, dirty, &mut stats);
+        }
+    }
+    persistence.flush_writes();
+}
+
+/// The directory of the current save slot, shared by the stores of all
+/// persistence plugins.
+///
+/// Inserted with the directory of the first store that has one. Inserting it
+/// before adding the persistence plugins opens that slot instead. Change it
+/// with [`switch_save_slot`], which points every store to the new value.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Deref)]
+pub struct ChunkStorePath(pub PathBuf);
+
+/// Switches the stores of all persistence plugins to another save slot
+/// directory.
+///
+/// All chunks are despawned first, so dirty chunks are saved to the current
+/// slot, and every pending write is finished before the stores are repointed,
+/// so no data of one slot ends up in another. Payload caches are cleared.
+/// Loaders then load the chunks of the new slot. Stores that don't keep their
+/// data in a directory, see [`ChunkStore::relocate`], keep using their data.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn load_slot_2(mut commands: Commands) {
+///     commands.queue(|world: &mut World| switch_save_slot(world, "saves/slot_2"));
+/// }
+/// ```
+pub fn switch_save_slot(world: &mut World, path: impl Into<PathBuf>) {
+    let path = path.into();
+    let chunks: Vec<Entity> = world
+        .query_filtered::<Entity, With<Chunk>>()
+        .iter(world)
+        .collect();
+    for entity in chunks {
+        world.despawn(entity);
+    }
+    world.insert_resource(ChunkStorePath(path));
+    let switchers = world
+        .get_resource::<SaveSlotSwitchers>()
+        .map(|switchers| switchers.0.clone())
+        .unwrap_or_default();
+    for switch in switchers {
+        switch(world);
+    }
+}
+
+/// Repoints the store of every persistence plugin, see [`switch_save_slot`]
+#[derive(Resource, Default)]
+struct SaveSlotSwitchers(Vec<fn(&mut World)>);
+
+/// Finishes the writes of `ChunkData<T>` and repoints its store to the
+/// [`ChunkStorePath`]
+fn switch_store<T: Send + Sync + 'static>(world: &mut World) {
+    let path = world.resource::<ChunkStorePath>().0.clone();
+    let mut persistence = world.resource_mut::<ChunkPersistence<T>>();
+    persistence.flush_writes();
+    if let Some(cache) = &mut persistence.cache {
+        cache.clear();
+    }
+    persistence.relocate(&path);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{
+        ChunkLoadState, ChunkyPlugin,
+        chunk_generation::{ChunkGenerationPlugin, ChunkGenerator, GenerationContext},
+        tests::{app, test_dir, update_until},
+    };
+
+    /// Keeps chunks in memory, shared with its clones
+    #[derive(Clone, Default)]
+    struct MemoryStore(Arc<Mutex<HashMap<IVec3, Vec<u8>>>>);
+
+    impl ChunkStore for MemoryStore {
+        fn save(&self, pos: IVec3, bytes: &[u8]) -> io::Result<()> {
+            self.0.lock().unwrap().insert(pos, bytes.to_vec());
+            Ok(())
+        }
+
+        fn load(&self, pos: IVec3) -> io::Result<Option<Vec<u8>>> {
+            Ok(self.0.lock().unwrap().get(&pos).cloned())
+        }
+
+        fn exists(&self, pos: IVec3) -> bool {
+            self.0.lock().unwrap().contains_key(&pos)
+        }
+    }
+
+    #[cfg(any(feature = "ron", feature = "bincode"))]
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Column {
+        height: u16,
+        blocks: Vec<u8>,
+    }
+
+    #[cfg(any(feature = "ron", feature = "bincode"))]
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Payload {
+        biome: String,
+        columns: Vec<Column>,
+        light: Option<Vec<u8>>,
+    }
+
+    #[cfg(any(feature = "ron", feature = "bincode"))]
+    fn round_trip<F: SerdeFormat>() {
+        let payload = Payload {
+            biome: "forest".into(),
+            columns: (0..4)
+                .map(|height| Column {
+                    height,
+                    blocks: vec![height as u8; height as usize],
+                })
+                .collect(),
+            light: Some(vec![0, 15, 255]),
+        };
+        let encoded = SerdeCodec::<Payload, F>::encode(&payload).unwrap();
+        assert_eq!(SerdeCodec::<Payload, F>::decode(&encoded).unwrap(), payload);
+        assert!(SerdeCodec::<Payload, F>::decode(&encoded[..encoded.len() / 2]).is_err());
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn ron_round_trips() {
+        round_trip::<Ron>();
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn bincode_round_trips() {
+        round_trip::<Bincode>();
+    }
+
+    /// Generates chunks without blocks
+    struct EmptyGenerator;
+
+    impl ChunkGenerator for EmptyGenerator {
+        type Output = Vec<u8>;
+
+        fn generate(&self, _pos: IVec3, _ctx: GenerationContext) -> GenerationResult<Vec<u8>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn undecodable_chunks_are_generated() {
+        let store = MemoryStore::default();
+        // A valid record around data the codec can't read
+        store
+            .save(
+                IVec3::ZERO,
+                &pack_record(ChunkCompression::None, 0, &[1, 2]),
+            )
+            .unwrap();
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(EmptyGenerator),
+            ChunkPersistencePlugin::new(
+                store,
+                |data: &Vec<u8>| Ok(data.clone()),
+                |bytes: &[u8]| match bytes.len() {
+                    4.. => Ok(bytes.to_vec()),
+                    _ => Err(GenerationError::new("truncated")),
+                },
+            ),
+        ));
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLoadState::Requested))
+            .id();
+
+        let mut corrupt = Vec::new();
+        for _ in 0..100 {
+            app.update();
+            corrupt.extend(
+                app.world_mut()
+                    .resource_mut::<Messages<ChunkLoadCorrupt>>()
+                    .drain()
+                    .map(|corrupt| (corrupt.pos, corrupt.expected)),
+            );
+            if app.world().get::<ChunkLoadState>(chunk) == Some(&ChunkLoadState::Ready) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(corrupt, [(IVec3::ZERO, None)]);
+        let data = app.world().get::<ChunkData<Vec<u8>>>(chunk);
+        assert_eq!(data.map(|data| data.0.len()), Some(0));
+    }
+
+    #[test]
+    fn stored_chunks_are_persistent() {
+        let store = MemoryStore::default();
+        store
+            .save(
+                IVec3::ZERO,
+                &pack_record(ChunkCompression::None, 0, &[1, 2, 3, 4]),
+            )
+            .unwrap();
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(EmptyGenerator),
+            ChunkPersistencePlugin::new(
+                store,
+                |data: &Vec<u8>| Ok(data.clone()),
+                |bytes: &[u8]| Ok(bytes.to_vec()),
+            ),
+        ));
+        let stored = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLoadState::Requested))
+            .id();
+        let generated = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::X), ChunkLoadState::Requested))
+            .id();
+        for _ in 0..100 {
+            app.update();
+            let ready =
+                |entity| app.world().get::<ChunkLoadState>(entity) == Some(&ChunkLoadState::Ready);
+            if ready(stored) && ready(generated) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let stored = app.world().entity(stored);
+        assert_eq!(
+            stored
+                .get::<ChunkData<Vec<u8>>>()
+                .map(|data| data.0.clone()),
+            Some(vec![1, 2, 3, 4])
+        );
+        assert!(stored.contains::<ChunkPersistent>());
+        assert!(!app.world().entity(generated).contains::<ChunkPersistent>());
+    }
+
+    /// Fills chunks with their x coordinate
+    struct PosGenerator;
+
+    impl ChunkGenerator for PosGenerator {
+        type Output = Vec<u8>;
+
+        fn generate(&self, pos: IVec3, _ctx: GenerationContext) -> GenerationResult<Vec<u8>> {
+            Ok(vec![pos.x as u8; 16])
+        }
+    }
+
+    /// Persists `Vec<u8>` chunk data as is
+    fn raw_persistence(store: impl ChunkStore) -> ChunkPersistencePlugin<Vec<u8>> {
+        ChunkPersistencePlugin::new(
+            store,
+            |data: &Vec<u8>| Ok(data.clone()),
+            |bytes: &[u8]| Ok(bytes.to_vec()),
+        )
+    }
+
+    fn is_ready(world: &World, entity: Entity) -> bool {
+        world.get::<ChunkLoadState>(entity) == Some(&ChunkLoadState::Ready)
+    }
+
+    fn writes_done(world: &World) -> bool {
+        world
+            .resource::<ChunkPersistence<Vec<u8>>>()
+            .writes
+            .is_empty()
+    }
+
+    fn chunk_data(world: &World, entity: Entity) -> Option<Vec<u8>> {
+        world
+            .get::<ChunkData<Vec<u8>>>(entity)
+            .map(|data| data.0.clone())
+    }
+
+    #[test]
+    fn unloaded_chunks_load_back_identical_after_a_restart() {
+        let dir = test_dir("restart");
+        let generated = {
+            let mut app = app((
+                ChunkyPlugin::default(),
+                ChunkGenerationPlugin::new(PosGenerator),
+                raw_persistence(FilesystemChunkStore::new(&dir)).with_force_save_generated(true),
+            ));
+            let chunk = app
+                .world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::X * 3), ChunkLoadState::Requested))
+                .id();
+            update_until(&mut app, |world| is_ready(world, chunk));
+            let generated = chunk_data(app.world(), chunk);
+            app.world_mut().despawn(chunk);
+            update_until(&mut app, writes_done);
+            generated
+        };
+
+        // Generating would give empty data, so it can only come from the files
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(EmptyGenerator),
+            raw_persistence(FilesystemChunkStore::new(&dir)),
+        ));
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::X * 3), ChunkLoadState::Requested))
+            .id();
+        update_until(&mut app, |world| is_ready(world, chunk));
+
+        assert_eq!(generated, Some(vec![3; 16]));
+        assert_eq!(chunk_data(app.world(), chunk), generated);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Unloads a clean and a dirty chunk and returns the written and skipped
+    /// counts along with the stored positions
+    fn unload_clean_and_dirty(force_save_generated: bool) -> (u64, u64, Vec<IVec3>) {
+        let store = MemoryStore::default();
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(PosGenerator),
+            raw_persistence(store.clone()).with_force_save_generated(force_save_generated),
+        ));
+        let [clean, dirty] = [IVec3::X, IVec3::Y].map(|pos| {
+            app.world_mut()
+                .spawn((Chunk, ChunkPos(pos), ChunkLoadState::Requested))
+                .id()
+        });
+        update_until(&mut app, |world| {
+            is_ready(world, clean) && is_ready(world, dirty)
+        });
+        app.world_mut().entity_mut(dirty).insert(ChunkDirty);
+        app.world_mut().despawn(clean);
+        app.world_mut().despawn(dirty);
+        update_until(&mut app, writes_done);
+
+        let stats = app.world().resource::<ChunkPersistenceStats>();
+        let mut stored: Vec<_> = store.0.lock().unwrap().keys().copied().collect();
+        stored.sort_by_key(|pos| pos.to_array());
+        (stats.chunks_written, stats.chunks_skipped, stored)
+    }
+
+    #[test]
+    fn only_dirty_chunks_are_saved_unless_forced() {
+        assert_eq!(unload_clean_and_dirty(false), (1, 1, vec![IVec3::Y]));
+        assert_eq!(
+            unload_clean_and_dirty(true),
+            (2, 0, vec![IVec3::Y, IVec3::X])
+        );
+    }
+
+    #[cfg(all(feature = "chunk_loader", feature = "chunk_unloader"))]
+    #[test]
+    fn edits_survive_walking_away_and_back() {
+        use crate::{
+            ChunkManager, chunk_loader::ChunkLoader, chunk_unloader::ChunkUnloadByDistance,
+        };
+
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(PosGenerator),
+            raw_persistence(MemoryStore::default()),
+        ));
+        app.insert_resource(ChunkUnloadByDistance);
+        let loader = app
+            .world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)))
+            .id();
+        let chunk_at_origin =
+            |world: &World| world.resource::<ChunkManager>().get_chunk(&IVec3::ZERO);
+        update_until(&mut app, |world| {
+            chunk_at_origin(world).is_some_and(|chunk| is_ready(world, chunk))
+        });
+
+        let chunk = chunk_at_origin(app.world()).unwrap();
+        app.world_mut()
+            .get_mut::<ChunkData<Vec<u8>>>(chunk)
+            .unwrap()
+            .0[0] = 42;
+        app.world_mut().commands().entity(chunk).mark_dirty();
+
+        let move_loader = |app: &mut App, x: f32| {
+            app.world_mut()
+                .entity_mut(loader)
+                .insert(Transform::from_xyz(x, 0.0, 0.0));
+        };
+        move_loader(&mut app, 100.0);
+        update_until(&mut app, |world| chunk_at_origin(world).is_none());
+        move_loader(&mut app, 0.0);
+        update_until(&mut app, |world| {
+            chunk_at_origin(world).is_some_and(|chunk| is_ready(world, chunk))
+        });
+
+        let chunk = chunk_at_origin(app.world()).unwrap();
+        let mut edited = vec![0; 16];
+        edited[0] = 42;
+        assert_eq!(chunk_data(app.world(), chunk), Some(edited));
+        assert!(!app.world().entity(chunk).contains::<ChunkDirty>());
+    }
+
+    #[test]
+    fn older_payload_versions_are_migrated() {
+        let store = MemoryStore::default();
+        // Version 1 misspelled the field that version 2 calls `height`
+        for (pos, version) in [(IVec3::ZERO, 1), (IVec3::X, 2), (IVec3::Y, 3)] {
+            let record = match version {
+                1 => pack_record(ChunkCompression::None, 1, b"hight:7"),
+                _ => pack_record(ChunkCompression::None, version, b"height:7"),
+            };
+            store.save(pos, &record).unwrap();
+        }
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(EmptyGenerator),
+            ChunkPersistencePlugin::new(
+                store,
+                |data: &Vec<u8>| Ok([b"height:", &data[..]].concat()),
+                |bytes: &[u8]| match bytes.strip_prefix(b"height:") {
+                    Some(height) => Ok(height.to_vec()),
+                    None => Err(GenerationError::new("no height")),
+                },
+            )
+            .with_payload_version(2)
+            .with_migration(|version, bytes| {
+                match (version, bytes.strip_prefix(b"hight:")) {
+                    (1, Some(height)) => Ok([b"height:", height].concat()),
+                    _ => Err(GenerationError::new("unknown format")),
+                }
+            }),
+        ));
+        let chunks = [IVec3::ZERO, IVec3::X, IVec3::Y].map(|pos| {
+            app.world_mut()
+                .spawn((Chunk, ChunkPos(pos), ChunkLoadState::Requested))
+                .id()
+        });
+
+        let mut migration_failed = Vec::new();
+        for _ in 0..100 {
+            app.update();
+            migration_failed.extend(
+                app.world_mut()
+                    .resource_mut::<Messages<ChunkMigrationFailed>>()
+                    .drain()
+                    .map(|failed| (failed.pos, failed.version)),
+            );
+            if chunks.iter().all(|&chunk| is_ready(app.world(), chunk)) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(chunk_data(app.world(), chunks[0]), Some(b"7".to_vec()));
+        assert_eq!(chunk_data(app.world(), chunks[1]), Some(b"7".to_vec()));
+        // Newer versions can't be migrated, so that chunk is generated
+        assert_eq!(chunk_data(app.world(), chunks[2]), Some(Vec::new()));
+        assert_eq!(migration_failed, [(IVec3::Y, 3)]);
+    }
+
+    #[test]
+    fn autosave_saves_the_dirty_chunks_once_the_interval_passed() {
+        use bevy::time::TimeUpdateStrategy;
+
+        let store = MemoryStore::default();
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(PosGenerator),
+            raw_persistence(store.clone()).with_autosave(Duration::from_secs(1), 10),
+        ));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+        let chunks = [0, 1, 2, 3].map(|x| {
+            app.world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::X * x), ChunkLoadState::Requested))
+                .id()
+        });
+        update_until(&mut app, |world| {
+            chunks.iter().all(|&chunk| is_ready(world, chunk))
+        });
+
+        for &chunk in &chunks[..3] {
+            app.world_mut().entity_mut(chunk).insert(ChunkDirty);
+        }
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            200,
+        )));
+        for _ in 0..4 {
+            app.update();
+        }
+        assert_eq!(
+            app.world()
+                .resource::<ChunkPersistenceStats>()
+                .chunks_written,
+            0
+        );
+
+        app.update();
+        update_until(&mut app, writes_done);
+        let mut saved: Vec<_> = store.0.lock().unwrap().keys().map(|pos| pos.x).collect();
+        saved.sort();
+        assert_eq!(saved, [0, 1, 2]);
+        assert_eq!(
+            app.world()
+                .resource::<ChunkPersistenceStats>()
+                .chunks_written,
+            3
+        );
+        assert!(
+            chunks
+                .iter()
+                .all(|&chunk| !app.world().entity(chunk).contains::<ChunkDirty>())
+        );
+    }
+
+    #[test]
+    fn autosave_without_a_budget_saves_every_pass_in_one_frame() {
+        use bevy::time::TimeUpdateStrategy;
+
+        let store = MemoryStore::default();
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(PosGenerator),
+            raw_persistence(store.clone()).with_autosave(Duration::from_millis(200), 0),
+        ));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+        let chunks = [0, 1, 2].map(|x| {
+            app.world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::X * x), ChunkLoadState::Requested))
+                .id()
+        });
+        update_until(&mut app, |world| {
+            chunks.iter().all(|&chunk| is_ready(world, chunk))
+        });
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            200,
+        )));
+
+        // Each pass saves every dirty chunk and starts the next interval
+        for pass in 1..=2 {
+            for &chunk in &chunks {
+                app.world_mut().entity_mut(chunk).insert(ChunkDirty);
+            }
+            app.update();
+            assert!(
+                chunks
+                    .iter()
+                    .all(|&chunk| !app.world().entity(chunk).contains::<ChunkDirty>())
+            );
+            update_until(&mut app, writes_done);
+            assert_eq!(
+                app.world()
+                    .resource::<ChunkPersistenceStats>()
+                    .chunks_written,
+                3 * pass
+            );
+        }
+    }
+
+    /// A [`MemoryStore`] counting how often it is read
+    #[derive(Clone, Default)]
+    struct CountingStore {
+        store: MemoryStore,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl ChunkStore for CountingStore {
+        fn save(&self, pos: IVec3, bytes: &[u8]) -> io::Result<()> {
+            self.store.save(pos, bytes)
+        }
+
+        fn load(&self, pos: IVec3) -> io::Result<Option<Vec<u8>>> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.store.load(pos)
+        }
+
+        fn exists(&self, pos: IVec3) -> bool {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.store.exists(pos)
+        }
+    }
+
+    #[test]
+    fn the_payload_cache_answers_until_it_is_full() {
+        use crate::chunk_payload_cache::CacheCapacity;
+
+        let store = CountingStore::default();
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(PosGenerator),
+            raw_persistence(store.clone())
+                .with_payload_cache(ChunkPayloadCache::new(CacheCapacity::Entries(1))),
+        ));
+        let load = |app: &mut App, x: i32| {
+            let chunk = app
+                .world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::X * x), ChunkLoadState::Requested))
+                .id();
+            update_until(app, |world| is_ready(world, chunk));
+            chunk
+        };
+        let first = load(&mut app, 1);
+        let second = load(&mut app, 2);
+        let stats = |app: &App| {
+            let stats = app.world().resource::<ChunkPersistenceStats>();
+            (stats.cache_hits, stats.cache_misses)
+        };
+        assert_eq!(stats(&app), (0, 2));
+
+        // Clean chunks are only cached, so the data can't come from the store
+        app.world_mut().despawn(second);
+        let reads = store.reads.load(Ordering::Relaxed);
+        let second = load(&mut app, 2);
+        assert_eq!(store.reads.load(Ordering::Relaxed), reads);
+        assert_eq!(stats(&app), (1, 2));
+        assert_eq!(chunk_data(app.world(), second), Some(vec![2; 16]));
+
+        // The second chunk evicts the first one from the single entry
+        for chunk in [first, second] {
+            app.world_mut().entity_mut(chunk).insert(ChunkDirty);
+            app.world_mut().despawn(chunk);
+        }
+        update_until(&mut app, writes_done);
+        let first = load(&mut app, 1);
+        assert!(store.reads.load(Ordering::Relaxed) > reads);
+        assert_eq!(stats(&app), (1, 3));
+        assert_eq!(chunk_data(app.world(), first), Some(vec![1; 16]));
+    }
+
+    /// A [`MemoryStore`] whose reads and writes wait until it is opened, or
+    /// for a second at most
+    #[derive(Clone, Default)]
+    struct SlowStore {
+        store: MemoryStore,
+        open: Arc<AtomicBool>,
+    }
+
+    impl SlowStore {
+        fn wait(&self) {
+            let started = std::time::Instant::now();
+            while !self.open.load(Ordering::Relaxed) && started.elapsed() < Duration::from_secs(1) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    impl ChunkStore for SlowStore {
+        fn save(&self, pos: IVec3, bytes: &[u8]) -> io::Result<()> {
+            self.wait();
+            self.store.save(pos, bytes)
+        }
+
+        fn load(&self, pos: IVec3) -> io::Result<Option<Vec<u8>>> {
+            self.wait();
+            self.store.load(pos)
+        }
+
+        fn exists(&self, pos: IVec3) -> bool {
+            self.wait();
+            self.store.exists(pos)
+        }
+    }
+
+    #[test]
+    fn a_slow_store_never_blocks_the_main_thread() {
+        let store = SlowStore::default();
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(PosGenerator),
+            raw_persistence(store.clone()),
+        ));
+        let spawn = |app: &mut App| {
+            app.world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::X * 5), ChunkLoadState::Requested))
+                .id()
+        };
+        let chunk = spawn(&mut app);
+        let started = std::time::Instant::now();
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert!(!is_ready(app.world(), chunk));
+
+        store.open.store(true, Ordering::Relaxed);
+        update_until(&mut app, |world| is_ready(world, chunk));
+        assert_eq!(chunk_data(app.world(), chunk), Some(vec![5; 16]));
+
+        // Read back while the write is stuck, the chunk gets the written data
+        store.open.store(false, Ordering::Relaxed);
+        app.world_mut()
+            .get_mut::<ChunkData<Vec<u8>>>(chunk)
+            .unwrap()
+            .0[0] = 42;
+        app.world_mut().entity_mut(chunk).insert(ChunkDirty);
+        app.world_mut().despawn(chunk);
+        let chunk = spawn(&mut app);
+        update_until(&mut app, |world| is_ready(world, chunk));
+        let mut edited = vec![5; 16];
+        edited[0] = 42;
+        assert!(!writes_done(app.world()));
+        assert_eq!(chunk_data(app.world(), chunk), Some(edited.clone()));
+
+        // So does reading it back from the store once it is written
+        store.open.store(true, Ordering::Relaxed);
+        app.world_mut().despawn(chunk);
+        update_until(&mut app, writes_done);
+        let chunk = spawn(&mut app);
+        update_until(&mut app, |world| is_ready(world, chunk));
+        assert_eq!(chunk_data(app.world(), chunk), Some(edited));
+    }
+
+    #[test]
+    fn a_flipped_byte_is_caught_and_the_chunk_regenerated() {
+        let dir = test_dir("flipped_byte");
+        let store = FilesystemChunkStore::new(&dir);
+        store
+            .save(IVec3::X, &pack_record(ChunkCompression::None, 0, &[9; 16]))
+            .unwrap();
+        let path = store.chunk_path(IVec3::X);
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(PosGenerator),
+            raw_persistence(store),
+        ));
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::X), ChunkLoadState::Requested))
+            .id();
+        let mut corrupt = Vec::new();
+        for _ in 0..100 {
+            app.update();
+            corrupt.extend(
+                app.world_mut()
+                    .resource_mut::<Messages<ChunkLoadCorrupt>>()
+                    .drain(),
+            );
+            if is_ready(app.world(), chunk) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let [corrupt] = &corrupt[..] else {
+            panic!("expected one corrupt chunk, got {}", corrupt.len());
+        };
+        assert_eq!(corrupt.pos, IVec3::X);
+        assert!(corrupt.expected.is_some() && corrupt.expected != corrupt.found);
+        assert_eq!(chunk_data(app.world(), chunk), Some(vec![1; 16]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn switching_slots_loads_the_data_of_the_new_slot() {
+        let dirs = [test_dir("slot_a"), test_dir("slot_b")];
+        for (dir, block) in dirs.iter().zip([1, 2]) {
+            FilesystemChunkStore::new(dir)
+                .save(
+                    IVec3::X,
+                    &pack_record(ChunkCompression::None, 0, &[block; 16]),
+                )
+                .unwrap();
+        }
+
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(EmptyGenerator),
+            raw_persistence(FilesystemChunkStore::new(&dirs[0])),
+        ));
+        let load = |app: &mut App| {
+            let chunk = app
+                .world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::X), ChunkLoadState::Requested))
+                .id();
+            update_until(app, |world| is_ready(world, chunk));
+            chunk_data(app.world(), chunk)
+        };
+        assert_eq!(load(&mut app), Some(vec![1; 16]));
+
+        switch_save_slot(app.world_mut(), &dirs[1]);
+        assert_eq!(**app.world().resource::<ChunkStorePath>(), dirs[1]);
+        assert_eq!(load(&mut app), Some(vec![2; 16]));
+        for dir in dirs {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    /// Fills chunks with long runs of equal bytes
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    struct RunGenerator;
+
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    impl ChunkGenerator for RunGenerator {
+        type Output = Vec<u8>;
+
+        fn generate(&self, pos: IVec3, _ctx: GenerationContext) -> GenerationResult<Vec<u8>> {
+            Ok((0..4096).map(|i| (i / 64) as u8 ^ pos.x as u8).collect())
+        }
+    }
+
+    /// Saves a generated chunk with `compression`, loads it back in a new app
+    /// and returns the size of the stored record
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    fn compressed_round_trip(compression: ChunkCompression) -> usize {
+        let store = MemoryStore::default();
+        let generated = {
+            let mut app = app((
+                ChunkyPlugin::default(),
+                ChunkGenerationPlugin::new(RunGenerator),
+                raw_persistence(store.clone())
+                    .with_compression(compression)
+                    .with_force_save_generated(true),
+            ));
+            let chunk = app
+                .world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::X), ChunkLoadState::Requested))
+                .id();
+            update_until(&mut app, |world| is_ready(world, chunk));
+            let generated = chunk_data(app.world(), chunk);
+            app.world_mut().despawn(chunk);
+            update_until(&mut app, writes_done);
+            generated
+        };
+
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(EmptyGenerator),
+            raw_persistence(store.clone()).with_compression(compression),
+        ));
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::X), ChunkLoadState::Requested))
+            .id();
+        update_until(&mut app, |world| is_ready(world, chunk));
+        assert_eq!(generated.as_ref().map(Vec::len), Some(4096));
+        assert_eq!(chunk_data(app.world(), chunk), generated);
+        store.load(IVec3::X).unwrap().unwrap().len()
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn lz4_round_trips_smaller() {
+        let uncompressed = compressed_round_trip(ChunkCompression::None);
+        assert!(compressed_round_trip(ChunkCompression::Lz4) < uncompressed);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trips_smaller() {
+        let uncompressed = compressed_round_trip(ChunkCompression::None);
+        assert!(compressed_round_trip(ChunkCompression::Zstd) < uncompressed);
+    }
+}
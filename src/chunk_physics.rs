@@ -0,0 +1,173 @@
+//! Colliders matching the bounds of chunks, for any physics engine.
+//!
+//! The crate does not depend on a physics engine. Instead, implement
+//! [`ChunkColliderBuilder`] to create your engine's collider and add a
+//! [`ChunkColliderPlugin`]. Once a chunk becomes `Ready`, a child entity with
+//! the collider and a [`ChunkCollider`] marker is spawned at the chunk's
+//! center, so a cuboid collider, which is centered on its entity, covers the
+//! chunk exactly. Despawning the chunk despawns its collider.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! // Stands in for e.g. `avian3d::prelude::Collider`
+//! #[derive(Component)]
+//! struct Collider {
+//!     half_extents: Vec3,
+//! }
+//!
+//! struct ChunkBounds;
+//!
+//! impl ChunkColliderBuilder for ChunkBounds {
+//!     type Collider = Collider;
+//!
+//!     fn build(&self, _chunk_pos: IVec3, chunk_size: Vec3) -> Collider {
+//!         // With avian3d: `Collider::cuboid(chunk_size.x, chunk_size.y, chunk_size.z)`
+//!         Collider {
+//!             half_extents: chunk_size / 2.0,
+//!         }
+//!     }
+//! }
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(ChunkyPlugin::default())
+//!     .add_plugins(ChunkColliderPlugin::new(ChunkBounds));
+//! ```
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::{ChunkManager, ChunkPos, ChunkySystems, NewlyReadyChunks};
+
+/// Creates the collider of a chunk for a physics engine.
+pub trait ChunkColliderBuilder: Send + Sync + 'static {
+    /// The components making up the collider
+    type Collider: Bundle;
+
+    /// Creates the collider for the chunk at `chunk_pos`, centered on its
+    /// entity and sized `chunk_size` in world units
+    fn build(&self, chunk_pos: IVec3, chunk_size: Vec3) -> Self::Collider;
+}
+
+/// Spawns a collider built by a [`ChunkColliderBuilder`] for every ready chunk.
+pub struct ChunkColliderPlugin<B: ChunkColliderBuilder> {
+    builder: Arc<B>,
+}
+
+impl<B: ChunkColliderBuilder> ChunkColliderPlugin<B> {
+    /// Creates a plugin spawning colliders built by `builder`
+    pub fn new(builder: B) -> Self {
+        Self {
+            builder: Arc::new(builder),
+        }
+    }
+}
+
+impl<B: ChunkColliderBuilder> Plugin for ChunkColliderPlugin<B> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ActiveColliderBuilder(self.builder.clone()))
+            .add_systems(
+                Update,
+                spawn_chunk_colliders::<B>.after(ChunkySystems::PostGenerate),
+            );
+        #[cfg(feature = "reflect")]
+        app.register_type::<ChunkCollider>();
+    }
+}
+
+/// Marks the child entity holding the collider of a chunk.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkCollider;
+
+/// The builder used for chunk colliders
+#[derive(Resource)]
+struct ActiveColliderBuilder<B: ChunkColliderBuilder>(Arc<B>);
+
+/// Spawns the collider of every chunk that just became ready, unless it already
+/// has one, e.g. after being regenerated
+fn spawn_chunk_colliders<B: ChunkColliderBuilder>(
+    mut commands: Commands,
+    builder: Res<ActiveColliderBuilder<B>>,
+    chunk_manager: Res<ChunkManager>,
+    chunks: Query<(Entity, &ChunkPos, Option<&Children>), NewlyReadyChunks>,
+    colliders: Query<(), With<ChunkCollider>>,
+) {
+    let chunk_size = chunk_manager.get_size();
+    for (entity, ChunkPos(chunk_pos), children) in chunks.iter() {
+        if children.is_some_and(|children| children.iter().any(|child| colliders.contains(child))) {
+            continue;
+        }
+        commands.spawn((
+            ChunkCollider,
+            builder.0.build(*chunk_pos, chunk_size),
+            Transform::from_translation(chunk_size / 2.0 - chunk_manager.anchor_offset()),
+            ChildOf(entity),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{Chunk, ChunkLoadState, ChunkyPlugin, tests::app};
+
+    /// Records the arguments of every collider it builds
+    #[derive(Clone, Default)]
+    struct CountingBuilder(Arc<Mutex<Vec<(IVec3, Vec3)>>>);
+
+    #[derive(Component)]
+    struct TestCollider;
+
+    impl ChunkColliderBuilder for CountingBuilder {
+        type Collider = TestCollider;
+
+        fn build(&self, chunk_pos: IVec3, chunk_size: Vec3) -> TestCollider {
+            self.0.lock().unwrap().push((chunk_pos, chunk_size));
+            TestCollider
+        }
+    }
+
+    /// Readies two chunks and returns the arguments the builder got
+    fn build_calls(chunk_size: Vec3) -> Vec<(IVec3, Vec3)> {
+        let builder = CountingBuilder::default();
+        let mut app = app((
+            ChunkyPlugin::default().with_chunk_size(chunk_size),
+            ChunkColliderPlugin::new(builder.clone()),
+        ));
+        for pos in [IVec3::ZERO, IVec3::new(-2, 1, 3)] {
+            app.world_mut()
+                .spawn((Chunk, ChunkPos(pos), ChunkLoadState::Ready));
+        }
+        app.update();
+        // Already built colliders are not built again
+        app.update();
+
+        let mut colliders = app
+            .world_mut()
+            .query_filtered::<(), (With<ChunkCollider>, With<TestCollider>, With<ChildOf>)>();
+        assert_eq!(colliders.iter(app.world()).count(), 2);
+        let mut calls = builder.0.lock().unwrap().clone();
+        calls.sort_by_key(|(pos, _)| pos.to_array());
+        calls
+    }
+
+    #[test]
+    fn colliders_are_built_once_per_chunk_with_its_size() {
+        for chunk_size in [Vec3::splat(10.0), vec3(16.0, 32.0, 8.0)] {
+            assert_eq!(
+                build_calls(chunk_size),
+                [
+                    (IVec3::new(-2, 1, 3), chunk_size),
+                    (IVec3::ZERO, chunk_size)
+                ]
+            );
+        }
+    }
+}
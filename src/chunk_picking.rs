@@ -0,0 +1,193 @@
+//! Selecting chunks by clicking on them.
+//!
+//! [`ChunkPickingPlugin`] casts a ray from the cursor through the active
+//! camera when the [`pick_button`](ChunkPickingConfig::pick_button) is
+//! pressed, and selects the first loaded chunk it hits, see
+//! [`ChunkManager::raycast`]. Clicking where no loaded chunk is hit clears the
+//! selection. The selection is kept in [`SelectedChunk`], announced with
+//! [`ChunkSelected`] and logged with the state, the number of child entities
+//! and the version of the chunk, and the chunk visualizer highlights it. The
+//! version is the tick its load state last changed.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(ChunkyPlugin::default())
+//!     .add_plugins(ChunkPickingPlugin);
+//! ```
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{ChunkLoadState, ChunkManager, ChunkySystems};
+
+/// Selects chunks under the cursor on click, see the [module docs](self).
+pub struct ChunkPickingPlugin;
+
+impl Plugin for ChunkPickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChunkPickingConfig>()
+            .init_resource::<SelectedChunk>()
+            .add_message::<ChunkSelected>()
+            .add_systems(
+                Update,
+                (
+                    // Apps without an input plugin have no mouse input
+                    pick_chunk.run_if(resource_exists::<ButtonInput<MouseButton>>),
+                    log_selected_chunk,
+                )
+                    .chain()
+                    .after(ChunkySystems::PostGenerate)
+                    .before(ChunkySystems::Visualize),
+            );
+        #[cfg(feature = "reflect")]
+        app.register_type::<ChunkPickingConfig>()
+            .register_type::<SelectedChunk>();
+    }
+}
+
+/// Settings of chunk picking
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct ChunkPickingConfig {
+    /// Mouse button selecting the chunk under the cursor (default: left)
+    pub pick_button: MouseButton,
+    /// How far from the camera chunks can be picked, in world units
+    /// (default: 1000)
+    pub max_distance: f32,
+}
+
+impl Default for ChunkPickingConfig {
+    fn default() -> Self {
+        Self {
+            pick_button: MouseButton::Left,
+            max_distance: 1000.0,
+        }
+    }
+}
+
+/// The chunk position last picked with the cursor, `None` if nothing is
+/// selected.
+///
+/// Set it to select a chunk from code; the selection is not cleared when the
+/// chunk unloads.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct SelectedChunk(pub Option<IVec3>);
+
+/// Fired when the [`SelectedChunk`] changes through picking, with `None`
+/// when the selection was cleared.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSelected {
+    /// The newly selected chunk position, `None` if the selection was cleared
+    pub chunk_pos: Option<IVec3>,
+}
+
+/// Picks the first loaded chunk under the cursor on click
+fn pick_chunk(
+    chunk_manager: Res<ChunkManager>,
+    config: Res<ChunkPickingConfig>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut selected: ResMut<SelectedChunk>,
+    mut selections: MessageWriter<ChunkSelected>,
+) {
+    if !mouse.just_pressed(config.pick_button) {
+        return;
+    }
+    let Some(cursor) = window.single().ok().and_then(Window::cursor_position) else {
+        return;
+    };
+    // The camera rendering first, as in the visualizer
+    let Some((camera, transform)) = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .min_by_key(|(camera, _)| camera.order)
+    else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(transform, cursor) else {
+        return;
+    };
+
+    let chunk_pos = chunk_manager
+        .raycast(ray, config.max_distance)
+        .map(|chunk| chunk.pos);
+    if selected.0 != chunk_pos {
+        selected.0 = chunk_pos;
+        selections.write(ChunkSelected { chunk_pos });
+    }
+}
+
+/// Logs the chunk picked this frame
+fn log_selected_chunk(
+    chunk_manager: Res<ChunkManager>,
+    mut selections: MessageReader<ChunkSelected>,
+    chunks: Query<(Option<Ref<ChunkLoadState>>, Option<&Children>)>,
+) {
+    for ChunkSelected { chunk_pos } in selections.read() {
+        let Some(chunk_pos) = chunk_pos else {
+            info!("Chunk selection cleared");
+            continue;
+        };
+        let Some(entity) = chunk_manager.get_chunk(chunk_pos) else {
+            continue;
+        };
+        let Ok((load_state, children)) = chunks.get(entity) else {
+            continue;
+        };
+        let entities = children.map_or(0, |children| children.len());
+        match load_state {
+            Some(load_state) => info!(
+                "Selected chunk {chunk_pos} ({entity}), {:?}, {entities} entities, version {}",
+                *load_state,
+                load_state.last_changed().get()
+            ),
+            None => info!("Selected chunk {chunk_pos} ({entity}), {entities} entities"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChunkyPlugin, tests::app};
+
+    #[test]
+    fn picking_runs_without_mouse_input() {
+        let mut app = app((ChunkyPlugin::default(), ChunkPickingPlugin));
+        app.update();
+        assert_eq!(app.world().resource::<SelectedChunk>().0, None);
+    }
+
+    #[test]
+    fn a_camera_ray_selects_the_first_loaded_chunk_it_hits() {
+        let mut chunk_manager = ChunkManager::new(Vec3::splat(10.0));
+        let near = Entity::from_raw_u32(1).unwrap();
+        let far = Entity::from_raw_u32(2).unwrap();
+        chunk_manager.insert(IVec3::new(0, 0, -3), near);
+        chunk_manager.insert(IVec3::new(0, 0, -6), far);
+        chunk_manager.insert(IVec3::new(2, 0, -1), Entity::from_raw_u32(3).unwrap());
+
+        // A camera above the origin looking down the -z axis and slightly down
+        let camera = Transform::from_xyz(5.0, 5.0, 5.0).looking_at(vec3(5.0, 2.0, -55.0), Vec3::Y);
+        let ray = Ray3d::new(camera.translation, camera.forward());
+        let hit = chunk_manager.raycast(ray, 1000.0).unwrap();
+        assert_eq!((hit.pos, hit.entity), (IVec3::new(0, 0, -3), near));
+
+        // Out of reach, and past the chunks once the near one is gone
+        assert!(chunk_manager.raycast(ray, 20.0).is_none());
+        chunk_manager.remove(&IVec3::new(0, 0, -3));
+        assert_eq!(
+            chunk_manager.raycast(ray, 1000.0).map(|hit| hit.entity),
+            Some(far)
+        );
+        let sky = Ray3d::new(camera.translation, Dir3::Y);
+        assert!(chunk_manager.raycast(sky, 1000.0).is_none());
+    }
+}
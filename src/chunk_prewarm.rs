@@ -0,0 +1,231 @@
+//! Synchronous loading of a region before gameplay starts.
+//!
+//! Either call [`prewarm_region`] from an exclusive system, or insert a
+//! [`ChunkPrewarmRegion`] and enter [`ChunkPrewarm::Running`]; the state moves
+//! to [`ChunkPrewarm::Done`] once the region is loaded and generated.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! fn setup(mut commands: Commands, mut prewarm: ResMut<NextState<ChunkPrewarm>>) {
+//!     commands.insert_resource(ChunkPrewarmRegion(IVec3::splat(-2), IVec3::splat(2)));
+//!     prewarm.set(ChunkPrewarm::Running);
+//! }
+//!
+//! fn gameplay() {}
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(ChunkyPlugin::default())
+//!     .add_systems(Startup, setup)
+//!     .add_systems(Update, gameplay.run_if(in_state(ChunkPrewarm::Done)));
+//! ```
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+
+use crate::{Chunk, ChunkLoadState, ChunkManager, ChunkPos};
+
+pub(crate) struct ChunkPrewarmPlugin;
+
+impl Plugin for ChunkPrewarmPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<ChunkPrewarm>()
+            .init_resource::<ChunkPrewarmPumps>()
+            .add_systems(OnEnter(ChunkPrewarm::Running), run_chunk_prewarm);
+    }
+}
+
+/// State for waiting on the initial region to load
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+pub enum ChunkPrewarm {
+    /// No prewarm was requested (default)
+    #[default]
+    Idle,
+    /// The [`ChunkPrewarmRegion`] is being loaded
+    Running,
+    /// The [`ChunkPrewarmRegion`] is loaded and generated, or prewarming
+    /// stalled, see [`prewarm_region`]
+    Done,
+}
+
+/// The region, given as two chunk positions, loaded when entering
+/// [`ChunkPrewarm::Running`]
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ChunkPrewarmRegion(pub IVec3, pub IVec3);
+
+/// Functions that advance chunk generation by one step, registered by the
+/// generation plugins
+#[derive(Resource, Default)]
+pub(crate) struct ChunkPrewarmPumps(pub(crate) Vec<fn(&mut World)>);
+
+/// How long [`prewarm_region`] waits without any chunk of the region moving on
+/// before giving up
+const PREWARM_STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest [`prewarm_region`] sleeps between pumps while no chunk moves on
+const PREWARM_MAX_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Spawns all chunks between two chunk positions and blocks until their
+/// generation has finished, sleeping between steps while waiting on tasks.
+///
+/// Without a generation plugin this only spawns the chunks. Only chunks on the
+/// default [`ChunkLayer`](crate::ChunkLayer) are spawned and waited for.
+///
+/// Returns whether every chunk finished. Should no chunk of the region leave
+/// `Requested` or `Generating` for 10 seconds, e.g. because nothing generates
+/// it, a warning is logged and the remaining chunks are left to finish
+/// during gameplay.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::helpers::*;
+///
+/// fn load_spawn_area(world: &mut World) {
+///     prewarm_region(world, IVec3::splat(-2), IVec3::splat(2));
+/// }
+/// ```
+pub fn prewarm_region(world: &mut World, chunk_pos_0: IVec3, chunk_pos_1: IVec3) -> bool {
+    let (min, max) = (chunk_pos_0.min(chunk_pos_1), chunk_pos_0.max(chunk_pos_1));
+
+    let mut positions = Vec::new();
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                positions.push(ivec3(x, y, z));
+            }
+        }
+    }
+
+    for &chunk_pos in &positions {
+        if !world.resource::<ChunkManager>().is_loaded(&chunk_pos) {
+            world.spawn((Chunk, ChunkPos(chunk_pos), ChunkLoadState::Requested));
+        }
+    }
+    world.flush();
+
+    let pumps = world
+        .get_resource::<ChunkPrewarmPumps>()
+        .map(|pumps| pumps.0.clone())
+        .unwrap_or_default();
+    if pumps.is_empty() {
+        return true;
+    }
+
+    let mut pending = pending_chunks(world, &positions);
+    let mut last_progress = Instant::now();
+    let mut backoff = Duration::ZERO;
+    while pending != (0, 0) {
+        for pump in &pumps {
+            pump(world);
+        }
+        let now_pending = pending_chunks(world, &positions);
+        if now_pending != pending {
+            pending = now_pending;
+            last_progress = Instant::now();
+            backoff = Duration::ZERO;
+        } else if last_progress.elapsed() >= PREWARM_STALL_TIMEOUT {
+            warn!(
+                "Prewarming stalled with {} requested and {} generating chunks left",
+                pending.0, pending.1
+            );
+            return false;
+        } else {
+            // Leave the cores to the generation tasks instead of spinning
+            backoff = (backoff * 2).clamp(Duration::from_micros(50), PREWARM_MAX_BACKOFF);
+            thread::sleep(backoff);
+        }
+    }
+    true
+}
+
+/// Counts the `Requested` and `Generating` chunks at the given positions,
+/// the ones still awaiting their content.
+///
+/// Chunks between generation stages are waiting for neighbors, which the pumps
+/// already advanced as far as possible.
+fn pending_chunks(world: &World, positions: &[IVec3]) -> (usize, usize) {
+    let chunk_manager = world.resource::<ChunkManager>();
+    let mut pending = (0, 0);
+    for entity in positions
+        .iter()
+        .filter_map(|chunk_pos| chunk_manager.get_chunk(chunk_pos))
+    {
+        match world.get::<ChunkLoadState>(entity) {
+            Some(ChunkLoadState::Requested) => pending.0 += 1,
+            Some(ChunkLoadState::Generating) if !is_between_stages(world, entity) => {
+                pending.1 += 1;
+            }
+            _ => {}
+        }
+    }
+    pending
+}
+
+#[cfg(feature = "chunk_generation")]
+fn is_between_stages(world: &World, entity: Entity) -> bool {
+    world.get::<crate::prelude::ChunkStage>(entity).is_some()
+}
+
+#[cfg(not(feature = "chunk_generation"))]
+fn is_between_stages(_world: &World, _entity: Entity) -> bool {
+    false
+}
+
+/// Loads the ChunkPrewarmRegion and finishes the prewarm
+fn run_chunk_prewarm(world: &mut World) {
+    if let Some(ChunkPrewarmRegion(chunk_pos_0, chunk_pos_1)) =
+        world.get_resource::<ChunkPrewarmRegion>().copied()
+    {
+        prewarm_region(world, chunk_pos_0, chunk_pos_1);
+    } else {
+        warn!("Entered ChunkPrewarm::Running without a ChunkPrewarmRegion");
+    }
+    world
+        .resource_mut::<NextState<ChunkPrewarm>>()
+        .set(ChunkPrewarm::Done);
+}
+
+#[cfg(all(test, feature = "chunk_generation"))]
+mod tests {
+    use super::*;
+    use crate::{ChunkyPlugin, prelude::*, tests::app};
+
+    /// Generates the y coordinate of each chunk
+    struct HeightGenerator;
+
+    impl ChunkGenerator for HeightGenerator {
+        type Output = i32;
+
+        fn generate(&self, pos: IVec3, _ctx: GenerationContext) -> GenerationResult<i32> {
+            Ok(pos.y)
+        }
+    }
+
+    #[test]
+    fn the_region_is_ready_after_prewarming() {
+        let mut app = app((
+            ChunkyPlugin::default(),
+            ChunkGenerationPlugin::new(HeightGenerator),
+        ));
+        let (min, max) = (IVec3::splat(-1), IVec3::new(2, 1, 1));
+        assert!(prewarm_region(app.world_mut(), max, min));
+
+        let world = app.world();
+        let progress = world.resource::<ChunkStreamingProgress>();
+        assert_eq!(progress.fraction_ready_in(min, max), 1.0);
+        assert_eq!(progress.ready, 4 * 3 * 3);
+        let chunk = world.resource::<ChunkManager>().get_chunk(&max).unwrap();
+        assert_eq!(
+            world.get::<ChunkData<i32>>(chunk).map(|data| data.0),
+            Some(1)
+        );
+    }
+}
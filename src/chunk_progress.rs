@@ -0,0 +1,325 @@
+//! Loading progress for loading screens.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::ChunkManager;
+#[cfg(feature = "chunk_loader")]
+use crate::chunk_loader::ChunkLoader;
+use crate::{Chunk, ChunkLoadState};
+
+/// How many chunks are in each [`ChunkLoadState`].
+///
+/// Kept up to date whenever a chunk's load state changes or the chunk is
+/// despawned, so reading it is free.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn update_loading_bar(progress: Res<ChunkStreamingProgress>) {
+///     let spawn_area = (IVec3::splat(-2), IVec3::splat(2));
+///     info!("{:.0}% loaded", progress.fraction_ready_in(spawn_area.0, spawn_area.1) * 100.0);
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ChunkStreamingProgress {
+    /// Chunks waiting for generation to start
+    pub pending_spawn: usize,
+    /// Chunks being generated
+    pub generating: usize,
+    /// Chunks with their content available
+    pub ready: usize,
+    /// Chunks whose generation failed
+    pub failed: usize,
+    /// Positions of the ready chunks, with their [`ChunkLayer`](crate::ChunkLayer) as `w`
+    ready_chunks: HashSet<IVec4>,
+}
+
+impl ChunkStreamingProgress {
+    /// Returns the fraction of the chunks between two chunk positions on the
+    /// default [`ChunkLayer`](crate::ChunkLayer) that are ready, from `0.0` to `1.0`
+    pub fn fraction_ready_in(&self, chunk_pos_0: IVec3, chunk_pos_1: IVec3) -> f32 {
+        let (min, max) = (chunk_pos_0.min(chunk_pos_1), chunk_pos_0.max(chunk_pos_1));
+        let total = (max - min + IVec3::ONE).as_vec3().element_product();
+
+        let mut ready = 0;
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    if self.ready_chunks.contains(&ivec4(x, y, z, 0)) {
+                        ready += 1;
+                    }
+                }
+            }
+        }
+        ready as f32 / total
+    }
+
+    /// Checks if the chunk at `chunk_pos` on the default [`ChunkLayer`](crate::ChunkLayer) is
+    /// ready
+    pub fn is_ready(&self, chunk_pos: IVec3) -> bool {
+        self.is_ready_in_layer(chunk_pos, 0)
+    }
+
+    /// Checks if the chunk at `chunk_pos` on a [`ChunkLayer`](crate::ChunkLayer) is ready
+    pub fn is_ready_in_layer(&self, chunk_pos: IVec3, layer: i32) -> bool {
+        self.ready_chunks.contains(&chunk_pos.extend(layer))
+    }
+
+    /// Counts a chunk entering `state`, at a position with its layer as `w`
+    pub(crate) fn add(&mut self, state: ChunkLoadState, chunk_pos: Option<IVec4>) {
+        *self.count_mut(state) += 1;
+        if state == ChunkLoadState::Ready
+            && let Some(chunk_pos) = chunk_pos
+        {
+            self.ready_chunks.insert(chunk_pos);
+        }
+    }
+
+    /// Counts a chunk leaving `state`, at a position with its layer as `w`
+    pub(crate) fn remove(&mut self, state: ChunkLoadState, chunk_pos: Option<IVec4>) {
+        let count = self.count_mut(state);
+        *count = count.saturating_sub(1);
+        if state == ChunkLoadState::Ready
+            && let Some(chunk_pos) = chunk_pos
+        {
+            self.ready_chunks.remove(&chunk_pos);
+        }
+    }
+
+    /// Moves a ready chunk to or away from a position with its layer as `w`,
+    /// as its [`ChunkLayer`](crate::ChunkLayer) changes
+    pub(crate) fn set_ready_at(&mut self, chunk_pos: IVec4, ready: bool) {
+        if ready {
+            self.ready_chunks.insert(chunk_pos);
+        } else {
+            self.ready_chunks.remove(&chunk_pos);
+        }
+    }
+
+    fn count_mut(&mut self, state: ChunkLoadState) -> &mut usize {
+        match state {
+            ChunkLoadState::Requested => &mut self.pending_spawn,
+            ChunkLoadState::Generating => &mut self.generating,
+            ChunkLoadState::Ready => &mut self.ready,
+            ChunkLoadState::Failed => &mut self.failed,
+        }
+    }
+}
+
+/// Chunk counts for debug overlays, updated every frame after
+/// [`ChunkySystems::PostGenerate`](crate::ChunkySystems::PostGenerate).
+///
+/// See [`ChunkStreamingProgress`] for the chunks in each load state.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn log_stats(stats: Res<ChunkStats>) {
+///     info!("{} chunks, {} new", stats.loaded, stats.spawned_this_frame);
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkStats {
+    /// Chunks registered in the [`ChunkManager`]
+    pub loaded: usize,
+    /// Chunks spawned since the last update
+    pub spawned_this_frame: usize,
+    /// Chunks despawned since the last update, including those despawned
+    /// after it in the previous frame
+    pub despawned_this_frame: usize,
+}
+
+/// Recounts the ChunkStats
+pub(crate) fn update_chunk_stats(
+    chunk_manager: Res<ChunkManager>,
+    spawned: Query<(), Added<Chunk>>,
+    mut despawned: RemovedComponents<Chunk>,
+    mut stats: ResMut<ChunkStats>,
+) {
+    stats.set_if_neq(ChunkStats {
+        loaded: chunk_manager.len(),
+        spawned_this_frame: spawned.iter().count(),
+        despawned_this_frame: despawned.read().count(),
+    });
+}
+
+/// Run condition that becomes true once every chunk within `radius` of each
+/// [`ChunkLoader`] is ready, and stays true afterwards.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn show_start_button() {}
+///
+/// App::new().add_systems(
+///     Update,
+///     show_start_button.run_if(initial_area_ready(IVec3::new(2, 0, 2))),
+/// );
+/// ```
+#[cfg(feature = "chunk_loader")]
+pub fn initial_area_ready(
+    radius: IVec3,
+) -> impl FnMut(
+    Res<ChunkStreamingProgress>,
+    Res<ChunkManager>,
+    Query<&GlobalTransform, With<ChunkLoader>>,
+    Local<bool>,
+) -> bool
++ Clone {
+    move |progress, chunk_manager, loaders, mut ready| {
+        if !*ready && !loaders.is_empty() {
+            *ready = loaders.iter().all(|transform| {
+                let center = chunk_manager.get_chunk_pos(&transform.translation());
+                progress.fraction_ready_in(center - radius, center + radius) >= 1.0
+            });
+        }
+        *ready
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChunkLayer, ChunkLoadStateCommands, ChunkPos, ChunkyPlugin, tests::app};
+
+    /// Spawns the `Requested` chunks of a 3x1x3 patch around the origin
+    fn spawn_patch(app: &mut App) -> Vec<Entity> {
+        (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |z| IVec3::new(x, 0, z)))
+            .map(|pos| {
+                app.world_mut()
+                    .spawn((Chunk, ChunkPos(pos), ChunkLoadState::Requested))
+                    .id()
+            })
+            .collect()
+    }
+
+    fn set_ready(app: &mut App, chunk: Entity) {
+        app.world_mut()
+            .commands()
+            .entity(chunk)
+            .set_load_state(ChunkLoadState::Ready);
+    }
+
+    #[test]
+    fn the_ready_fraction_rises_to_one_while_loading() {
+        let mut app = app(ChunkyPlugin::default());
+        let chunks = spawn_patch(&mut app);
+        let fraction = |app: &App| {
+            app.world()
+                .resource::<ChunkStreamingProgress>()
+                .fraction_ready_in(IVec3::new(1, 0, 1), IVec3::new(-1, 0, -1))
+        };
+        app.update();
+        let mut fractions = vec![fraction(&app)];
+        for chunk in chunks {
+            set_ready(&mut app, chunk);
+            app.update();
+            fractions.push(fraction(&app));
+        }
+
+        assert_eq!(fractions[0], 0.0);
+        assert!(fractions.is_sorted_by(|a, b| a < b), "{fractions:?}");
+        assert_eq!(fractions.last(), Some(&1.0));
+    }
+
+    #[cfg(feature = "chunk_loader")]
+    #[test]
+    fn the_initial_area_stays_ready_once_it_was() {
+        #[derive(Resource, Default)]
+        struct Runs(usize);
+
+        let mut app = app(ChunkyPlugin::default());
+        app.init_resource::<Runs>().add_systems(
+            Update,
+            (|mut runs: ResMut<Runs>| runs.0 += 1).run_if(initial_area_ready(IVec3::new(1, 0, 1))),
+        );
+        let runs = |app: &App| app.world().resource::<Runs>().0;
+        app.update();
+        assert_eq!(runs(&app), 0, "no loader yet");
+
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)));
+        let chunks = spawn_patch(&mut app);
+        app.update();
+        assert_eq!(runs(&app), 0);
+
+        for &chunk in &chunks[1..] {
+            set_ready(&mut app, chunk);
+        }
+        app.update();
+        assert_eq!(runs(&app), 0, "one chunk is still missing");
+        set_ready(&mut app, chunks[0]);
+        app.update();
+        assert_eq!(runs(&app), 1);
+
+        app.world_mut().despawn(chunks[0]);
+        app.update();
+        assert_eq!(runs(&app), 2);
+    }
+
+    #[test]
+    fn ready_chunks_are_kept_per_layer() {
+        let mut app = app(ChunkyPlugin::default());
+        let layered = app
+            .world_mut()
+            .spawn((
+                Chunk,
+                ChunkPos(IVec3::ZERO),
+                ChunkLayer(1),
+                ChunkLoadState::Ready,
+            ))
+            .id();
+        let progress = app.world().resource::<ChunkStreamingProgress>();
+        assert!(progress.is_ready_in_layer(IVec3::ZERO, 1));
+        assert!(!progress.is_ready(IVec3::ZERO));
+
+        app.world_mut().despawn(layered);
+        let progress = app.world().resource::<ChunkStreamingProgress>();
+        assert!(!progress.is_ready_in_layer(IVec3::ZERO, 1));
+        assert_eq!(progress.ready, 0);
+    }
+
+    #[test]
+    fn stats_count_the_chunks_of_each_frame() {
+        let mut app = app(ChunkyPlugin::default());
+        let chunks: Vec<_> = (0..3)
+            .map(|x| {
+                app.world_mut()
+                    .spawn((Chunk, ChunkPos(IVec3::new(x, 0, 0))))
+                    .id()
+            })
+            .collect();
+        app.update();
+        assert_eq!(
+            *app.world().resource::<ChunkStats>(),
+            ChunkStats {
+                loaded: 3,
+                spawned_this_frame: 3,
+                despawned_this_frame: 0,
+            }
+        );
+
+        app.world_mut().despawn(chunks[0]);
+        app.update();
+        assert_eq!(
+            *app.world().resource::<ChunkStats>(),
+            ChunkStats {
+                loaded: 2,
+                spawned_this_frame: 0,
+                despawned_this_frame: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,504 @@
+//! A [`ChunkStore`] packing the chunks of a region into a single file.
+//!
+//! Each region file starts with a header: the magic bytes `CHKR`, the format
+//! version and the region size as little-endian `u32`s, followed by an offset
+//! table with one entry per chunk of the region, ordered by `x`, then `y`, then
+//! `z`. An entry holds the offset (`u64`), the reserved capacity, the length and
+//! an FNV-1a checksum (`u32` each) of the chunk's data; an offset of `0` marks a
+//! chunk that was never saved.
+//!
+//! A chunk that shrinks or keeps its size is rewritten in place; one that grows
+//! is appended to the file and its old copy becomes unused. Once more than half
+//! of a file, and at least 1 MiB, is unused, the file is rewritten without the
+//! gaps.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    RegionSize,
+    chunk_persistence::{ChecksumMismatch, ChunkStore},
+};
+
+/// Identifies region files
+const MAGIC: [u8; 4] = *b"CHKR";
+
+/// The region file format version written by this crate
+const VERSION: u32 = 1;
+
+/// Bytes before the offset table: magic, version and region size
+const HEADER_LEN: u64 = 20;
+
+/// Bytes per offset table entry: offset, capacity, length and checksum
+const ENTRY_LEN: u64 = 20;
+
+/// Unused bytes a region file must hold before it is compacted
+#[cfg(not(test))]
+const COMPACT_THRESHOLD: u64 = 1 << 20;
+/// Small enough for tests to reach compaction
+#[cfg(test)]
+const COMPACT_THRESHOLD: u64 = 1 << 10;
+
+/// A [`ChunkStore`] writing the chunks of each region into one file.
+///
+/// Avoids the many small files of
+/// [`FilesystemChunkStore`](crate::prelude::FilesystemChunkStore). Files are
+/// named `x_y_z.region` after the region position and created on the first
+/// save. A damaged chunk is reported as [`io::ErrorKind::InvalidData`] without
+/// affecting the other chunks of its region.
+///
+/// Region files stay open once used. Each is locked while it is read or
+/// written, so chunks of different regions are accessed in parallel.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// let store = RegionFileStore::new("saves/world").with_region_size(RegionSize(IVec3::splat(16)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegionFileStore {
+    dir: PathBuf,
+    region_size: RegionSize,
+    regions: Arc<Mutex<HashMap<IVec3, Arc<Mutex<RegionFile>>>>>,
+}
+
+impl RegionFileStore {
+    /// Creates a store that keeps its region files in `dir`, with the default
+    /// [`RegionSize`]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            region_size: RegionSize::DEFAULT,
+            regions: default(),
+        }
+    }
+
+    /// Sets how many chunks each region file holds on each axis.
+    ///
+    /// Opening a region file written with a different size fails. Sizes below
+    /// 1 chunk are raised to 1.
+    pub fn with_region_size(mut self, region_size: RegionSize) -> Self {
+        self.region_size = region_size.clamped();
+        self
+    }
+
+    /// The directory the region files are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the region file holding `pos` and the index of `pos` in its
+    /// table, opening the file first if needed.
+    ///
+    /// Returns `None` if the file does not exist and `create` is false.
+    fn region(
+        &self,
+        pos: IVec3,
+        create: bool,
+    ) -> io::Result<Option<(Arc<Mutex<RegionFile>>, usize)>> {
+        let size = self.region_size.0;
+        let region_pos = pos.div_euclid(size);
+        let local = pos.rem_euclid(size);
+        let index = (local.x + local.y * size.x + local.z * size.x * size.y) as usize;
+
+        let mut regions = lock(&self.regions);
+        if let Some(region) = regions.get(&region_pos) {
+            return Ok(Some((region.clone(), index)));
+        }
+        let path = self.dir.join(format!(
+            "{}_{}_{}.region",
+            region_pos.x, region_pos.y, region_pos.z
+        ));
+        let region = if path.is_file() {
+            RegionFile::open(path, size)?
+        } else if create {
+            fs::create_dir_all(&self.dir)?;
+            RegionFile::create(path, size)?
+        } else {
+            return Ok(None);
+        };
+        let region = Arc::new(Mutex::new(region));
+        regions.insert(region_pos, region.clone());
+        Ok(Some((region, index)))
+    }
+}
+
+impl ChunkStore for RegionFileStore {
+    fn save(&self, pos: IVec3, bytes: &[u8]) -> io::Result<()> {
+        let Some((region, index)) = self.region(pos, true)? else {
+            return Ok(());
+        };
+        lock(&region).write(index, bytes)
+    }
+
+    fn load(&self, pos: IVec3) -> io::Result<Option<Vec<u8>>> {
+        let Some((region, index)) = self.region(pos, false)? else {
+            return Ok(None);
+        };
+        lock(&region).read(index)
+    }
+
+    fn exists(&self, pos: IVec3) -> bool {
+        match self.region(pos, false) {
+            Ok(Some((region, index))) => lock(&region).entries[index].offset != 0,
+            _ => false,
+        }
+    }
+
+    fn root(&self) -> Option<&Path> {
+        Some(&self.dir)
+    }
+
+    fn relocate(&self, root: &Path) -> Option<Arc<dyn ChunkStore>> {
+        Some(Arc::new(Self::new(root).with_region_size(self.region_size)))
+    }
+}
+
+/// Locks a mutex, ignoring poisoning as the data is always left consistent
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// An open region file and its offset table
+#[derive(Debug)]
+struct RegionFile {
+    path: PathBuf,
+    file: File,
+    size: IVec3,
+    entries: Vec<Entry>,
+    /// Length of the file
+    len: u64,
+    /// Bytes after the table not reserved by any entry
+    unused: u64,
+}
+
+/// Where the data of a chunk is stored in a region file
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+    offset: u64,
+    capacity: u32,
+    length: u32,
+    checksum: u32,
+}
+
+impl Entry {
+    fn to_bytes(self) -> [u8; ENTRY_LEN as usize] {
+        let mut bytes = [0; ENTRY_LEN as usize];
+        bytes[0..8].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.capacity.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.length.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        Self {
+            offset: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            capacity: u32_at(8),
+            length: u32_at(12),
+            checksum: u32_at(16),
+        }
+    }
+}
+
+impl RegionFile {
+    /// Creates an empty region file
+    fn create(path: PathBuf, size: IVec3) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let entries = vec![Entry::default(); size.element_product() as usize];
+        file.write_all(&header(size, &entries))?;
+        Ok(Self {
+            path,
+            file,
+            size,
+            len: table_end(entries.len()),
+            entries,
+            unused: 0,
+        })
+    }
+
+    /// Opens an existing region file, checking its header
+    fn open(path: PathBuf, size: IVec3) -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let len = file.metadata()?.len();
+
+        let mut head = [0; HEADER_LEN as usize];
+        file.read_exact(&mut head)
+            .map_err(|_| invalid_data("region file header is truncated"))?;
+        let u32_at = |i: usize| u32::from_le_bytes(head[i..i + 4].try_into().unwrap());
+        if head[0..4] != MAGIC {
+            return Err(invalid_data("not a region file"));
+        }
+        if u32_at(4) != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported region file version {}",
+                u32_at(4)
+            )));
+        }
+        if [u32_at(8), u32_at(12), u32_at(16)] != size.as_uvec3().to_array() {
+            return Err(invalid_data(
+                "region file was written with a different region size",
+            ));
+        }
+
+        let count = size.element_product() as usize;
+        let mut table = vec![0; count * ENTRY_LEN as usize];
+        file.read_exact(&mut table)
+            .map_err(|_| invalid_data("region file table is truncated"))?;
+        let entries: Vec<Entry> = table
+            .chunks_exact(ENTRY_LEN as usize)
+            .map(Entry::from_bytes)
+            .collect();
+        let reserved: u64 = entries.iter().map(|entry| entry.capacity as u64).sum();
+        Ok(Self {
+            path,
+            file,
+            size,
+            unused: len.saturating_sub(table_end(count) + reserved),
+            len,
+            entries,
+        })
+    }
+
+    /// Reads the data of one chunk, checking it against its checksum
+    fn read(&mut self, index: usize) -> io::Result<Option<Vec<u8>>> {
+        let entry = self.entries[index];
+        if entry.offset == 0 {
+            return Ok(None);
+        }
+        if entry.offset + entry.length as u64 > self.len {
+            return Err(invalid_data(
+                "chunk data lies past the end of the region file",
+            ));
+        }
+        let mut bytes = vec![0; entry.length as usize];
+        self.file.seek(SeekFrom::Start(entry.offset))?;
+        self.file.read_exact(&mut bytes)?;
+        let found = checksum(&bytes);
+        if found != entry.checksum {
+            return Err(ChecksumMismatch {
+                expected: entry.checksum,
+                found,
+            }
+            .into());
+        }
+        Ok(Some(bytes))
+    }
+
+    /// Writes the data of one chunk, in place if it fits, otherwise appended
+    fn write(&mut self, index: usize, bytes: &[u8]) -> io::Result<()> {
+        let length = u32::try_from(bytes.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "chunk data too large"))?;
+        let mut entry = self.entries[index];
+        if entry.offset == 0 || length > entry.capacity {
+            self.unused += entry.capacity as u64;
+            entry.offset = self.len;
+            entry.capacity = length;
+            self.len += length as u64;
+        }
+        entry.length = length;
+        entry.checksum = checksum(bytes);
+
+        // The data is written before the table, so the entry never points to
+        // bytes that were not written yet
+        self.file.seek(SeekFrom::Start(entry.offset))?;
+        self.file.write_all(bytes)?;
+        self.file
+            .seek(SeekFrom::Start(HEADER_LEN + index as u64 * ENTRY_LEN))?;
+        self.file.write_all(&entry.to_bytes())?;
+        self.entries[index] = entry;
+
+        if self.unused >= COMPACT_THRESHOLD && self.unused * 2 > self.len {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the file without unused bytes, through a temporary file that
+    /// replaces it once complete.
+    ///
+    /// The old file is closed before it is replaced, as some platforms can't
+    /// rename over an open file.
+    fn compact(&mut self) -> io::Result<()> {
+        let mut entries = self.entries.clone();
+        let mut data = Vec::new();
+        let mut offset = table_end(entries.len());
+        for (index, entry) in entries.iter_mut().enumerate() {
+            if entry.offset == 0 {
+                continue;
+            }
+            // Damaged chunks are dropped, as they would fail to load anyway
+            let Ok(Some(bytes)) = self.read(index) else {
+                *entry = Entry::default();
+                continue;
+            };
+            *entry = Entry {
+                offset,
+                capacity: entry.length,
+                ..*entry
+            };
+            offset += bytes.len() as u64;
+            data.extend_from_slice(&bytes);
+        }
+
+        let temp_path = self.path.with_extension("region.tmp");
+        let mut temp = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)?;
+        temp.write_all(&header(self.size, &entries))?;
+        temp.write_all(&data)?;
+        temp.sync_all()?;
+
+        // The handle follows the temporary file to its new name
+        drop(std::mem::replace(&mut self.file, temp));
+        if let Err(error) = fs::rename(&temp_path, &self.path) {
+            // The old file is still complete, so it is used further
+            self.file = OpenOptions::new().read(true).write(true).open(&self.path)?;
+            return Err(error);
+        }
+        self.entries = entries;
+        self.len = offset;
+        self.unused = 0;
+        Ok(())
+    }
+}
+
+/// The header and offset table of a region file
+fn header(size: IVec3, entries: &[Entry]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(table_end(entries.len()) as usize);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    for axis in size.as_uvec3().to_array() {
+        bytes.extend_from_slice(&axis.to_le_bytes());
+    }
+    for entry in entries {
+        bytes.extend_from_slice(&entry.to_bytes());
+    }
+    bytes
+}
+
+/// The offset of the first chunk's data in a region file with `count` entries
+fn table_end(count: usize) -> u64 {
+    HEADER_LEN + count as u64 * ENTRY_LEN
+}
+
+/// The 32-bit FNV-1a hash of the data
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, &byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_dir;
+
+    /// Data of a different length for every chunk
+    fn chunk_bytes(pos: IVec3) -> Vec<u8> {
+        let len = (pos.x + 2 * pos.y + 3 * pos.z).rem_euclid(50) as usize + 1;
+        vec![(pos.x ^ pos.y ^ pos.z) as u8; len]
+    }
+
+    #[test]
+    fn chunks_read_back_from_a_reopened_store() {
+        let dir = test_dir("region_files");
+        let region_size = RegionSize(IVec3::splat(4));
+        let positions: Vec<_> = (-6..6)
+            .flat_map(|x| (-2..3).flat_map(move |y| (0..5).map(move |z| IVec3::new(x, y, z))))
+            .collect();
+        assert_eq!(positions.len(), 300);
+        {
+            let store = RegionFileStore::new(&dir).with_region_size(region_size);
+            for &pos in &positions {
+                store.save(pos, &[0; 8]).unwrap();
+            }
+            // Chunks growing past their capacity are appended
+            for &pos in &positions {
+                store.save(pos, &chunk_bytes(pos)).unwrap();
+            }
+        }
+        assert!(fs::read_dir(&dir).unwrap().count() > 1);
+
+        let store = RegionFileStore::new(&dir).with_region_size(region_size);
+        for &pos in &positions {
+            assert!(store.exists(pos));
+            assert_eq!(store.load(pos).unwrap(), Some(chunk_bytes(pos)), "{pos}");
+        }
+        assert!(!store.exists(IVec3::new(0, 0, 5)));
+        assert_eq!(store.load(IVec3::new(0, 0, 5)).unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn growing_chunks_compact_the_file() {
+        let dir = test_dir("region_compaction");
+        let path = dir.join("0_0_0.region");
+        let region_size = RegionSize(IVec3::splat(4));
+        let store = RegionFileStore::new(&dir).with_region_size(region_size);
+        // Every round outgrows the last one, so each chunk is appended again
+        let mut sizes = Vec::new();
+        for round in 1..=10 {
+            for x in 0..4 {
+                store
+                    .save(IVec3::X * x, &vec![round as u8; 50 * round])
+                    .unwrap();
+                sizes.push(fs::metadata(&path).unwrap().len());
+            }
+        }
+        assert!(sizes.windows(2).any(|pair| pair[1] < pair[0]), "{sizes:?}");
+        assert!(!dir.join("0_0_0.region.tmp").exists());
+
+        for x in 0..4 {
+            assert_eq!(store.load(IVec3::X * x).unwrap(), Some(vec![10; 500]));
+        }
+        let store = RegionFileStore::new(&dir).with_region_size(region_size);
+        for x in 0..4 {
+            assert_eq!(store.load(IVec3::X * x).unwrap(), Some(vec![10; 500]));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_flipped_byte_only_damages_its_chunk() {
+        let dir = test_dir("region_flipped_byte");
+        let store = RegionFileStore::new(&dir).with_region_size(RegionSize(IVec3::splat(4)));
+        store.save(IVec3::ZERO, &[1; 16]).unwrap();
+        store.save(IVec3::X, &[2; 16]).unwrap();
+        drop(store);
+
+        // The second chunk's data ends the file
+        let path = dir.join("0_0_0.region");
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        let store = RegionFileStore::new(&dir).with_region_size(RegionSize(IVec3::splat(4)));
+        assert_eq!(store.load(IVec3::ZERO).unwrap(), Some(vec![1; 16]));
+        let error = store.load(IVec3::X).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,340 @@
+//! Capturing the state of the chunk world for save games and restoring it.
+//!
+//! A [`ChunkWorldSnapshot`] holds the registered chunk positions with their
+//! layers, load states, pins and persistence markers, the world offset and the chunk loaders, but no entity
+//! ids or chunk content. With the `serde` feature it can be serialized along
+//! with the rest of a save game, while chunk content is saved by the
+//! persistence plugin.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! #[derive(Resource)]
+//! struct SaveGame(ChunkWorldSnapshot);
+//!
+//! fn save(world: &mut World) {
+//!     let snapshot = ChunkWorldSnapshot::capture(world);
+//!     world.insert_resource(SaveGame(snapshot));
+//! }
+//!
+//! fn load(mut commands: Commands, save: Res<SaveGame>) {
+//!     commands.queue(save.0.clone());
+//! }
+//! ```
+
+use bevy::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "chunk_loader")]
+use crate::chunk_loader::{ChunkLoader, ChunkLoaderDisabled, ChunkLoaderStats};
+#[cfg(feature = "chunk_persistence")]
+use crate::chunk_persistence::ChunkDirty;
+#[cfg(feature = "chunk_unloader")]
+use crate::chunk_unloader::ChunkPinned;
+#[cfg(all(feature = "chunk_loader", feature = "chunk_unloader"))]
+use crate::chunk_unloader::ChunkUnloadRadius;
+use crate::{Chunk, ChunkLayer, ChunkLoadState, ChunkManager, ChunkPersistent, ChunkPos};
+
+/// Everything needed to rebuild the chunk world, without entity ids.
+///
+/// Applying a snapshot despawns all existing chunks first. The captured chunks
+/// are spawned again, those that had a [`ChunkLoadState`] as `Requested`, so
+/// their content is loaded from the [`ChunkStore`](crate::prelude::ChunkStore)
+/// or generated again. Loaders are spawned as entities of their own, holding
+/// only a [`Transform`] and their loader components. If the snapshot holds
+/// loaders, the existing ones lose their loader components first, so none is
+/// duplicated, while their entities, e.g. the player, are kept. Clear
+/// [`loaders`](Self::loaders) if the game spawns its loaders itself.
+///
+/// It can also be applied as a [`Command`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChunkWorldSnapshot {
+    /// The registered chunks on all layers, sorted by layer and position
+    pub chunks: Vec<ChunkSnapshot>,
+    /// See [`ChunkManager::set_world_offset`]
+    pub world_offset: Vec3,
+    /// The chunk loaders
+    #[cfg(feature = "chunk_loader")]
+    pub loaders: Vec<LoaderSnapshot>,
+}
+
+/// A chunk in a [`ChunkWorldSnapshot`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChunkSnapshot {
+    pub pos: IVec3,
+    /// The chunk's [`ChunkLayer`], 0 for the default layer
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub layer: i32,
+    /// The chunk's load state, if it had one
+    pub load_state: Option<ChunkLoadState>,
+    /// Whether the chunk had [`ChunkPinned`]
+    #[cfg(feature = "chunk_unloader")]
+    pub pinned: bool,
+    /// Whether the chunk had [`ChunkPersistent`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub persistent: bool,
+    /// Whether the chunk had [`ChunkDirty`], so it is saved again once its
+    /// content is loaded or generated
+    #[cfg(feature = "chunk_persistence")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dirty: bool,
+}
+
+/// A chunk loader in a [`ChunkWorldSnapshot`]
+#[cfg(feature = "chunk_loader")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LoaderSnapshot {
+    /// The loader's position in the world
+    pub translation: Vec3,
+    /// See [`ChunkLoader`]
+    pub radius: IVec3,
+    /// Whether the loader had [`ChunkLoaderDisabled`]
+    pub disabled: bool,
+    /// See [`ChunkUnloadRadius`]
+    #[cfg(feature = "chunk_unloader")]
+    pub unload_radius: Option<IVec3>,
+}
+
+impl ChunkWorldSnapshot {
+    /// Captures the chunk world
+    pub fn capture(world: &World) -> Self {
+        let chunk_manager = world.resource::<ChunkManager>();
+        let mut chunks: Vec<_> = chunk_manager
+            .iter_all_layers()
+            .map(|(pos, layer, entity)| ChunkSnapshot {
+                pos,
+                layer,
+                load_state: world.get::<ChunkLoadState>(entity).copied(),
+                #[cfg(feature = "chunk_unloader")]
+                pinned: world.get::<ChunkPinned>(entity).is_some(),
+                persistent: world.get::<ChunkPersistent>(entity).is_some(),
+                #[cfg(feature = "chunk_persistence")]
+                dirty: world.get::<ChunkDirty>(entity).is_some(),
+            })
+            .collect();
+        chunks.sort_by_key(|chunk| (chunk.layer, chunk.pos.to_array()));
+
+        Self {
+            chunks,
+            world_offset: chunk_manager.get_world_offset(),
+            #[cfg(feature = "chunk_loader")]
+            loaders: capture_loaders(world),
+        }
+    }
+
+    /// Despawns all chunks and rebuilds the captured chunk world
+    pub fn apply(self, world: &mut World) {
+        let existing: Vec<Entity> = world
+            .query_filtered::<Entity, With<Chunk>>()
+            .iter(world)
+            .collect();
+        for entity in existing {
+            world.despawn(entity);
+        }
+
+        world
+            .resource_mut::<ChunkManager>()
+            .set_world_offset(self.world_offset);
+        for chunk in self.chunks {
+            // Spawned with its layer, so it never takes a default layer slot
+            let mut entity = match chunk.layer {
+                0 => world.spawn((Chunk, ChunkPos(chunk.pos))),
+                layer => world.spawn((Chunk, ChunkPos(chunk.pos), ChunkLayer(layer))),
+            };
+            if chunk.load_state.is_some() {
+                entity.insert(ChunkLoadState::Requested);
+            }
+            #[cfg(feature = "chunk_unloader")]
+            if chunk.pinned {
+                entity.insert(ChunkPinned);
+            }
+            if chunk.persistent {
+                entity.insert(ChunkPersistent);
+            }
+            #[cfg(feature = "chunk_persistence")]
+            if chunk.dirty {
+                entity.insert(ChunkDirty);
+            }
+        }
+
+        #[cfg(feature = "chunk_loader")]
+        if !self.loaders.is_empty() {
+            let existing: Vec<Entity> = world
+                .query_filtered::<Entity, With<ChunkLoader>>()
+                .iter(world)
+                .collect();
+            for entity in existing {
+                let mut entity = world.entity_mut(entity);
+                entity.remove::<(ChunkLoader, ChunkLoaderStats, ChunkLoaderDisabled)>();
+                #[cfg(feature = "chunk_unloader")]
+                entity.remove::<ChunkUnloadRadius>();
+            }
+        }
+        #[cfg(feature = "chunk_loader")]
+        for loader in self.loaders {
+            let mut entity = world.spawn((
+                Transform::from_translation(loader.translation),
+                ChunkLoader(loader.radius),
+            ));
+            if loader.disabled {
+                entity.insert(ChunkLoaderDisabled);
+            }
+            #[cfg(feature = "chunk_unloader")]
+            if let Some(unload_radius) = loader.unload_radius {
+                entity.insert(ChunkUnloadRadius(unload_radius));
+            }
+        }
+    }
+}
+
+impl Command for ChunkWorldSnapshot {
+    fn apply(self, world: &mut World) {
+        ChunkWorldSnapshot::apply(self, world);
+    }
+}
+
+/// Captures every loader with a [`GlobalTransform`]
+#[cfg(feature = "chunk_loader")]
+fn capture_loaders(world: &World) -> Vec<LoaderSnapshot> {
+    let Some(mut loaders) = world.try_query::<(Entity, &ChunkLoader, &GlobalTransform)>() else {
+        return Vec::new();
+    };
+    loaders
+        .iter(world)
+        .map(|(entity, ChunkLoader(radius), transform)| LoaderSnapshot {
+            translation: transform.translation(),
+            radius: *radius,
+            disabled: world.get::<ChunkLoaderDisabled>(entity).is_some(),
+            #[cfg(feature = "chunk_unloader")]
+            unload_radius: world
+                .get::<ChunkUnloadRadius>(entity)
+                .map(|unload_radius| unload_radius.0),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChunkyPlugin, tests::app};
+
+    #[test]
+    fn layers_survive_a_round_trip() {
+        let mut app = app(ChunkyPlugin::default());
+        app.world_mut().spawn((Chunk, ChunkPos(IVec3::X)));
+        app.world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::X), ChunkLayer(1)));
+        app.update();
+
+        let snapshot = ChunkWorldSnapshot::capture(app.world());
+        assert_eq!(
+            snapshot
+                .chunks
+                .iter()
+                .map(|chunk| (chunk.pos, chunk.layer))
+                .collect::<Vec<_>>(),
+            [(IVec3::X, 0), (IVec3::X, 1)]
+        );
+
+        snapshot.apply(app.world_mut());
+        app.update();
+        let chunk_manager = app.world().resource::<ChunkManager>();
+        assert!(chunk_manager.is_loaded_in_layer(&IVec3::X, 0));
+        assert!(chunk_manager.is_loaded_in_layer(&IVec3::X, 1));
+        assert_eq!(chunk_manager.len(), 2);
+    }
+
+    #[cfg(all(feature = "chunk_loader", feature = "chunk_unloader"))]
+    #[test]
+    fn loaders_are_restored_into_a_fresh_app() {
+        let mut app = app(ChunkyPlugin::default());
+        app.world_mut().spawn((
+            Transform::from_xyz(100.0, 0.0, 0.0),
+            ChunkLoader(IVec3::new(1, 0, 1)),
+            ChunkLoaderDisabled,
+            ChunkUnloadRadius(IVec3::splat(3)),
+        ));
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)));
+        app.update();
+        let snapshot = ChunkWorldSnapshot::capture(app.world());
+
+        let mut restored = crate::tests::app(ChunkyPlugin::default());
+        let player = restored
+            .world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ONE)))
+            .id();
+        snapshot.clone().apply(restored.world_mut());
+        // Applying again must not duplicate the loaders
+        snapshot.clone().apply(restored.world_mut());
+        restored.update();
+
+        assert!(restored.world().get::<ChunkLoader>(player).is_none());
+        let mut loaders: Vec<_> = restored
+            .world_mut()
+            .query::<(
+                &ChunkLoader,
+                Has<ChunkLoaderDisabled>,
+                Option<&ChunkUnloadRadius>,
+            )>()
+            .iter(restored.world())
+            .map(|(ChunkLoader(radius), disabled, unload_radius)| {
+                (*radius, disabled, unload_radius.map(|radius| radius.0))
+            })
+            .collect();
+        loaders.sort_by_key(|(radius, ..)| radius.to_array());
+        assert_eq!(
+            loaders,
+            [
+                (IVec3::ZERO, false, None),
+                (IVec3::new(1, 0, 1), true, Some(IVec3::splat(3))),
+            ]
+        );
+    }
+
+    #[cfg(feature = "chunk_persistence")]
+    #[test]
+    fn dirty_chunks_stay_dirty() {
+        let mut app = app(ChunkyPlugin::default());
+        app.world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::X), ChunkPersistent, ChunkDirty));
+        app.world_mut().spawn((Chunk, ChunkPos(IVec3::Y)));
+        app.update();
+
+        ChunkWorldSnapshot::capture(app.world()).apply(app.world_mut());
+        app.update();
+        let chunk_manager = app.world().resource::<ChunkManager>();
+        let dirty = chunk_manager.get_chunk(&IVec3::X).unwrap();
+        let clean = chunk_manager.get_chunk(&IVec3::Y).unwrap();
+        assert!(app.world().entity(dirty).contains::<ChunkPersistent>());
+        assert!(app.world().entity(dirty).contains::<ChunkDirty>());
+        assert!(!app.world().entity(clean).contains::<ChunkPersistent>());
+        assert!(!app.world().entity(clean).contains::<ChunkDirty>());
+    }
+
+    #[cfg(all(feature = "serde", feature = "chunk_loader"))]
+    #[test]
+    fn snapshots_round_trip_through_json() {
+        let mut app = app(ChunkyPlugin::default());
+        app.world_mut().spawn((
+            Chunk,
+            ChunkPos(IVec3::new(2, -1, 0)),
+            ChunkLayer(3),
+            ChunkLoadState::Ready,
+        ));
+        app.world_mut().spawn((
+            Transform::from_xyz(100.0, 0.0, 0.0),
+            ChunkLoader(IVec3::new(1, 0, 1)),
+        ));
+        app.update();
+
+        let snapshot = ChunkWorldSnapshot::capture(app.world());
+        assert_eq!(snapshot.loaders[0].radius, IVec3::new(1, 0, 1));
+        assert_eq!(crate::tests::json_round_trip(&snapshot), snapshot);
+    }
+}
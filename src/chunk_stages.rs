@@ -0,0 +1,301 @@
+//! Generation stages that run once the neighbors of a chunk have caught up.
+//!
+//! Some features can only be placed once the terrain around a chunk exists,
+//! e.g. trees or ores crossing chunk borders. Add a [`GenerationStage`] for each
+//! of these phases with [`ChunkGenerationPlugin::with_stage`]. The
+//! [`ChunkGenerator`] output counts as stage `0`; stage `k` of a chunk runs once
+//! all its neighbors completed stage `k - 1`, and the chunk becomes `Ready`
+//! after the last stage. Until then it stays `Generating`, with its progress in
+//! [`ChunkStage`].
+//!
+//! Chunks at the edge of the loaded area are missing neighbors, so they wait at
+//! an earlier stage until the neighbors are loaded.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! struct Terrain;
+//!
+//! impl ChunkGenerator for Terrain {
+//!     type Output = Vec<u8>;
+//!
+//!     fn generate(&self, _pos: IVec3, _ctx: GenerationContext) -> GenerationResult<Vec<u8>> {
+//!         Ok(vec![1; 1000])
+//!     }
+//! }
+//!
+//! struct Trees;
+//!
+//! impl GenerationStage<Vec<u8>> for Trees {
+//!     fn run(&self, data: &mut Vec<u8>, ctx: &StageContext<Vec<u8>>) -> GenerationResult<()> {
+//!         // Continue trees growing over from the -X neighbor
+//!         if let Some(neighbor) = ctx.neighbor(IVec3::NEG_X) {
+//!             data[0] = neighbor[9];
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(ChunkyPlugin::default())
+//!     .add_plugins(ChunkGenerationPlugin::new(Terrain).with_stage(Trees));
+//! ```
+
+use std::{
+    collections::HashMap,
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::Arc,
+    time::Instant,
+};
+
+use bevy::{ecs::entity::EntityHashSet, prelude::*};
+
+use crate::{
+    ChunkLayer, ChunkLoadState, ChunkLoadStateCommands, ChunkManager, ChunkPos, NEIGHBORS_6,
+    NEIGHBORS_26,
+    chunk_generation::{
+        ChunkData, ChunkGenerationFailed, ChunkGenerationRetry, ChunkRng, GenerationBudget,
+        GenerationError, GenerationResult, GenerationRetryPolicy, WorldSeed, chunk_seed,
+        fail_generation,
+    },
+};
+
+#[cfg(doc)]
+use crate::chunk_generation::{ChunkGenerationPlugin, ChunkGenerator};
+
+/// A generation phase that runs on a chunk after its neighbors completed the
+/// previous phase.
+///
+/// Stages run on the main thread. Returning an error, or panicking, fails the
+/// chunk like a failed [`ChunkGenerator`], so it is generated again from
+/// stage `0`.
+pub trait GenerationStage<T>: Send + Sync + 'static {
+    /// Advances the chunk's payload to this stage
+    fn run(&self, data: &mut T, ctx: &StageContext<T>) -> GenerationResult<()>;
+}
+
+/// Information about the chunk a [`GenerationStage`] runs on.
+pub struct StageContext<'a, T> {
+    /// Position of the chunk in chunk coordinates
+    pub chunk_pos: IVec3,
+    /// The [`ChunkLayer`] of the chunk, 0 for the default layer
+    pub layer: i32,
+    /// Size of chunks in world units
+    pub chunk_size: Vec3,
+    /// World position of the chunk's origin corner
+    pub origin: Vec3,
+    /// The current [`WorldSeed`]
+    pub seed: u64,
+    /// The stage being run, starting at `1`
+    pub stage: u8,
+    neighbors: HashMap<IVec3, &'a T>,
+}
+
+impl<T> StageContext<'_, T> {
+    /// Returns the data of the neighbor at `offset`, e.g. `IVec3::NEG_X`.
+    ///
+    /// Every neighbor in the [`StageNeighborhood`] has completed at least the
+    /// previous stage; others return `None`.
+    pub fn neighbor(&self, offset: IVec3) -> Option<&T> {
+        self.neighbors.get(&offset).copied()
+    }
+
+    /// Returns a random number generator unique to this chunk, seed and stage
+    pub fn chunk_rng(&self) -> ChunkRng {
+        ChunkRng::new(chunk_seed(
+            self.seed.wrapping_add(self.stage as u64),
+            self.chunk_pos,
+        ))
+    }
+}
+
+/// Which neighbors must have completed the previous stage before a stage runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum StageNeighborhood {
+    /// The 6 chunks sharing a face (default)
+    #[default]
+    Faces,
+    /// All 26 surrounding chunks
+    All,
+}
+
+impl StageNeighborhood {
+    /// Offsets of the neighbors in the neighborhood
+    pub fn offsets(self) -> &'static [IVec3] {
+        match self {
+            StageNeighborhood::Faces => &NEIGHBORS_6,
+            StageNeighborhood::All => &NEIGHBORS_26,
+        }
+    }
+}
+
+/// The last completed generation stage of a chunk.
+///
+/// Inserted once the [`ChunkGenerator`] output is applied, as stage `0`, when
+/// the generation plugin has stages.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deref)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkStage(pub u8);
+
+/// Fired when a chunk completes a generation stage, including stage `0`.
+#[derive(Message, Debug, Clone)]
+pub struct ChunkStageCompleted {
+    /// The chunk entity
+    pub entity: Entity,
+    /// Position of the chunk in chunk coordinates
+    pub pos: IVec3,
+    /// The completed stage, `0` for the [`ChunkGenerator`] output
+    pub stage: u8,
+}
+
+/// The stages added to a generation plugin
+#[derive(Resource)]
+pub(crate) struct GenerationStages<T> {
+    pub(crate) stages: Vec<Arc<dyn GenerationStage<T>>>,
+    pub(crate) neighborhood: StageNeighborhood,
+}
+
+/// Runs the next stage of every chunk whose neighbors have caught up
+pub(crate) fn advance_chunk_stages<T: Send + Sync + 'static>(world: &mut World) {
+    run_chunk_stages::<T>(world);
+}
+
+/// Runs at most one stage per chunk, within the [`GenerationBudget`], and
+/// returns whether any stage ran
+pub(crate) fn run_chunk_stages<T: Send + Sync + 'static>(world: &mut World) -> bool {
+    let Some(stages) = world.get_resource::<GenerationStages<T>>() else {
+        return false;
+    };
+    let stage_list = stages.stages.clone();
+    let offsets = stages.neighborhood.offsets();
+    let last_stage = stage_list.len() as u8;
+
+    // Progress of every registered chunk, taken before running any stage, so a
+    // chunk never advances twice in one run
+    let mut completed = HashMap::new();
+    let mut waiting = Vec::new();
+    let mut chunks = world.query_filtered::<(
+        Entity,
+        &ChunkPos,
+        &ChunkStage,
+        &ChunkLoadState,
+        Option<&ChunkLayer>,
+    ), With<ChunkData<T>>>();
+    let chunk_manager = world.resource::<ChunkManager>();
+    for (entity, ChunkPos(pos), ChunkStage(stage), load_state, layer) in chunks.iter(world) {
+        let layer = layer.map_or(0, |layer| layer.0);
+        if chunk_manager.get_chunk_in_layer(pos, layer) != Some(entity) {
+            continue;
+        }
+        // Neighbors are looked up on the chunk's own layer
+        let key = pos.extend(layer);
+        match load_state {
+            ChunkLoadState::Generating => {
+                completed.insert(key, (entity, *stage));
+                if *stage < last_stage {
+                    waiting.push((entity, *pos, layer, *stage));
+                }
+            }
+            ChunkLoadState::Ready => {
+                completed.insert(key, (entity, *stage));
+            }
+            _ => {}
+        }
+    }
+
+    let chunk_size = chunk_manager.get_size();
+    let seed = world.get_resource::<WorldSeed>().map_or(0, |seed| seed.0);
+    let budget = world.resource::<GenerationBudget>().clone();
+    let retry_policy = *world.resource::<GenerationRetryPolicy>();
+    let start = Instant::now();
+    let mut applied = 0;
+
+    for (entity, chunk_pos, layer, stage) in waiting {
+        if budget.is_exhausted(applied, start) {
+            break;
+        }
+        let mut neighbors = Vec::with_capacity(offsets.len());
+        for &offset in offsets {
+            match completed.get(&(chunk_pos + offset).extend(layer)) {
+                Some(&(neighbor, neighbor_stage)) if neighbor_stage >= stage => {
+                    neighbors.push((offset, neighbor));
+                }
+                _ => break,
+            }
+        }
+        if neighbors.len() < offsets.len() {
+            continue;
+        }
+        applied += 1;
+
+        let next_stage = stage + 1;
+        let result = {
+            let mut entities: EntityHashSet = neighbors.iter().map(|(_, e)| *e).collect();
+            entities.insert(entity);
+            let Ok(mut fetched) = world.get_entity_mut(&entities) else {
+                continue;
+            };
+            let Some(mut chunk) = fetched.remove(&entity) else {
+                continue;
+            };
+            let neighbors = neighbors
+                .iter()
+                .filter_map(|(offset, neighbor)| {
+                    let ChunkData(data) = fetched.get(neighbor)?.get::<ChunkData<T>>()?;
+                    Some((*offset, data))
+                })
+                .collect();
+            let Some(mut data) = chunk.get_mut::<ChunkData<T>>() else {
+                continue;
+            };
+            let ctx = StageContext {
+                chunk_pos,
+                layer,
+                chunk_size,
+                origin: chunk_pos.as_vec3() * chunk_size,
+                seed,
+                stage: next_stage,
+                neighbors,
+            };
+            let stage = &stage_list[stage as usize];
+            catch_unwind(AssertUnwindSafe(|| stage.run(&mut data.0, &ctx)))
+                .unwrap_or_else(|_| Err(GenerationError::new("generation stage panicked")))
+        };
+
+        match result {
+            Ok(()) => {
+                let mut commands = world.commands();
+                let mut entity_commands = commands.entity(entity);
+                entity_commands.insert(ChunkStage(next_stage));
+                if next_stage == last_stage {
+                    entity_commands.set_load_state(ChunkLoadState::Ready);
+                }
+                world.write_message(ChunkStageCompleted {
+                    entity,
+                    pos: chunk_pos,
+                    stage: next_stage,
+                });
+            }
+            Err(error) => {
+                let retry = world.get::<ChunkGenerationRetry>(entity).copied();
+                fail_generation(
+                    &mut world.commands().entity(entity),
+                    retry.as_ref(),
+                    &retry_policy,
+                    chunk_pos,
+                    &error,
+                );
+                world.write_message(ChunkGenerationFailed {
+                    pos: chunk_pos,
+                    error,
+                });
+            }
+        }
+    }
+    world.flush();
+    applied > 0
+}
@@ -0,0 +1,540 @@
+//! Chunk load states and state-filtered queries.
+//!
+//! Every chunk spawned by the loader or the helpers carries a
+//! [`ChunkLoadState`]. The state is immutable, so it can only change through
+//! [`ChunkLoadStateCommands::set_load_state`] or by inserting a new state.
+//! Illegal transitions are rejected either way. A marker component from
+//! [`load_state`] mirrors the current state, enabling filters like
+//! [`ReadyChunks`]:
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! fn count_ready(ready: Query<&ChunkPos, ReadyChunks>) {
+//!     info!("{} chunks are ready", ready.iter().count());
+//! }
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use bevy::{
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+};
+
+use crate::{Chunk, ChunkLayer, ChunkPos, chunk_progress::ChunkStreamingProgress};
+
+/// The loading lifecycle of a chunk.
+///
+/// Legal transitions:
+/// - `Requested` → `Generating`, `Ready` or `Failed`
+/// - `Generating` → `Ready` or `Failed`
+/// - `Ready` → `Requested` (to generate the chunk again)
+/// - `Failed` → `Requested` or `Generating` (to retry)
+///
+/// Inserting a state through an illegal transition logs a warning and puts
+/// the previous state back when the insert's commands are applied. The chunk
+/// still counts as `Changed<ChunkLoadState>` and `Insert` observers see both
+/// states, whereas [`ChunkLoadStateCommands::set_load_state`] checks the
+/// transition before inserting anything.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[component(
+    immutable,
+    on_insert = on_insert_load_state,
+    on_replace = on_replace_load_state,
+    on_remove = on_remove_load_state
+)]
+pub enum ChunkLoadState {
+    /// The chunk exists but has no content yet (default)
+    #[default]
+    Requested,
+    /// The chunk's content is being generated
+    Generating,
+    /// The chunk's content is available
+    Ready,
+    /// Generating the chunk's content failed
+    Failed,
+}
+
+impl ChunkLoadState {
+    /// Checks if moving from this state to `next` is allowed.
+    ///
+    /// Staying in the same state is always allowed.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use ChunkLoadState::*;
+        self == next
+            || matches!(
+                (self, next),
+                (Requested, Generating | Ready | Failed)
+                    | (Generating, Ready | Failed)
+                    | (Ready, Requested)
+                    | (Failed, Requested | Generating)
+            )
+    }
+
+    /// Returns `next` if moving from this state to it is allowed
+    pub fn transition(self, next: Self) -> Result<Self, InvalidLoadStateTransition> {
+        if self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(InvalidLoadStateTransition {
+                from: self,
+                to: next,
+            })
+        }
+    }
+}
+
+/// Error returned for an illegal [`ChunkLoadState`] transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidLoadStateTransition {
+    /// The state the chunk was in
+    pub from: ChunkLoadState,
+    /// The rejected next state
+    pub to: ChunkLoadState,
+}
+
+impl fmt::Display for InvalidLoadStateTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "illegal chunk load state transition from {:?} to {:?}",
+            self.from, self.to
+        )
+    }
+}
+
+impl std::error::Error for InvalidLoadStateTransition {}
+
+/// Marker components mirroring the current [`ChunkLoadState`].
+///
+/// Kept in sync automatically; use them as query filters.
+pub mod load_state {
+    use bevy::prelude::*;
+
+    /// Present while the chunk is [`ChunkLoadState::Requested`](crate::ChunkLoadState::Requested)
+    #[derive(Component, Debug, Clone, Copy, Default)]
+    pub struct Requested;
+
+    /// Present while the chunk is [`ChunkLoadState::Generating`](crate::ChunkLoadState::Generating)
+    #[derive(Component, Debug, Clone, Copy, Default)]
+    pub struct Generating;
+
+    /// Present while the chunk is [`ChunkLoadState::Ready`](crate::ChunkLoadState::Ready)
+    #[derive(Component, Debug, Clone, Copy, Default)]
+    pub struct Ready;
+
+    /// Present while the chunk is [`ChunkLoadState::Failed`](crate::ChunkLoadState::Failed)
+    #[derive(Component, Debug, Clone, Copy, Default)]
+    pub struct Failed;
+}
+
+/// Query filter matching chunks in the `Requested` state
+pub type RequestedChunks = (With<Chunk>, With<load_state::Requested>);
+
+/// Query filter matching chunks in the `Generating` state
+pub type GeneratingChunks = (With<Chunk>, With<load_state::Generating>);
+
+/// Query filter matching chunks in the `Ready` state
+pub type ReadyChunks = (With<Chunk>, With<load_state::Ready>);
+
+/// Query filter matching chunks in the `Failed` state
+pub type FailedChunks = (With<Chunk>, With<load_state::Failed>);
+
+/// Query filter matching chunks that just became `Ready`
+pub type NewlyReadyChunks = (With<Chunk>, Added<load_state::Ready>);
+
+/// Fired once a chunk becomes `Ready`, after
+/// [`ChunkySystems::PostGenerate`](crate::ChunkySystems::PostGenerate) has run.
+///
+/// See [`ChunkReadyTiming`] for delaying it by a frame.
+#[derive(Message, Debug, Clone)]
+pub struct ChunkReady {
+    /// The chunk entity
+    pub entity: Entity,
+    /// Position of the chunk in chunk coordinates
+    pub pos: IVec3,
+}
+
+/// Decides in which frame [`ChunkReady`] is sent.
+///
+/// Inserted as a resource by [`ChunkyPlugin`](crate::ChunkyPlugin), so it can
+/// be changed at runtime.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub enum ChunkReadyTiming {
+    /// Sent in the frame the chunk becomes ready (default)
+    #[default]
+    SameFrame,
+    /// Sent in the following frame.
+    ///
+    /// Chunks spawned together are all registered by then, even if some of
+    /// them were spawned later in the frame, so meshers reading [`ChunkReady`]
+    /// see a stable set of neighbors.
+    NextFrame,
+}
+
+/// Sends ChunkReady for every chunk that just became ready, or for the chunks
+/// that became ready last frame with ChunkReadyTiming::NextFrame
+pub(crate) fn send_chunk_ready(
+    chunks: Query<(Entity, &ChunkPos), NewlyReadyChunks>,
+    still_ready: Query<(), ReadyChunks>,
+    timing: Option<Res<ChunkReadyTiming>>,
+    mut deferred: Local<Vec<(Entity, IVec3)>>,
+    mut ready: MessageWriter<ChunkReady>,
+) {
+    for (entity, pos) in deferred.drain(..) {
+        if still_ready.contains(entity) {
+            ready.write(ChunkReady { entity, pos });
+        }
+    }
+
+    let next_frame = timing.is_some_and(|timing| *timing == ChunkReadyTiming::NextFrame);
+    for (entity, ChunkPos(pos)) in chunks.iter() {
+        if next_frame {
+            deferred.push((entity, *pos));
+        } else {
+            ready.write(ChunkReady { entity, pos: *pos });
+        }
+    }
+}
+
+/// Extension methods for changing a chunk's [`ChunkLoadState`].
+pub trait ChunkLoadStateCommands {
+    /// Moves the chunk to `next`, logging a warning and keeping the current
+    /// state if the transition is illegal.
+    ///
+    /// Does nothing if the chunk already is in `next`. A chunk without a state
+    /// is treated as `Requested`.
+    fn set_load_state(&mut self, next: ChunkLoadState) -> &mut Self;
+}
+
+impl ChunkLoadStateCommands for EntityCommands<'_> {
+    fn set_load_state(&mut self, next: ChunkLoadState) -> &mut Self {
+        self.queue_silenced(move |mut entity: EntityWorldMut| {
+            let current = entity.get::<ChunkLoadState>().copied();
+            if current == Some(next) {
+                return;
+            }
+            match current.unwrap_or_default().transition(next) {
+                Ok(state) => {
+                    entity.insert(state);
+                }
+                Err(error) => warn!("Chunk {}: {}", entity.id(), error),
+            }
+        })
+    }
+}
+
+/// The states replaced on each chunk, so inserting a new ChunkLoadState can be
+/// checked against the one it replaced
+#[derive(Resource, Debug, Default)]
+pub(crate) struct ReplacedLoadStates {
+    replaced: HashMap<Entity, ChunkLoadState>,
+    /// Chunks being put back into their previous state after an illegal insert
+    reverting: HashSet<Entity>,
+}
+
+/// Syncs the state marker components and the ChunkStreamingProgress with the
+/// new ChunkLoadState, putting the previous state back if the transition is
+/// illegal
+fn on_insert_load_state(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let state = *world.get::<ChunkLoadState>(entity).unwrap();
+    let chunk_pos = progress_pos(&world, entity);
+    if let Some(mut progress) = world.get_resource_mut::<ChunkStreamingProgress>() {
+        progress.add(state, chunk_pos);
+    }
+
+    let previous = world
+        .get_resource_mut::<ReplacedLoadStates>()
+        .and_then(|mut states| {
+            let previous = states.replaced.remove(&entity)?;
+            let reverting = states.reverting.remove(&entity);
+            Some((previous, reverting))
+        });
+    match previous {
+        // The markers still match a reverted or unchanged state
+        Some((previous, reverting)) if reverting || previous == state => return,
+        Some((previous, _)) if !previous.can_transition_to(state) => {
+            warn!(
+                "Chunk {}: {}",
+                entity,
+                InvalidLoadStateTransition {
+                    from: previous,
+                    to: state,
+                }
+            );
+            if let Some(mut states) = world.get_resource_mut::<ReplacedLoadStates>() {
+                states.reverting.insert(entity);
+            }
+            world
+                .commands()
+                .entity(entity)
+                .queue_silenced(move |mut entity: EntityWorldMut| {
+                    entity.insert(previous);
+                });
+            return;
+        }
+        _ => {}
+    }
+
+    world
+        .commands()
+        .entity(entity)
+        .queue_silenced(move |mut entity: EntityWorldMut| {
+            entity.remove::<(
+                load_state::Requested,
+                load_state::Generating,
+                load_state::Ready,
+                load_state::Failed,
+            )>();
+            match state {
+                ChunkLoadState::Requested => entity.insert(load_state::Requested),
+                ChunkLoadState::Generating => entity.insert(load_state::Generating),
+                ChunkLoadState::Ready => entity.insert(load_state::Ready),
+                ChunkLoadState::Failed => entity.insert(load_state::Failed),
+            };
+        });
+}
+
+/// The position of a chunk in the ChunkStreamingProgress, with its layer as `w`
+fn progress_pos(world: &DeferredWorld, entity: Entity) -> Option<IVec4> {
+    let layer = world.get::<ChunkLayer>(entity).map_or(0, |layer| layer.0);
+    world.get::<ChunkPos>(entity).map(|pos| pos.0.extend(layer))
+}
+
+/// Removes the old ChunkLoadState from the ChunkStreamingProgress and keeps it
+/// for the following insert
+fn on_replace_load_state(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let state = *world.get::<ChunkLoadState>(entity).unwrap();
+    let chunk_pos = progress_pos(&world, entity);
+    if let Some(mut progress) = world.get_resource_mut::<ChunkStreamingProgress>() {
+        progress.remove(state, chunk_pos);
+    }
+    if let Some(mut states) = world.get_resource_mut::<ReplacedLoadStates>() {
+        states.replaced.insert(entity, state);
+    }
+}
+
+/// Forgets the replaced state, as no insert follows a removal
+fn on_remove_load_state(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    if let Some(mut states) = world.get_resource_mut::<ReplacedLoadStates>() {
+        states.replaced.remove(&entity);
+        states.reverting.remove(&entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChunkManager, ChunkyPlugin, NEIGHBORS_6, tests::app};
+
+    fn ready_messages(app: &mut App) -> usize {
+        app.world_mut()
+            .resource_mut::<Messages<ChunkReady>>()
+            .drain()
+            .count()
+    }
+
+    #[test]
+    fn illegal_inserts_are_reverted() {
+        let mut app = app(ChunkyPlugin::default());
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLoadState::Ready))
+            .id();
+        app.update();
+
+        let last_run = app.world_mut().increment_change_tick();
+        app.world_mut()
+            .entity_mut(chunk)
+            .insert(ChunkLoadState::Failed);
+        app.world_mut().flush();
+        let entity = app.world().entity(chunk);
+        assert_eq!(entity.get::<ChunkLoadState>(), Some(&ChunkLoadState::Ready));
+        assert!(entity.contains::<load_state::Ready>());
+        assert!(!entity.contains::<load_state::Failed>());
+        // Reverted, yet still marked as changed
+        let ticks = entity.get_change_ticks::<ChunkLoadState>().unwrap();
+        assert!(ticks.is_changed(last_run, app.world().read_change_tick()));
+
+        let progress = app.world().resource::<ChunkStreamingProgress>();
+        assert_eq!((progress.ready, progress.failed), (1, 0));
+        assert!(progress.is_ready(IVec3::ZERO));
+    }
+
+    #[test]
+    fn state_filters_match_the_chunks_in_their_state() {
+        use ChunkLoadState::*;
+        let mut app = app(ChunkyPlugin::default());
+        let mut x = 0;
+        let chunks = [Requested, Generating, Ready, Failed, Ready].map(|state| {
+            x += 1;
+            app.world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::X * x), state))
+                .id()
+        });
+        // Not a chunk, so no filter matches it
+        app.world_mut()
+            .spawn((ChunkPos(IVec3::Y), load_state::Ready));
+        app.update();
+
+        fn matching<F: bevy::ecs::query::QueryFilter>(world: &mut World) -> Vec<Entity> {
+            let mut entities: Vec<_> = world.query_filtered::<Entity, F>().iter(world).collect();
+            entities.sort();
+            entities
+        }
+        let world = app.world_mut();
+        assert_eq!(matching::<RequestedChunks>(world), [chunks[0]]);
+        assert_eq!(matching::<GeneratingChunks>(world), [chunks[1]]);
+        let mut ready = vec![chunks[2], chunks[4]];
+        ready.sort();
+        assert_eq!(matching::<ReadyChunks>(world), ready);
+        assert_eq!(matching::<FailedChunks>(world), [chunks[3]]);
+    }
+
+    #[test]
+    fn legal_inserts_update_the_markers() {
+        let mut app = app(ChunkyPlugin::default());
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLoadState::Requested))
+            .id();
+        app.world_mut()
+            .entity_mut(chunk)
+            .insert(ChunkLoadState::Generating);
+        app.world_mut().flush();
+
+        let entity = app.world().entity(chunk);
+        assert!(entity.contains::<load_state::Generating>());
+        assert!(!entity.contains::<load_state::Requested>());
+    }
+
+    #[test]
+    fn setting_the_same_state_sends_no_second_ready() {
+        let mut app = app(ChunkyPlugin::default());
+        let chunk = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLoadState::Requested))
+            .id();
+        app.world_mut()
+            .commands()
+            .entity(chunk)
+            .set_load_state(ChunkLoadState::Ready);
+        app.update();
+        assert_eq!(ready_messages(&mut app), 1);
+
+        app.world_mut()
+            .commands()
+            .entity(chunk)
+            .set_load_state(ChunkLoadState::Ready);
+        app.update();
+        assert_eq!(ready_messages(&mut app), 0);
+
+        app.world_mut()
+            .entity_mut(chunk)
+            .insert(ChunkLoadState::Ready);
+        app.update();
+        assert_eq!(ready_messages(&mut app), 0);
+        assert_eq!(app.world().resource::<ChunkStreamingProgress>().ready, 1);
+    }
+
+    /// Spawns a 4x4x4 batch of ready chunks, every other slice late in the
+    /// first frame, and returns how many ChunkReady messages were sent and for
+    /// how many interior chunks not all 6 neighbors were registered by then
+    fn ready_with_missing_neighbors(timing: ChunkReadyTiming) -> (usize, usize) {
+        #[derive(Resource, Default)]
+        struct Seen {
+            ready: usize,
+            missing: usize,
+        }
+
+        let mut app = app(ChunkyPlugin::default().with_ready_timing(timing));
+        let (early, late): (Vec<_>, Vec<_>) = (0..64)
+            .map(|index| IVec3::new(index / 16, index / 4 % 4, index % 4))
+            .partition(|pos| pos.x % 2 == 0);
+        for pos in early {
+            app.world_mut()
+                .spawn((Chunk, ChunkPos(pos), ChunkLoadState::Ready));
+        }
+        let mut late = Some(late);
+        app.init_resource::<Seen>()
+            .add_systems(PostUpdate, move |mut commands: Commands| {
+                for pos in late.take().into_iter().flatten() {
+                    commands.spawn((Chunk, ChunkPos(pos), ChunkLoadState::Ready));
+                }
+            })
+            .add_systems(
+                Update,
+                (|mut ready: MessageReader<ChunkReady>,
+                  chunk_manager: Res<ChunkManager>,
+                  mut seen: ResMut<Seen>| {
+                    for ready in ready.read() {
+                        seen.ready += 1;
+                        let interior = ready.pos.cmpgt(IVec3::ZERO).all()
+                            && ready.pos.cmplt(IVec3::splat(3)).all();
+                        if interior
+                            && NEIGHBORS_6
+                                .iter()
+                                .any(|offset| !chunk_manager.is_loaded(&(ready.pos + *offset)))
+                        {
+                            seen.missing += 1;
+                        }
+                    }
+                })
+                .after(send_chunk_ready),
+            );
+        for _ in 0..3 {
+            app.update();
+        }
+        let seen = app.world().resource::<Seen>();
+        (seen.ready, seen.missing)
+    }
+
+    #[test]
+    fn next_frame_readiness_waits_for_the_whole_batch() {
+        assert_eq!(
+            ready_with_missing_neighbors(ChunkReadyTiming::NextFrame),
+            (64, 0)
+        );
+        // The 4 early interior chunks are announced before their late neighbors
+        assert_eq!(
+            ready_with_missing_neighbors(ChunkReadyTiming::SameFrame),
+            (64, 4)
+        );
+    }
+
+    #[test]
+    fn transitions_follow_the_lifecycle() {
+        use ChunkLoadState::*;
+        let states = [Requested, Generating, Ready, Failed];
+        // Rows are the current state, columns the next one, in `states` order
+        let legal = [
+            [true, true, true, true],
+            [false, true, true, true],
+            [true, false, true, false],
+            [true, true, false, true],
+        ];
+        for (from, row) in states.into_iter().zip(legal) {
+            for (to, legal) in states.into_iter().zip(row) {
+                assert_eq!(from.can_transition_to(to), legal, "{from:?} -> {to:?}");
+                assert_eq!(from.transition(to).is_ok(), legal, "{from:?} -> {to:?}");
+            }
+        }
+        assert_eq!(
+            Ready.transition(Generating),
+            Err(InvalidLoadStateTransition {
+                from: Ready,
+                to: Generating,
+            })
+        );
+    }
+}
@@ -7,6 +7,10 @@
 //!   a radius from all [`ChunkLoader`]s
 //! - **Hybrid**: Both conditions must be met (enabled when both resources exist)
 //!
+//! Whenever distance-based unloading is enabled, alone or hybrid, chunks still
+//! generating are unloaded as soon as they leave the unload radius of every
+//! loader, which cancels their generation.
+//!
 //! # Enabling Unloading
 //!
 //! Unloading is opt-in via resources. Insert the appropriate resource(s) to enable:
@@ -34,14 +38,18 @@
 
 use std::time::Instant;
 
-use bevy::prelude::*;
+use bevy::{ecs::schedule::InternedScheduleLabel, prelude::*};
 
-use crate::{Chunk, ChunkManager, ChunkPos};
+#[cfg(feature = "chunk_loader")]
+use crate::GeneratingChunks;
+use crate::{Chunk, ChunkPos, ChunkySystems};
 
 #[cfg(feature = "chunk_loader")]
-use crate::chunk_loader::ChunkLoader;
+use crate::{ChunkManager, chunk_loader::ChunkLoader};
 
-pub struct ChunkUnloaderPlugin;
+pub struct ChunkUnloaderPlugin {
+    pub schedule: InternedScheduleLabel,
+}
 
 impl Plugin for ChunkUnloaderPlugin {
     fn build(&self, app: &mut App) {
@@ -50,44 +58,60 @@ impl Plugin for ChunkUnloaderPlugin {
         // Limit-based systems
         #[cfg(feature = "chunk_loader")]
         app.add_systems(
-            PostUpdate,
+            self.schedule,
             (update_chunk_last_access_by_limit, unload_chunks_by_limit)
                 .chain()
                 .run_if(
                     resource_exists::<ChunkUnloadLimit>
                         .and(not(resource_exists::<ChunkUnloadByDistance>)),
-                ),
+                )
+                .in_set(ChunkySystems::Unload),
         );
 
         #[cfg(not(feature = "chunk_loader"))]
         app.add_systems(
-            PostUpdate,
+            self.schedule,
             (update_chunk_last_access_by_limit, unload_chunks_by_limit)
                 .chain()
-                .run_if(resource_exists::<ChunkUnloadLimit>),
+                .run_if(resource_exists::<ChunkUnloadLimit>)
+                .in_set(ChunkySystems::Unload),
         );
 
         // Distance-based and hybrid systems (require chunk_loader)
         #[cfg(feature = "chunk_loader")]
         {
             app.add_systems(
-                PostUpdate,
+                self.schedule,
                 unload_chunks_by_distance
                     .after(update_chunk_last_access_by_loader)
                     .run_if(
                         resource_exists::<ChunkUnloadByDistance>
                             .and(not(resource_exists::<ChunkUnloadLimit>)),
-                    ),
+                    )
+                    .in_set(ChunkySystems::Unload),
             );
 
             app.add_systems(
-                PostUpdate,
+                self.schedule,
                 (update_chunk_last_access_by_loader, unload_chunks_hybrid)
                     .chain()
                     .run_if(
                         resource_exists::<ChunkUnloadByDistance>
                             .and(resource_exists::<ChunkUnloadLimit>),
-                    ),
+                    )
+                    .in_set(ChunkySystems::Unload),
+            );
+
+            // Distance-only unloading already covers generating chunks, and
+            // limit-only unloading ignores distance
+            app.add_systems(
+                self.schedule,
+                unload_generating_chunks
+                    .run_if(
+                        resource_exists::<ChunkUnloadByDistance>
+                            .and(resource_exists::<ChunkUnloadLimit>),
+                    )
+                    .in_set(ChunkySystems::Unload),
             );
 
             #[cfg(feature = "reflect")]
@@ -192,7 +216,9 @@ pub struct ChunkPinned;
 
 /// Fired when a chunk is about to be despawned by the unload system.
 ///
-/// Users can observe this to save chunk data before removal.
+/// The despawn is applied before readers of this message run, so the chunk's
+/// components are gone by then. To save chunk data before removal, use the
+/// `chunk_persistence` feature or an observer on its removal.
 #[derive(Message, Debug, Clone)]
 pub struct ChunkUnloadEvent {
     pub entity: Entity,
@@ -204,7 +230,10 @@ pub struct ChunkUnloadEvent {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 pub enum ChunkUnloadReason {
-    /// Chunk exceeded distance from all loaders (requires `chunk_loader`)
+    /// Chunk exceeded distance from all loaders (requires `chunk_loader`).
+    ///
+    /// Also sent for chunks still generating in limit-based and hybrid
+    /// unloading.
     OutOfRange,
     /// Chunk evicted due to count limit (LRU)
     LimitExceeded,
@@ -239,12 +268,14 @@ fn update_chunk_last_access_by_limit(
             match last_access {
                 Some(mut access) => access.0 = now,
                 None => {
-                    commands.entity(entity).insert(ChunkLastAccess(now));
+                    commands.entity(entity).try_insert(ChunkLastAccess(now));
                 }
             }
         } else if last_access.is_none() {
             // Ensure all chunks have the component for LRU tracking
-            commands.entity(entity).insert(ChunkLastAccess::default());
+            commands
+                .entity(entity)
+                .try_insert(ChunkLastAccess::default());
         }
     }
 }
@@ -260,7 +291,9 @@ fn update_chunk_last_access_by_limit(
 ) {
     for (entity, last_access) in chunks.iter() {
         if last_access.is_none() {
-            commands.entity(entity).insert(ChunkLastAccess::default());
+            commands
+                .entity(entity)
+                .try_insert(ChunkLastAccess::default());
         }
     }
 }
@@ -300,7 +333,7 @@ fn unload_chunks_by_limit(
             chunk_pos,
             reason: ChunkUnloadReason::LimitExceeded,
         });
-        commands.entity(entity).despawn();
+        unload_chunk(&mut commands, entity);
     }
 }
 
@@ -328,7 +361,7 @@ fn update_chunk_last_access_by_loader(
             match last_access {
                 Some(mut access) => access.0 = now,
                 None => {
-                    commands.entity(entity).insert(ChunkLastAccess(now));
+                    commands.entity(entity).try_insert(ChunkLastAccess(now));
                 }
             }
         }
@@ -351,7 +384,7 @@ fn unload_chunks_by_distance(
                 chunk_pos: chunk_pos.0,
                 reason: ChunkUnloadReason::OutOfRange,
             });
-            commands.entity(entity).despawn();
+            unload_chunk(&mut commands, entity);
         }
     }
 }
@@ -366,6 +399,7 @@ fn unload_chunks_hybrid(
         (Entity, &ChunkPos, Option<&ChunkLastAccess>),
         (With<Chunk>, Without<ChunkPinned>),
     >,
+    generating: Query<(), GeneratingChunks>,
     chunk_manager: Res<ChunkManager>,
     limit: Res<ChunkUnloadLimit>,
 ) {
@@ -377,10 +411,14 @@ fn unload_chunks_hybrid(
 
     let to_remove = chunk_count - limit.max_chunks;
 
-    // Only consider chunks that are out of range
+    // Only consider chunks that are out of range, generating ones are
+    // unloaded by unload_generating_chunks
     let mut candidates: Vec<_> = chunks
         .iter()
-        .filter(|(_, chunk_pos, _)| !is_in_any_unload_radius(chunk_pos.0, &loaders, &chunk_manager))
+        .filter(|(entity, chunk_pos, _)| {
+            !generating.contains(*entity)
+                && !is_in_any_unload_radius(chunk_pos.0, &loaders, &chunk_manager)
+        })
         .map(|(e, pos, access)| {
             let time = access.map(|a| a.0).unwrap_or(Instant::now());
             (e, pos.0, time)
@@ -396,7 +434,35 @@ fn unload_chunks_hybrid(
             chunk_pos,
             reason: ChunkUnloadReason::Hybrid,
         });
-        commands.entity(entity).despawn();
+        unload_chunk(&mut commands, entity);
+    }
+}
+
+/// Unloads chunks still generating once they are out of range in hybrid mode,
+/// cancelling their generation instead of keeping them until the limit is
+/// exceeded.
+///
+/// Does nothing without loaders, as chunks are then spawned by hand.
+#[cfg(feature = "chunk_loader")]
+fn unload_generating_chunks(
+    mut commands: Commands,
+    mut unload_events: MessageWriter<ChunkUnloadEvent>,
+    loaders: Query<(&ChunkLoader, Option<&ChunkUnloadRadius>, &GlobalTransform)>,
+    chunks: Query<(Entity, &ChunkPos), (GeneratingChunks, Without<ChunkPinned>)>,
+    chunk_manager: Res<ChunkManager>,
+) {
+    if loaders.is_empty() {
+        return;
+    }
+    for (entity, chunk_pos) in chunks.iter() {
+        if !is_in_any_unload_radius(chunk_pos.0, &loaders, &chunk_manager) {
+            unload_events.write(ChunkUnloadEvent {
+                entity,
+                chunk_pos: chunk_pos.0,
+                reason: ChunkUnloadReason::OutOfRange,
+            });
+            unload_chunk(&mut commands, entity);
+        }
     }
 }
 
@@ -404,6 +470,15 @@ fn unload_chunks_hybrid(
 // Helpers
 // ============================================================================
 
+/// Despawns an unloaded chunk, or pools it for reuse by the loaders, see
+/// [`ChunkLoaderSettings::pool_size`](crate::prelude::ChunkLoaderSettings::pool_size).
+fn unload_chunk(commands: &mut Commands, entity: Entity) {
+    #[cfg(feature = "chunk_loader")]
+    commands.queue(move |world: &mut World| crate::chunk_loader::pool_or_despawn(world, entity));
+    #[cfg(not(feature = "chunk_loader"))]
+    commands.entity(entity).despawn();
+}
+
 /// Checks if a chunk is within any loader's unload radius.
 #[cfg(feature = "chunk_loader")]
 fn is_in_any_unload_radius(
@@ -424,3 +499,55 @@ fn is_within_radius(pos: IVec3, center: IVec3, radius: IVec3) -> bool {
     let diff = (pos - center).abs();
     diff.x <= radius.x && diff.y <= radius.y && diff.z <= radius.z
 }
+
+#[cfg(all(test, feature = "chunk_loader"))]
+mod tests {
+    use super::*;
+    use crate::{ChunkLoadState, ChunkyPlugin, tests::app};
+
+    /// Runs the unloader on a generating and a ready chunk out of range and
+    /// returns whether each is still there, with the unload messages
+    fn unload_out_of_range(by_distance: bool) -> (bool, bool, Vec<(IVec3, ChunkUnloadReason)>) {
+        let mut app = app(ChunkyPlugin::default());
+        app.insert_resource(ChunkUnloadLimit { max_chunks: 100 });
+        if by_distance {
+            app.insert_resource(ChunkUnloadByDistance);
+        }
+        app.world_mut()
+            .spawn((Transform::default(), ChunkLoader(IVec3::ZERO)));
+        let far = IVec3::new(10, 0, 0);
+        let generating = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(far), ChunkLoadState::Generating))
+            .id();
+        let ready = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(far + IVec3::Y), ChunkLoadState::Ready))
+            .id();
+        app.update();
+        app.update();
+
+        let unloaded = app
+            .world_mut()
+            .resource_mut::<Messages<ChunkUnloadEvent>>()
+            .drain()
+            .map(|unloaded| (unloaded.chunk_pos, unloaded.reason))
+            .collect();
+        (
+            app.world().get_entity(generating).is_ok(),
+            app.world().get_entity(ready).is_ok(),
+            unloaded,
+        )
+    }
+
+    #[test]
+    fn generating_chunks_out_of_range_are_unloaded_in_hybrid_mode() {
+        let far = IVec3::new(10, 0, 0);
+        assert_eq!(
+            unload_out_of_range(true),
+            (false, true, vec![(far, ChunkUnloadReason::OutOfRange)])
+        );
+        // Without distance unloading, distance never unloads a chunk
+        assert_eq!(unload_out_of_range(false), (true, true, vec![]));
+    }
+}
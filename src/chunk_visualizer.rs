@@ -1,16 +1,123 @@
+use crate::Chunk;
+use crate::ChunkLayer;
+use crate::ChunkLoadState;
 use crate::ChunkManager;
+use crate::ChunkPersistent;
 use crate::ChunkPos;
-use bevy::prelude::*;
+use crate::ChunkUpAxis;
+use crate::ChunkySystems;
+use crate::NEIGHBORS_6;
+#[cfg(feature = "chunk_loader")]
+use crate::chunk_loader::{ChunkLoader, ChunkLoaderDisabled, chunks_to_load};
+#[cfg(feature = "chunk_picking")]
+use crate::chunk_picking::SelectedChunk;
+#[cfg(feature = "chunk_unloader")]
+use crate::chunk_unloader::ChunkPinned;
+use crate::{RejectedChunk, UnmanagedChunk};
+use bevy::{
+    asset::RenderAssetUsages,
+    color::palettes::tailwind,
+    gizmos::GizmoPlugin,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+    window::PrimaryWindow,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+};
 
-pub struct ChunkBoundryVisualizerPlugin;
+pub struct ChunkBoundryVisualizerPlugin {
+    /// Toggled with the [`ChunkBoundryVisualizer`] state, otherwise with the
+    /// [`ChunkVisualizerEnabled`] resource
+    pub use_state: bool,
+}
 impl Plugin for ChunkBoundryVisualizerPlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<ChunkBoundryVisualizer>().add_systems(
+        if self.use_state {
+            app.init_state::<ChunkBoundryVisualizer>();
+        } else {
+            app.init_resource::<ChunkVisualizerEnabled>();
+        }
+        app.init_resource::<ChunkVisualizerMode>()
+            .init_resource::<ChunkVisualizerConfig>()
+            .init_resource::<VisualizerDepth>()
+            .add_systems(
+                Update,
+                (
+                    chunk_boundry_visualizer.run_if(|config: Res<ChunkVisualizerConfig>| {
+                        config.backend == VisualizerBackend::Gizmos
+                    }),
+                    warn_orphan_chunks,
+                )
+                    .run_if(visualizer_enabled)
+                    .in_set(ChunkySystems::Visualize),
+            );
+        app.add_systems(
+            Update,
+            stamp_chunk_load_time
+                .before(chunk_boundry_visualizer)
+                .in_set(ChunkySystems::Visualize),
+        );
+        app.add_systems(
+            Update,
+            (toggle_visualizer, shift_y_slice.run_if(visualizer_enabled))
+                .run_if(resource_exists::<ButtonInput<KeyCode>>)
+                .before(ChunkySystems::Visualize),
+        );
+        app.add_systems(
+            Update,
+            (
+                sync_chunk_labels.run_if(labels_shown),
+                despawn_chunk_labels
+                    .run_if(not(labels_shown).and(any_with_component::<ChunkLabel>)),
+            )
+                .in_set(ChunkySystems::Visualize),
+        );
+        #[cfg(feature = "chunk_loader")]
+        app.init_resource::<ChunkLoaderPreview>().add_systems(
             Update,
-            chunk_boundry_visualizer.run_if(in_state(ChunkBoundryVisualizer::On)),
+            chunk_loader_preview
+                .run_if(visualizer_enabled.and(|preview: Res<ChunkLoaderPreview>| preview.enabled))
+                .in_set(ChunkySystems::Visualize),
         );
         #[cfg(feature = "reflect")]
-        app.register_type::<ChunkBoundryVisualizer>();
+        app.register_type::<ChunkBoundryVisualizer>()
+            .register_type::<ChunkVisualizerEnabled>()
+            .register_type::<ChunkVisualizerMode>()
+            .register_type::<ChunkVisualizerConfig>()
+            .register_type::<VisualizerDepth>()
+            .register_type::<ChunkHighlightTarget>()
+            .register_type::<ChunkLoadedAt>()
+            .register_type::<ChunkHeatSource>();
+        #[cfg(all(feature = "reflect", feature = "chunk_loader"))]
+        app.register_type::<ChunkLoaderPreview>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        // Apps without the PbrPlugin can't show the mesh backend
+        if app.world().contains_resource::<Assets<StandardMaterial>>() {
+            app.init_resource::<ChunkWireframeAssets>().add_systems(
+                Update,
+                (
+                    sync_chunk_wireframes.run_if(wireframes_shown),
+                    despawn_chunk_wireframes
+                        .run_if(not(wireframes_shown).and(any_with_component::<ChunkWireframe>)),
+                )
+                    .in_set(ChunkySystems::Visualize),
+            );
+        }
+        // Headless apps without the GizmoPlugin have nothing to draw with
+        if !app.is_plugin_added::<GizmoPlugin>() {
+            return;
+        }
+        app.init_gizmo_group::<ChunkGizmos>().add_systems(
+            Update,
+            apply_visualizer_depth
+                .run_if(resource_changed::<VisualizerDepth>)
+                .before(chunk_boundry_visualizer)
+                .in_set(ChunkySystems::Visualize),
+        );
     }
 }
 
@@ -26,47 +133,1675 @@ pub enum ChunkBoundryVisualizer {
     Off,
 }
 
-/// Shows all existing chunk boundaries using gizmos
+/// Toggles chunk boundary visualization without a state.
+///
+/// Used instead of the [`ChunkBoundryVisualizer`] state when it is turned off
+/// with
+/// [`ChunkyPlugin::with_visualizer_state`](crate::ChunkyPlugin::with_visualizer_state).
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugins(ChunkyPlugin::default().with_visualizer_state(false))
+///     .insert_resource(ChunkVisualizerEnabled(true));
+/// ```
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Deref, DerefMut)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct ChunkVisualizerEnabled(pub bool);
+
+/// Flips the visualizer when the toggle key of the ChunkVisualizerConfig is
+/// pressed
+fn toggle_visualizer(
+    keys: Res<ButtonInput<KeyCode>>,
+    config: Res<ChunkVisualizerConfig>,
+    state: Option<Res<State<ChunkBoundryVisualizer>>>,
+    next_state: Option<ResMut<NextState<ChunkBoundryVisualizer>>>,
+    enabled: Option<ResMut<ChunkVisualizerEnabled>>,
+) {
+    let Some(toggle_key) = config.toggle_key else {
+        return;
+    };
+    if !keys.just_pressed(toggle_key)
+        || config
+            .toggle_modifier
+            .is_some_and(|modifier| !keys.pressed(modifier))
+    {
+        return;
+    }
+    if let (Some(state), Some(mut next_state)) = (state, next_state) {
+        next_state.set(match state.get() {
+            ChunkBoundryVisualizer::On => ChunkBoundryVisualizer::Off,
+            ChunkBoundryVisualizer::Off => ChunkBoundryVisualizer::On,
+        });
+    }
+    if let Some(mut enabled) = enabled {
+        enabled.0 = !enabled.0;
+    }
+}
+
+/// Moves the y slice of the ChunkVisualizerConfig by one chunk when one of
+/// its keys is pressed
+fn shift_y_slice(keys: Res<ButtonInput<KeyCode>>, mut config: ResMut<ChunkVisualizerConfig>) {
+    let pressed = |key: Option<KeyCode>| key.is_some_and(|key| keys.just_pressed(key));
+    let shift =
+        i32::from(pressed(config.y_slice_up_key)) - i32::from(pressed(config.y_slice_down_key));
+    if shift == 0 {
+        return;
+    }
+    if let Some(y_slice) = &config.y_slice {
+        let y_slice = y_slice.start() + shift..=y_slice.end() + shift;
+        config.y_slice = Some(y_slice);
+    }
+}
+
+/// Whether chunk boundaries are drawn, by either the state or the resource
+pub(crate) fn visualizer_enabled(
+    state: Option<Res<State<ChunkBoundryVisualizer>>>,
+    enabled: Option<Res<ChunkVisualizerEnabled>>,
+) -> bool {
+    state.is_some_and(|state| *state.get() == ChunkBoundryVisualizer::On)
+        || enabled.is_some_and(|enabled| enabled.0)
+}
+
+/// Colors and filters of the chunk boundary visualizer, read every frame so
+/// changes apply right away.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn calm_visualizer(mut config: ResMut<ChunkVisualizerConfig>) {
+///     // Only outline the ground of nearby chunks
+///     config.max_draw_distance = Some(100.0);
+///     config.bottom_only = true;
+///     config.ready_color = Color::WHITE;
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct ChunkVisualizerConfig {
+    /// Color of chunks without a [`ChunkLoadState`] (default: green)
+    pub base_color: Color,
+    /// Color of `Requested` chunks (default: yellow)
+    pub pending_color: Color,
+    /// Color of `Generating` chunks (default: orange)
+    pub generating_color: Color,
+    /// Color of `Ready` chunks (default: green)
+    pub ready_color: Color,
+    /// Color of `Failed` chunks (default: red)
+    pub failed_color: Color,
+    /// Color of chunks with [`ChunkPersistent`], or pinned with `ChunkPinned`
+    /// with the `chunk_unloader` feature, whatever their load state (default:
+    /// blue)
+    pub persistent_color: Color,
+    /// Only chunks whose bounds are at most this far from the active camera
+    /// are drawn. All chunks are drawn without a camera. (default: no limit)
+    pub max_draw_distance: Option<f32>,
+    /// Draws at most this many chunks, the ones nearest to the active camera
+    /// first (default: no limit)
+    pub max_drawn_chunks: Option<usize>,
+    /// Draws only the bottom rectangle of each chunk, along the
+    /// [`ChunkUpAxis`], instead of all 12 edges (default: false)
+    pub bottom_only: bool,
+    /// Which edges of each chunk are drawn (default: [`DrawMode::Full`])
+    pub draw_mode: DrawMode,
+    /// Shows the position of each drawn chunk as text over its center, see
+    /// [`ChunkLabel`] (default: false)
+    pub show_labels: bool,
+    /// Chunks at most this thick along an axis, e.g. with a chunk size of
+    /// `(32.0, 0.0, 32.0)`, are drawn as a single rectangle across that axis,
+    /// preferring the [`ChunkUpAxis`] (default: 0.001)
+    pub flat_threshold: f32,
+    /// Color of the chunks picked by a [`ChunkHighlightTarget`], overriding
+    /// the [`ChunkVisualizerMode`] (default: white)
+    pub highlight_color: Color,
+    /// Also draws the 6 chunks next to a highlighted chunk, in a faint
+    /// `highlight_color` (default: false)
+    pub highlight_neighbors: bool,
+    /// Key toggling the visualizer, either the [`ChunkBoundryVisualizer`]
+    /// state or the [`ChunkVisualizerEnabled`] resource. `None` turns the
+    /// toggle off, e.g. in release builds. (default: F3)
+    pub toggle_key: Option<KeyCode>,
+    /// Key that must be held while pressing the `toggle_key`, e.g. Alt for
+    /// Alt+F3 (default: none)
+    pub toggle_modifier: Option<KeyCode>,
+    /// Only chunks whose height along the [`ChunkUpAxis`] is in this range are
+    /// drawn and labeled, ghosts included (default: all heights).
+    ///
+    /// Despite its name, the slice follows the up axis rather than world Y:
+    /// with [`ChunkUpAxis::Z`] it selects Z chunk coordinates, so it always
+    /// cuts the world into horizontal layers.
+    pub y_slice: Option<RangeInclusive<i32>>,
+    /// Key moving the `y_slice` up by one chunk while the visualizer is on
+    /// (default: PageUp)
+    pub y_slice_up_key: Option<KeyCode>,
+    /// Key moving the `y_slice` down by one chunk while the visualizer is on
+    /// (default: PageDown)
+    pub y_slice_down_key: Option<KeyCode>,
+    /// Color of orphaned chunks, which are crossed out: chunk entities the
+    /// [`ChunkManager`] doesn't map their position to, e.g. duplicates kept by
+    /// [`ChunkCollisionPolicy::Warn`](crate::ChunkCollisionPolicy::Warn)
+    /// (default: fuchsia)
+    pub orphan_color: Color,
+    /// Color freshly loaded chunks flash in before fading to their usual
+    /// color over the `fresh_fade_secs` (default: light blue)
+    pub fresh_color: Color,
+    /// Seconds a chunk takes to fade from the `fresh_color` after it was
+    /// loaded, see [`ChunkLoadedAt`]. `0.0` turns the flash off. (default: 0.0)
+    pub fresh_fade_secs: f32,
+    /// How chunks are drawn (default: [`VisualizerBackend::Gizmos`])
+    pub backend: VisualizerBackend,
+    /// Number of [`ChunkHeatSource`]s at which a chunk is drawn coldest with
+    /// [`DrawMode::Heatmap`] (default: 1)
+    pub heatmap_min: usize,
+    /// Number of [`ChunkHeatSource`]s at which a chunk is drawn hottest with
+    /// [`DrawMode::Heatmap`] (default: 20)
+    pub heatmap_max: usize,
+    /// Color of the dashed boxes of unloaded chunks within a loader's radius
+    /// with [`DrawMode::IncludeGhosts`] (default: faint gray)
+    pub ghost_color: Color,
+}
+
+/// How the boundary visualizer draws chunks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum VisualizerBackend {
+    /// [`ChunkGizmos`] lines, redrawn every frame (default)
+    #[default]
+    Gizmos,
+    /// A [`ChunkWireframe`] child entity per chunk with a shared line mesh and
+    /// an unlit `StandardMaterial` per color. Costs nothing while chunks don't
+    /// change and is seen by every camera, e.g. in screenshots from other
+    /// render layers.
+    ///
+    /// Needs the `PbrPlugin`. Always draws all 12 edges, colored by the
+    /// [`ChunkVisualizerMode`] or the `orphan_color`; highlights, the
+    /// [`DrawMode`] and the fresh flash only apply to gizmos.
+    Mesh,
+}
+
+/// Which edges of a chunk the boundary visualizer draws
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum DrawMode {
+    /// All edges of every chunk (default)
+    #[default]
+    Full,
+    /// Only the edges on the surface of the loaded chunks: an edge is skipped
+    /// when the neighbors across both faces it borders are loaded, so edges
+    /// inside a block of chunks vanish and isolated chunks are drawn fully
+    OuterShell,
+    /// Only the chunks with a face towards an unloaded chunk, see
+    /// [`ChunkManager::boundary_chunks`], drawn fully. Orphaned chunks are
+    /// always drawn.
+    FrontierOnly,
+    /// Only the chunks containing a [`ChunkHeatSource`], drawn fully and
+    /// colored from blue to red as their number of sources goes from
+    /// `heatmap_min` to `heatmap_max`, overriding the [`ChunkVisualizerMode`]
+    Heatmap,
+    /// All edges of every chunk, plus dashed boxes in the `ghost_color` for
+    /// the unloaded chunks within the radius of an enabled [`ChunkLoader`],
+    /// the ones it would load next. Ghosts are culled together with the
+    /// chunks and need the `chunk_loader` feature.
+    IncludeGhosts,
+}
+
+impl Default for ChunkVisualizerConfig {
+    fn default() -> Self {
+        Self {
+            base_color: tailwind::GREEN_500.into(),
+            pending_color: tailwind::YELLOW_500.into(),
+            generating_color: tailwind::ORANGE_500.into(),
+            ready_color: tailwind::GREEN_500.into(),
+            failed_color: tailwind::RED_500.into(),
+            persistent_color: tailwind::BLUE_500.into(),
+            max_draw_distance: None,
+            max_drawn_chunks: None,
+            bottom_only: false,
+            draw_mode: DrawMode::Full,
+            show_labels: false,
+            flat_threshold: 0.001,
+            highlight_color: Color::WHITE,
+            highlight_neighbors: false,
+            toggle_key: Some(KeyCode::F3),
+            toggle_modifier: None,
+            y_slice: None,
+            y_slice_up_key: Some(KeyCode::PageUp),
+            y_slice_down_key: Some(KeyCode::PageDown),
+            orphan_color: tailwind::FUCHSIA_500.into(),
+            fresh_color: tailwind::SKY_300.into(),
+            fresh_fade_secs: 0.0,
+            backend: VisualizerBackend::Gizmos,
+            heatmap_min: 1,
+            heatmap_max: 20,
+            ghost_color: Color::from(tailwind::GRAY_400).with_alpha(0.4),
+        }
+    }
+}
+
+/// Highlights a chunk picked by this entity in the
+/// [`highlight_color`](ChunkVisualizerConfig::highlight_color), e.g. the chunk
+/// the player is in.
+///
+/// Only loaded chunks are highlighted. With the `chunk_picking` feature, the
+/// chunk selected with the cursor is highlighted as well.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn setup(mut commands: Commands) {
+///     // The chunk the player stands in
+///     commands.spawn((Transform::default(), ChunkHighlightTarget::Position));
+///     // The chunk under the cursor, on the ground at height 0
+///     commands.spawn((Camera3d::default(), ChunkHighlightTarget::Cursor { height: 0.0 }));
+/// }
+/// ```
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub enum ChunkHighlightTarget {
+    /// The chunk containing the entity (default)
+    #[default]
+    Position,
+    /// The chunk where the ray from the entity's [`Camera`] through the
+    /// cursor hits the ground plane at `height` along the [`ChunkUpAxis`]
+    Cursor { height: f32 },
+}
+
+/// The elapsed [`Time`] in seconds when a chunk was loaded, added to every
+/// [`Chunk`] by the visualizer plugin.
+///
+/// Drives the [`fresh_color`](ChunkVisualizerConfig::fresh_color) flash of
+/// newly loaded chunks.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkLoadedAt(pub f32);
+
+/// Adds the ChunkLoadedAt of newly loaded chunks
+fn stamp_chunk_load_time(
+    mut commands: Commands,
+    time: Res<Time>,
+    chunks: Query<Entity, Added<Chunk>>,
+) {
+    for entity in chunks.iter() {
+        commands
+            .entity(entity)
+            .try_insert(ChunkLoadedAt(time.elapsed_secs()));
+    }
+}
+
+/// Blends from the `fresh` color to the chunk's usual `color` as its `age`
+/// goes from 0 to `fade_secs` seconds
+pub(crate) fn fade_from_fresh(fresh: Color, color: Color, age: f32, fade_secs: f32) -> Color {
+    if fade_secs <= 0.0 {
+        return color;
+    }
+    fresh.mix(&color, (age / fade_secs).clamp(0.0, 1.0))
+}
+
+/// The UI text showing the position of a chunk, pointing at the chunk.
+///
+/// Spawned for every drawn chunk while [`ChunkVisualizerConfig::show_labels`]
+/// is on, and despawned together with its chunk, once the chunk is culled, or
+/// when labels are turned off. Labels follow their chunk's center as seen by
+/// the active camera; style them by changing their `TextFont` or `TextColor`.
+#[derive(Component, Debug)]
+#[relationship(relationship_target = ChunkLabels)]
+pub struct ChunkLabel(pub Entity);
+
+/// The [`ChunkLabel`] of a chunk
+#[derive(Component, Debug)]
+#[relationship_target(relationship = ChunkLabel, linked_spawn)]
+pub struct ChunkLabels(Entity);
+
+/// Whether chunk labels are shown
+fn labels_shown(
+    state: Option<Res<State<ChunkBoundryVisualizer>>>,
+    enabled: Option<Res<ChunkVisualizerEnabled>>,
+    config: Res<ChunkVisualizerConfig>,
+) -> bool {
+    config.show_labels && visualizer_enabled(state, enabled)
+}
+
+/// Spawns labels for the drawn chunks, despawns the ones of culled chunks and
+/// moves the rest over their chunk
+fn sync_chunk_labels(
+    mut commands: Commands,
+    chunk_manager: Res<ChunkManager>,
+    config: Res<ChunkVisualizerConfig>,
+    up_axis: Option<Res<ChunkUpAxis>>,
+    chunks: Query<(Entity, &ChunkPos)>,
+    mut labels: Query<(Entity, &ChunkLabel, &mut Node, &mut Visibility)>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    let camera = active_camera(&cameras);
+    let half_size = chunk_manager.get_size() / 2.0;
+    // Centers of the drawn chunks, left with the ones without a label
+    let mut unlabeled: HashMap<Entity, (IVec3, Vec3)> = cull_chunks(
+        &chunk_manager,
+        &config,
+        up_axis.map_or(ChunkUpAxis::Y, |up_axis| *up_axis),
+        camera.map(|(_, transform)| transform.translation()),
+        chunks
+            .iter()
+            .map(|(entity, ChunkPos(pos))| (*pos, (entity, *pos))),
+    )
+    .into_iter()
+    .map(|(_, origin, (entity, pos))| (entity, (pos, origin + half_size)))
+    .collect();
+    let to_viewport = |center: Vec3| {
+        camera.and_then(|(camera, transform)| camera.world_to_viewport(transform, center).ok())
+    };
+
+    for (label, ChunkLabel(chunk), mut node, mut visibility) in labels.iter_mut() {
+        let Some((_, center)) = unlabeled.remove(chunk) else {
+            commands.entity(label).despawn();
+            continue;
+        };
+        match to_viewport(center) {
+            Some(point) => {
+                node.left = Val::Px(point.x);
+                node.top = Val::Px(point.y);
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+    for (chunk, (pos, center)) in unlabeled {
+        let point = to_viewport(center);
+        commands.spawn((
+            ChunkLabel(chunk),
+            Text::new(format!("{}, {}, {}", pos.x, pos.y, pos.z)),
+            TextFont::from_font_size(14.0),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(point.map_or(0.0, |point| point.x)),
+                top: Val::Px(point.map_or(0.0, |point| point.y)),
+                ..default()
+            },
+            if point.is_some() {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+        ));
+    }
+}
+
+/// Despawns all chunk labels once they are turned off
+fn despawn_chunk_labels(mut commands: Commands, labels: Query<Entity, With<ChunkLabel>>) {
+    for label in labels.iter() {
+        commands.entity(label).despawn();
+    }
+}
+
+/// The child entity drawing a chunk with [`VisualizerBackend::Mesh`].
+///
+/// Spawned for every drawn chunk while the visualizer is on, and despawned
+/// together with its chunk, once the chunk is culled, or when the visualizer
+/// is turned off or switched to another backend.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct ChunkWireframe;
+
+/// The mesh shared by all ChunkWireframes, and their materials by color
+#[derive(Resource)]
+struct ChunkWireframeAssets {
+    /// The 12 edges of a unit cube from the origin, scaled to the chunk size
+    mesh: Handle<Mesh>,
+    materials: HashMap<[u32; 4], Handle<StandardMaterial>>,
+}
+
+impl FromWorld for ChunkWireframeAssets {
+    fn from_world(world: &mut World) -> Self {
+        let corners: Vec<[f32; 3]> = (0..8)
+            .map(|i| [(i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32])
+            .collect();
+        // Each edge joins two corners differing in one bit
+        let edges = (0..8u16)
+            .flat_map(|i| {
+                [1, 2, 4]
+                    .into_iter()
+                    .filter(move |bit| i & bit == 0)
+                    .flat_map(move |bit| [i, i | bit])
+            })
+            .collect();
+        let mesh = Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, corners)
+            .with_inserted_indices(Indices::U16(edges));
+        Self {
+            mesh: world.resource_mut::<Assets<Mesh>>().add(mesh),
+            materials: HashMap::new(),
+        }
+    }
+}
+
+impl ChunkWireframeAssets {
+    /// The unlit material of a color, added on first use
+    fn material(
+        &mut self,
+        color: Color,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        let key = color.to_linear().to_f32_array().map(f32::to_bits);
+        self.materials
+            .entry(key)
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: color,
+                    unlit: true,
+                    alpha_mode: if color.alpha() < 1.0 {
+                        AlphaMode::Blend
+                    } else {
+                        AlphaMode::Opaque
+                    },
+                    ..default()
+                })
+            })
+            .clone()
+    }
+}
+
+/// Whether chunks are drawn with ChunkWireframes
+fn wireframes_shown(
+    state: Option<Res<State<ChunkBoundryVisualizer>>>,
+    enabled: Option<Res<ChunkVisualizerEnabled>>,
+    config: Res<ChunkVisualizerConfig>,
+) -> bool {
+    config.backend == VisualizerBackend::Mesh && visualizer_enabled(state, enabled)
+}
+
+/// Spawns wireframes for the drawn chunks, despawns the ones of culled chunks
+/// and recolors the rest
+fn sync_chunk_wireframes(
+    mut commands: Commands,
+    chunk_manager: Res<ChunkManager>,
+    mode: Res<ChunkVisualizerMode>,
+    config: Res<ChunkVisualizerConfig>,
+    up_axis: Option<Res<ChunkUpAxis>>,
+    mut assets: ResMut<ChunkWireframeAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    chunks: Query<(
+        Entity,
+        &ChunkPos,
+        Option<&ChunkLoadState>,
+        Has<ChunkPersistent>,
+    )>,
+    #[cfg(feature = "chunk_unloader")] pinned: Query<(), With<ChunkPinned>>,
+    managed: Query<
+        Option<&ChunkLayer>,
+        (With<Chunk>, Without<UnmanagedChunk>, Without<RejectedChunk>),
+    >,
+    mut wireframes: Query<
+        (
+            Entity,
+            &ChildOf,
+            &mut MeshMaterial3d<StandardMaterial>,
+            &mut Transform,
+        ),
+        With<ChunkWireframe>,
+    >,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    let chunk_size = chunk_manager.get_size();
+    let up_axis = up_axis.map_or(ChunkUpAxis::Y, |up_axis| *up_axis);
+    let camera = active_camera(&cameras).map(|(_, transform)| transform.translation());
+    // Colors of the drawn chunks, left with the ones without a wireframe
+    let mut unframed: HashMap<Entity, Color> = cull_chunks(
+        &chunk_manager,
+        &config,
+        up_axis,
+        camera,
+        chunks.iter().map(|chunk| (chunk.1.0, chunk)),
+    )
+    .into_iter()
+    .map(
+        |(_, _, (entity, ChunkPos(chunk_pos), load_state, persistent))| {
+            #[cfg(feature = "chunk_unloader")]
+            let pinned = pinned.contains(entity);
+            #[cfg(not(feature = "chunk_unloader"))]
+            let pinned = false;
+            let persistent = pinned || persistent;
+            let color = if managed
+                .get(entity)
+                .is_ok_and(|layer| is_orphan_chunk(&chunk_manager, entity, *chunk_pos, layer))
+            {
+                config.orphan_color
+            } else {
+                color_for_chunk(&mode, &config, up_axis, *chunk_pos, load_state, persistent)
+            };
+            (entity, color)
+        },
+    )
+    .collect();
+
+    // The unit cube mesh spans the chunk from its origin corner
+    let corner = Transform::from_translation(-chunk_manager.anchor_offset()).with_scale(chunk_size);
+    for (wireframe, ChildOf(chunk), mut material, mut transform) in wireframes.iter_mut() {
+        let Some(color) = unframed.remove(chunk) else {
+            commands.entity(wireframe).despawn();
+            continue;
+        };
+        let handle = assets.material(color, &mut materials);
+        if material.0 != handle {
+            material.0 = handle;
+        }
+        transform.set_if_neq(corner);
+    }
+    for (chunk, color) in unframed {
+        commands.spawn((
+            ChunkWireframe,
+            ChildOf(chunk),
+            Mesh3d(assets.mesh.clone()),
+            MeshMaterial3d(assets.material(color, &mut materials)),
+            corner,
+        ));
+    }
+}
+
+/// Despawns all wireframes once the visualizer is turned off or uses gizmos
+fn despawn_chunk_wireframes(
+    mut commands: Commands,
+    wireframes: Query<Entity, With<ChunkWireframe>>,
+) {
+    for wireframe in wireframes.iter() {
+        commands.entity(wireframe).despawn();
+    }
+}
+
+/// Decides how the chunk boundaries are colored.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn setup(mut commands: Commands) {
+///     // Blue at the bottom of the world, white at the top
+///     commands.insert_resource(ChunkVisualizerMode::ColorByHeight {
+///         low: Color::srgb(0.0, 0.0, 1.0),
+///         high: Color::WHITE,
+///         min_y: -8,
+///         max_y: 8,
+///     });
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub enum ChunkVisualizerMode {
+    /// Colored by [`ChunkLoadState`] with the colors of the
+    /// [`ChunkVisualizerConfig`] (default)
+    #[default]
+    ByLoadState,
+    /// Blends from `low` to `high` as the chunk's height along the
+    /// [`ChunkUpAxis`] goes from `min_y` to `max_y`
+    ColorByHeight {
+        low: Color,
+        high: Color,
+        min_y: i32,
+        max_y: i32,
+    },
+}
+
+/// The gizmo config group chunk boundaries and loader outlines are drawn
+/// with, so their [`GizmoConfig`] can be changed without affecting other
+/// gizmos.
+///
+/// Registered when the [`GizmoPlugin`] is added, e.g. by `DefaultPlugins`.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn thin_chunk_lines(mut config_store: ResMut<GizmoConfigStore>) {
+///     let (config, _) = config_store.config_mut::<ChunkGizmos>();
+///     config.line.width = 1.0;
+/// }
+/// ```
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct ChunkGizmos;
+
+/// Whether chunk boundaries are hidden behind geometry or drawn on top of it.
+///
+/// Applied to the [`GizmoConfig`] of [`ChunkGizmos`] whenever it changes:
+/// `Overlay` sets its `depth_bias` to -1 and turns off `line.perspective`, so
+/// boundaries of occluded chunks stay visible at any distance, and `Tested`
+/// sets the `depth_bias` back to 0.
+///
+/// Overlay only works as long as the config isn't changed afterwards, and the
+/// camera renders the config's `render_layers`.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn show_hidden_chunks(mut depth: ResMut<VisualizerDepth>) {
+///     *depth = VisualizerDepth::Overlay;
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub enum VisualizerDepth {
+    /// Geometry in front of a boundary hides it (default)
+    #[default]
+    Tested,
+    /// Boundaries are drawn on top of all geometry
+    Overlay,
+}
+
+/// Updates the gizmo config of chunk boundaries to the [`VisualizerDepth`]
+fn apply_visualizer_depth(depth: Res<VisualizerDepth>, mut config_store: ResMut<GizmoConfigStore>) {
+    let (config, _) = config_store.config_mut::<ChunkGizmos>();
+    match *depth {
+        VisualizerDepth::Tested => config.depth_bias = 0.0,
+        VisualizerDepth::Overlay => {
+            config.depth_bias = -1.0;
+            config.line.perspective = false;
+        }
+    }
+}
+
+/// Outlines the region each [`ChunkLoader`] wants loaded, while the
+/// visualizer is [`On`](ChunkBoundryVisualizer::On) or
+/// [`ChunkVisualizerEnabled`].
+///
+/// Comparing the outline with the chunk boundaries inside shows how far
+/// loading lags behind, e.g. when the generation budget is too small.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn show_load_regions(mut preview: ResMut<ChunkLoaderPreview>) {
+///     preview.enabled = true;
+/// }
+/// ```
+#[cfg(feature = "chunk_loader")]
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct ChunkLoaderPreview {
+    /// Whether the outlines are drawn (default: false)
+    pub enabled: bool,
+    /// Color of the outlines (default: cyan)
+    pub color: Color,
+}
+
+#[cfg(feature = "chunk_loader")]
+impl Default for ChunkLoaderPreview {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: tailwind::CYAN_400.into(),
+        }
+    }
+}
+
+/// Draws the load region of every enabled ChunkLoader
+#[cfg(feature = "chunk_loader")]
+fn chunk_loader_preview(
+    chunk_manager: Res<ChunkManager>,
+    preview: Res<ChunkLoaderPreview>,
+    loaders: Query<(&GlobalTransform, &ChunkLoader), Without<ChunkLoaderDisabled>>,
+    mut gizmos: Gizmos<ChunkGizmos>,
+) {
+    for (transform, ChunkLoader(radius)) in loaders.iter() {
+        let center = chunk_manager.get_chunk_pos(&transform.translation());
+        let min = chunk_manager.get_world_pos(&(center - *radius));
+        let max = chunk_manager.get_world_pos(&(center + *radius + IVec3::ONE));
+        gizmos.cuboid(
+            Transform::from_translation((min + max) / 2.0).with_scale(max - min),
+            preview.color,
+        );
+    }
+}
+
+/// The active camera rendering first, which chunks are culled against
+fn active_camera<'a>(
+    cameras: &'a Query<(&Camera, &GlobalTransform)>,
+) -> Option<(&'a Camera, &'a GlobalTransform)> {
+    cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .min_by_key(|(camera, _)| camera.order)
+}
+
+/// Keeps the chunks within the y slice, draw distance and count limits of the
+/// [`ChunkVisualizerConfig`], with their distance to the camera and origin
+fn cull_chunks<T>(
+    chunk_manager: &ChunkManager,
+    config: &ChunkVisualizerConfig,
+    up_axis: ChunkUpAxis,
+    camera: Option<Vec3>,
+    chunks: impl Iterator<Item = (IVec3, T)>,
+) -> Vec<(f32, Vec3, T)> {
+    let chunk_size = chunk_manager.get_size();
+    let mut drawn: Vec<_> = chunks
+        .filter(|(chunk_pos, _)| {
+            config
+                .y_slice
+                .as_ref()
+                .is_none_or(|y_slice| y_slice.contains(&up_axis.height(*chunk_pos)))
+        })
+        .filter_map(|(chunk_pos, chunk)| {
+            let origin = chunk_manager.get_world_pos(&chunk_pos);
+            let Some(camera) = camera else {
+                return Some((0.0, origin, chunk));
+            };
+            let distance = camera.clamp(origin, origin + chunk_size).distance(camera);
+            let in_range = config
+                .max_draw_distance
+                .is_none_or(|max_draw_distance| distance <= max_draw_distance);
+            in_range.then_some((distance, origin, chunk))
+        })
+        .collect();
+    if let Some(max_drawn_chunks) = config.max_drawn_chunks
+        && drawn.len() > max_drawn_chunks
+    {
+        if max_drawn_chunks > 0 {
+            drawn.select_nth_unstable_by(max_drawn_chunks - 1, |a, b| a.0.total_cmp(&b.0));
+        }
+        drawn.truncate(max_drawn_chunks);
+    }
+    drawn
+}
+
+/// Whether a chunk entity is orphaned: the [`ChunkManager`] maps its position
+/// on its layer to another entity or to none at all
+pub(crate) fn is_orphan_chunk(
+    chunk_manager: &ChunkManager,
+    entity: Entity,
+    chunk_pos: IVec3,
+    layer: Option<&ChunkLayer>,
+) -> bool {
+    let layer = layer.map_or(0, |layer| layer.0);
+    chunk_manager.get_chunk_in_layer(&chunk_pos, layer) != Some(entity)
+}
+
+/// Logs the number of orphaned chunks at most once per second, as the
+/// visualizer would otherwise repeat it every frame
+fn warn_orphan_chunks(
+    chunk_manager: Res<ChunkManager>,
+    time: Res<Time>,
+    chunks: Query<
+        (Entity, &ChunkPos, Option<&ChunkLayer>),
+        (With<Chunk>, Without<UnmanagedChunk>, Without<RejectedChunk>),
+    >,
+    mut last_warning: Local<Option<f32>>,
+) {
+    let now = time.elapsed_secs();
+    if last_warning.is_some_and(|last_warning| now - last_warning < 1.0) {
+        return;
+    }
+    let orphans: Vec<IVec3> = chunks
+        .iter()
+        .filter(|(entity, ChunkPos(pos), layer)| {
+            is_orphan_chunk(&chunk_manager, *entity, *pos, *layer)
+        })
+        .map(|(_, ChunkPos(pos), _)| *pos)
+        .collect();
+    if orphans.is_empty() {
+        return;
+    }
+    let positions: HashSet<&IVec3> = orphans.iter().collect();
+    *last_warning = Some(now);
+    warn!(
+        "{} chunk entities at {} positions are not registered with the ChunkManager",
+        orphans.len(),
+        positions.len()
+    );
+}
+
+/// The bit of the axis along which chunks of `chunk_size` are flat, in the
+/// corner indices of the boundary visualizer, preferring the `up_bit`
+pub(crate) fn flat_bit(chunk_size: Vec3, up_bit: usize, threshold: f32) -> Option<usize> {
+    let flat = |bit: usize| chunk_size[bit.trailing_zeros() as usize].abs() <= threshold;
+    [up_bit, 1, 2, 4].into_iter().find(|&bit| flat(bit))
+}
+
+/// Counts the chunk an entity is in towards its heat with
+/// [`DrawMode::Heatmap`], e.g. for NPCs or physics bodies, to spot simulation
+/// hotspots.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn spawn_npc(mut commands: Commands, mut config: ResMut<ChunkVisualizerConfig>) {
+///     commands.spawn((Transform::default(), ChunkHeatSource));
+///     config.draw_mode = DrawMode::Heatmap;
+/// }
+/// ```
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkHeatSource;
+
+/// The [`DrawMode::Heatmap`] color of a chunk with `count` heat sources, from
+/// blue at `min` to red at `max`. Chunks without any have no color.
+pub(crate) fn heat_color(count: usize, min: usize, max: usize) -> Option<Color> {
+    if count == 0 {
+        return None;
+    }
+    let range = max.saturating_sub(min).max(1) as f32;
+    let t = (count.saturating_sub(min) as f32 / range).min(1.0);
+    Some(Color::from(tailwind::BLUE_500).mix(&tailwind::RED_500.into(), t))
+}
+
+/// Whether the [`DrawMode`] draws the chunk at `chunk_pos`, given the chunks
+/// on the `frontier` with [`DrawMode::FrontierOnly`] and whether the chunk is
+/// `heated` with [`DrawMode::Heatmap`]
+fn shown_in_mode(
+    draw_mode: DrawMode,
+    frontier: Option<&HashSet<IVec3>>,
+    heated: bool,
+    chunk_pos: &IVec3,
+) -> bool {
+    match draw_mode {
+        DrawMode::FrontierOnly => frontier.is_some_and(|frontier| frontier.contains(chunk_pos)),
+        DrawMode::Heatmap => heated,
+        DrawMode::Full | DrawMode::OuterShell | DrawMode::IncludeGhosts => true,
+    }
+}
+
+/// The color of a chunk's boundary in the [`ChunkVisualizerMode`]. Persistent
+/// and pinned chunks only stand out when colored by load state.
+pub(crate) fn color_for_chunk(
+    mode: &ChunkVisualizerMode,
+    config: &ChunkVisualizerConfig,
+    up_axis: ChunkUpAxis,
+    chunk_pos: IVec3,
+    load_state: Option<&ChunkLoadState>,
+    persistent: bool,
+) -> Color {
+    match *mode {
+        ChunkVisualizerMode::ByLoadState if persistent => config.persistent_color,
+        ChunkVisualizerMode::ByLoadState => match load_state {
+            None => config.base_color,
+            Some(ChunkLoadState::Ready) => config.ready_color,
+            Some(ChunkLoadState::Requested) => config.pending_color,
+            Some(ChunkLoadState::Generating) => config.generating_color,
+            Some(ChunkLoadState::Failed) => config.failed_color,
+        },
+        ChunkVisualizerMode::ColorByHeight {
+            low,
+            high,
+            min_y,
+            max_y,
+        } => {
+            let range = (max_y - min_y).max(1) as f32;
+            let height = up_axis.height(chunk_pos);
+            let t = ((height - min_y) as f32 / range).clamp(0.0, 1.0);
+            low.mix(&high, t)
+        }
+    }
+}
+
+/// Shows the chunk boundaries in range of the camera using gizmos, colored by
+/// the ChunkVisualizerMode
 #[cfg(feature = "chunk_visualizer")]
 fn chunk_boundry_visualizer(
     chunk_manager: Res<ChunkManager>,
-    chunks: Query<&ChunkPos>,
-    mut gizmos: Gizmos,
+    mode: Res<ChunkVisualizerMode>,
+    config: Res<ChunkVisualizerConfig>,
+    up_axis: Option<Res<ChunkUpAxis>>,
+    chunks: Query<(
+        Entity,
+        &ChunkPos,
+        Option<&ChunkLoadState>,
+        Option<&ChunkLoadedAt>,
+        Has<ChunkPersistent>,
+    )>,
+    #[cfg(feature = "chunk_unloader")] pinned: Query<(), With<ChunkPinned>>,
+    time: Res<Time>,
+    managed: Query<
+        Option<&ChunkLayer>,
+        (With<Chunk>, Without<UnmanagedChunk>, Without<RejectedChunk>),
+    >,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    targets: Query<(&ChunkHighlightTarget, &GlobalTransform, Option<&Camera>)>,
+    #[cfg(feature = "chunk_picking")] selected: Option<Res<SelectedChunk>>,
+    heat_sources: Query<&GlobalTransform, With<ChunkHeatSource>>,
+    #[cfg(feature = "chunk_loader")] loaders: Query<
+        (&GlobalTransform, &ChunkLoader),
+        Without<ChunkLoaderDisabled>,
+    >,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut gizmos: Gizmos<ChunkGizmos>,
 ) {
     let chunk_size = chunk_manager.get_size();
+    let up_axis = up_axis.map_or(ChunkUpAxis::Y, |up_axis| *up_axis);
+    // The bit of the up axis in the corner indices below
+    let up_bit = 1
+        << up_axis
+            .up()
+            .abs()
+            .to_array()
+            .iter()
+            .position(|&c| c != 0)
+            .unwrap();
+    // Only the rectangle of the corners without this bit is drawn
+    let rect_bit = flat_bit(chunk_size, up_bit, config.flat_threshold)
+        .or(config.bottom_only.then_some(up_bit));
+    let cursor = window.single().ok().and_then(Window::cursor_position);
+    // The chunk picked with the cursor
+    #[cfg(feature = "chunk_picking")]
+    let selected = selected.and_then(|selected| selected.0);
+    #[cfg(not(feature = "chunk_picking"))]
+    let selected = None;
+    let highlighted: HashSet<IVec3> = targets
+        .iter()
+        .filter_map(|(target, transform, camera)| {
+            let pos = match *target {
+                ChunkHighlightTarget::Position => transform.translation(),
+                ChunkHighlightTarget::Cursor { height } => {
+                    let up = up_axis.up().as_vec3();
+                    let ray = camera?.viewport_to_world(transform, cursor?).ok()?;
+                    let distance = ray.intersect_plane(up * height, InfinitePlane3d::new(up))?;
+                    ray.get_point(distance)
+                }
+            };
+            Some(chunk_manager.get_chunk_pos(&pos))
+        })
+        .chain(selected)
+        .collect();
+    let faint = config
+        .highlight_color
+        .with_alpha(config.highlight_color.alpha() * 0.3);
+
+    let frontier: Option<HashSet<IVec3>> =
+        (config.draw_mode == DrawMode::FrontierOnly).then(|| {
+            chunk_manager
+                .boundary_chunks()
+                .map(|(pos, _)| pos)
+                .collect()
+        });
+    let mut heat: HashMap<IVec3, usize> = HashMap::new();
+    if config.draw_mode == DrawMode::Heatmap {
+        for transform in heat_sources.iter() {
+            *heat
+                .entry(chunk_manager.get_chunk_pos(&transform.translation()))
+                .or_default() += 1;
+        }
+    }
+    let chunk_heat = |chunk_pos: &IVec3| {
+        heat_color(
+            heat.get(chunk_pos).copied().unwrap_or(0),
+            config.heatmap_min,
+            config.heatmap_max,
+        )
+    };
+
+    #[cfg(feature = "chunk_loader")]
+    let ghosts = match config.draw_mode {
+        DrawMode::IncludeGhosts => ghost_chunks(
+            &chunk_manager,
+            loaders
+                .iter()
+                .map(|(transform, ChunkLoader(radius))| (transform.translation(), *radius)),
+        ),
+        _ => HashSet::new(),
+    };
+    #[cfg(not(feature = "chunk_loader"))]
+    let ghosts = HashSet::new();
+
+    let camera = active_camera(&cameras).map(|(_, transform)| transform.translation());
+    let drawn = cull_chunks(
+        &chunk_manager,
+        &config,
+        up_axis,
+        camera,
+        chunks
+            .iter()
+            .filter(|(entity, ChunkPos(chunk_pos), ..)| {
+                let heated = chunk_heat(chunk_pos).is_some();
+                shown_in_mode(config.draw_mode, frontier.as_ref(), heated, chunk_pos)
+                    || managed.get(*entity).is_ok_and(|layer| {
+                        is_orphan_chunk(&chunk_manager, *entity, *chunk_pos, layer)
+                    })
+            })
+            .map(|chunk| (chunk.1.0, Some(chunk)))
+            .chain(ghosts.into_iter().map(|ghost| (ghost, None))),
+    );
+
+    for (_, origin, chunk) in drawn {
+        // Corner `i` is offset along each axis whose bit is set in `i`, and
+        // each edge joins two corners differing in one bit
+        let corner = |i: usize| {
+            origin
+                + chunk_size * Vec3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32)
+        };
+        let Some((entity, ChunkPos(chunk_pos), load_state, loaded_at, persistent)) = chunk else {
+            for (i, j) in chunk_edges(&chunk_manager, IVec3::ZERO, rect_bit, false) {
+                dashed_line(&mut gizmos, corner(i), corner(j), config.ghost_color);
+            }
+            continue;
+        };
+        #[cfg(feature = "chunk_unloader")]
+        let pinned = pinned.contains(entity);
+        #[cfg(not(feature = "chunk_unloader"))]
+        let pinned = false;
+        let persistent = pinned || persistent;
+        let orphan = managed
+            .get(entity)
+            .is_ok_and(|layer| is_orphan_chunk(&chunk_manager, entity, *chunk_pos, layer));
+        let color = if orphan {
+            config.orphan_color
+        } else if highlighted.contains(chunk_pos) {
+            config.highlight_color
+        } else if config.highlight_neighbors
+            && NEIGHBORS_6
+                .iter()
+                .any(|offset| highlighted.contains(&(chunk_pos + offset)))
+        {
+            faint
+        } else {
+            // Chunks without a ChunkLoadedAt yet count as fresh
+            let age = loaded_at.map_or(0.0, |loaded_at| time.elapsed_secs() - loaded_at.0);
+            let color = chunk_heat(chunk_pos).unwrap_or_else(|| {
+                color_for_chunk(&mode, &config, up_axis, *chunk_pos, load_state, persistent)
+            });
+            fade_from_fresh(config.fresh_color, color, age, config.fresh_fade_secs)
+        };
+
+        let outer_shell = config.draw_mode == DrawMode::OuterShell && !orphan;
+        for (i, j) in chunk_edges(&chunk_manager, *chunk_pos, rect_bit, outer_shell) {
+            gizmos.line(corner(i), corner(j), color);
+        }
+        if orphan {
+            // Crosses out each drawn face with its two diagonals, joining the
+            // corners that differ in both bits other than the face's own
+            let faces = match rect_bit {
+                Some(rect_bit) => vec![(rect_bit, 0)],
+                None => [1, 2, 4]
+                    .into_iter()
+                    .flat_map(|bit| [(bit, 0), (bit, bit)])
+                    .collect(),
+            };
+            for (face_bit, side) in faces {
+                let others = 7 & !face_bit;
+                let low = others & others.wrapping_neg();
+                gizmos.line(corner(side), corner(side | others), color);
+                gizmos.line(corner(side | low), corner(side | (others ^ low)), color);
+            }
+        }
+    }
+}
+
+/// The edges of the chunk at `chunk_pos` the boundary visualizer draws, as
+/// the indices of the two corners they join, see `chunk_boundry_visualizer`.
+///
+/// Only the rectangle of the corners without the `rect_bit` is kept. With
+/// `outer_shell`, edges are left out when the neighbors across both faces they
+/// border are loaded.
+fn chunk_edges(
+    chunk_manager: &ChunkManager,
+    chunk_pos: IVec3,
+    rect_bit: Option<usize>,
+    outer_shell: bool,
+) -> impl Iterator<Item = (usize, usize)> + '_ {
+    // Whether the face of the chunk towards the corners with `bit` set, or
+    // unset when `!side`, borders no loaded chunk
+    let exposed = move |bit: usize, side: bool| {
+        let offset = IVec3::new(
+            (bit & 1) as i32,
+            (bit >> 1 & 1) as i32,
+            (bit >> 2 & 1) as i32,
+        );
+        !chunk_manager.is_loaded(&(chunk_pos + if side { offset } else { -offset }))
+    };
+    (0..8)
+        .flat_map(|i| [1, 2, 4].map(|axis_bit| (i, axis_bit)))
+        .filter(move |&(i, axis_bit)| {
+            let j = i | axis_bit;
+            i != j
+                && rect_bit.is_none_or(|rect_bit| j & rect_bit == 0)
+                && (!outer_shell
+                    || [1, 2, 4]
+                        .into_iter()
+                        .filter(|&bit| bit != axis_bit)
+                        .any(|bit| exposed(bit, i & bit != 0)))
+        })
+        .map(|(i, axis_bit)| (i, i | axis_bit))
+}
+
+/// The unloaded chunk positions within the radius of the given loaders, as
+/// `(translation, radius)`, the ones they load next
+#[cfg(feature = "chunk_loader")]
+fn ghost_chunks(
+    chunk_manager: &ChunkManager,
+    loaders: impl Iterator<Item = (Vec3, IVec3)>,
+) -> HashSet<IVec3> {
+    loaders
+        .flat_map(|(translation, radius)| {
+            chunks_to_load(
+                chunk_manager.get_chunk_pos(&translation),
+                radius,
+                chunk_manager,
+            )
+        })
+        .collect()
+}
+
+/// Draws a line as 5 dashes with gaps between them
+fn dashed_line(gizmos: &mut Gizmos<ChunkGizmos>, start: Vec3, end: Vec3, color: Color) {
+    const DASHES: usize = 5;
+    let step = (end - start) / (2 * DASHES - 1) as f32;
+    for dash in 0..DASHES {
+        let from = start + step * (2 * dash) as f32;
+        gizmos.line(from, from + step, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chunks in a column from height -3 to 3 along `up_axis`
+    fn column(up_axis: ChunkUpAxis) -> impl Iterator<Item = (IVec3, i32)> {
+        (-3..=3).map(move |height| (up_axis.up() * height, height))
+    }
+
+    #[cfg(feature = "chunk_loader")]
+    #[test]
+    fn ghosts_are_the_chunks_loaders_would_load() {
+        let mut chunk_manager = ChunkManager::new(Vec3::splat(10.0));
+        for (index, chunk_pos) in [IVec3::ZERO, IVec3::X, IVec3::new(3, 0, 0)]
+            .into_iter()
+            .enumerate()
+        {
+            chunk_manager.insert(chunk_pos, Entity::from_raw_u32(index as u32 + 1).unwrap());
+        }
+        let loaders = [
+            (Vec3::splat(5.0), IVec3::new(1, 0, 1)),
+            (Vec3::new(35.0, 5.0, 5.0), IVec3::new(1, 0, 0)),
+        ];
+
+        let mut missing = HashSet::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                missing.insert(IVec3::new(x, 0, z));
+            }
+        }
+        missing.extend([IVec3::new(2, 0, 0), IVec3::new(4, 0, 0)]);
+        missing.retain(|chunk_pos| !chunk_manager.is_loaded(chunk_pos));
+
+        assert_eq!(ghost_chunks(&chunk_manager, loaders.into_iter()), missing);
+    }
+
+    #[test]
+    fn chunks_not_registered_as_themselves_are_orphans() {
+        let mut chunk_manager = ChunkManager::new(Vec3::ONE);
+        let [registered, duplicate, layered] =
+            [1, 2, 3].map(|index| Entity::from_raw_u32(index).unwrap());
+        chunk_manager.insert(IVec3::ZERO, registered);
+        chunk_manager.insert_in_layer(IVec3::ZERO, 1, layered);
+
+        assert!(!is_orphan_chunk(
+            &chunk_manager,
+            registered,
+            IVec3::ZERO,
+            None
+        ));
+        assert!(is_orphan_chunk(
+            &chunk_manager,
+            duplicate,
+            IVec3::ZERO,
+            None
+        ));
+        assert!(is_orphan_chunk(&chunk_manager, registered, IVec3::X, None));
+        assert!(!is_orphan_chunk(
+            &chunk_manager,
+            layered,
+            IVec3::ZERO,
+            Some(&ChunkLayer(1))
+        ));
+        assert!(is_orphan_chunk(&chunk_manager, layered, IVec3::ZERO, None));
+    }
+
+    #[test]
+    fn the_y_slice_keeps_chunks_within_it() {
+        let chunk_manager = ChunkManager::new(Vec3::ONE);
+        let config = ChunkVisualizerConfig {
+            y_slice: Some(-1..=2),
+            ..default()
+        };
+        let drawn = cull_chunks(
+            &chunk_manager,
+            &config,
+            ChunkUpAxis::Y,
+            None,
+            column(ChunkUpAxis::Y),
+        );
+        let mut heights: Vec<_> = drawn.into_iter().map(|(.., height)| height).collect();
+        heights.sort();
+        assert_eq!(heights, [-1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn the_y_slice_follows_the_up_axis() {
+        let chunk_manager = ChunkManager::new(Vec3::ONE);
+        let config = ChunkVisualizerConfig {
+            y_slice: Some(0..=1),
+            ..default()
+        };
+        // With Z up, the slice selects Z chunk coordinates
+        let drawn = cull_chunks(
+            &chunk_manager,
+            &config,
+            ChunkUpAxis::Z,
+            None,
+            column(ChunkUpAxis::Z).chain([(IVec3::new(0, 3, 1), 10)]),
+        );
+        let mut heights: Vec<_> = drawn.into_iter().map(|(.., height)| height).collect();
+        heights.sort();
+        assert_eq!(heights, [0, 1, 10]);
+    }
+
+    #[test]
+    fn zero_height_chunks_are_drawn_flat() {
+        let threshold = ChunkVisualizerConfig::default().flat_threshold;
+        // Corner bits are 1 for x, 2 for y and 4 for z
+        assert_eq!(flat_bit(vec3(10.0, 0.0, 10.0), 2, threshold), Some(2));
+        assert_eq!(flat_bit(vec3(10.0, 10.0, 0.0), 4, threshold), Some(4));
+        assert_eq!(flat_bit(vec3(10.0, 0.0005, 10.0), 2, threshold), Some(2));
+        assert_eq!(flat_bit(Vec3::splat(10.0), 2, threshold), None);
+        // A flat axis other than up is still drawn flat
+        assert_eq!(flat_bit(vec3(10.0, 0.0, 10.0), 4, threshold), Some(2));
+    }
+
+    #[test]
+    fn chunks_are_colored_by_state() {
+        let config = ChunkVisualizerConfig::default();
+        let color = |load_state: Option<ChunkLoadState>, persistent| {
+            color_for_chunk(
+                &ChunkVisualizerMode::ByLoadState,
+                &config,
+                ChunkUpAxis::Y,
+                IVec3::ZERO,
+                load_state.as_ref(),
+                persistent,
+            )
+        };
+        assert_eq!(color(None, false), config.base_color);
+        assert_eq!(
+            color(Some(ChunkLoadState::Requested), false),
+            config.pending_color
+        );
+        assert_eq!(
+            color(Some(ChunkLoadState::Generating), false),
+            config.generating_color
+        );
+        assert_eq!(
+            color(Some(ChunkLoadState::Ready), false),
+            config.ready_color
+        );
+        assert_eq!(
+            color(Some(ChunkLoadState::Failed), false),
+            config.failed_color
+        );
+        assert_eq!(
+            color(Some(ChunkLoadState::Ready), true),
+            config.persistent_color
+        );
+    }
+
+    #[test]
+    fn every_chunk_has_one_wireframe_until_it_unloads() {
+        let mut app = crate::tests::app((
+            // The assets of the PbrPlugin, which headless apps lack
+            |app: &mut App| {
+                app.init_asset::<Mesh>().init_asset::<StandardMaterial>();
+            },
+            crate::ChunkyPlugin::default().with_visualizer_state(false),
+        ));
+        app.insert_resource(ChunkVisualizerEnabled(true));
+        app.world_mut()
+            .resource_mut::<ChunkVisualizerConfig>()
+            .backend = VisualizerBackend::Mesh;
+        let mut chunks: Vec<_> = (0..3)
+            .map(|x| app.world_mut().spawn((Chunk, ChunkPos(IVec3::X * x))).id())
+            .collect();
+        chunks.sort();
+        let framed = |app: &mut App| {
+            let mut framed: Vec<_> = app
+                .world_mut()
+                .query_filtered::<&ChildOf, With<ChunkWireframe>>()
+                .iter(app.world())
+                .map(ChildOf::parent)
+                .collect();
+            framed.sort();
+            framed
+        };
+        app.update();
+        app.update();
+        assert_eq!(framed(&mut app), chunks);
+
+        app.world_mut().despawn(chunks[0]);
+        app.update();
+        assert_eq!(framed(&mut app), chunks[1..]);
+        let wireframes = app
+            .world_mut()
+            .query::<&ChunkWireframe>()
+            .iter(app.world())
+            .count();
+        assert_eq!(wireframes, 2);
+
+        // Switching to gizmos cleans up the wireframes
+        app.world_mut()
+            .resource_mut::<ChunkVisualizerConfig>()
+            .backend = VisualizerBackend::Gizmos;
+        app.update();
+        assert_eq!(framed(&mut app), []);
+    }
+
+    /// Holds `keys` down for one frame, then releases them
+    fn press(app: &mut App, keys: &[KeyCode]) {
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        for key in keys {
+            input.press(*key);
+        }
+        app.update();
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.release_all();
+        input.clear();
+    }
+
+    #[test]
+    fn the_toggle_key_flips_the_state_or_the_resource() {
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.world_mut()
+            .resource_mut::<ChunkVisualizerConfig>()
+            .toggle_modifier = Some(KeyCode::AltLeft);
+        let state = |app: &mut App| {
+            // The next state is applied in the following frame
+            app.update();
+            *app.world()
+                .resource::<State<ChunkBoundryVisualizer>>()
+                .get()
+        };
+        press(&mut app, &[KeyCode::F3]);
+        assert_eq!(state(&mut app), ChunkBoundryVisualizer::Off);
+        press(&mut app, &[KeyCode::AltLeft, KeyCode::F3]);
+        assert_eq!(state(&mut app), ChunkBoundryVisualizer::On);
+        press(&mut app, &[KeyCode::AltLeft, KeyCode::F3]);
+        assert_eq!(state(&mut app), ChunkBoundryVisualizer::Off);
+
+        let mut app =
+            crate::tests::app(crate::ChunkyPlugin::default().with_visualizer_state(false));
+        app.init_resource::<ButtonInput<KeyCode>>();
+        let enabled = |app: &App| app.world().resource::<ChunkVisualizerEnabled>().0;
+        press(&mut app, &[KeyCode::F3]);
+        assert!(enabled(&app));
+        press(&mut app, &[KeyCode::F2]);
+        assert!(enabled(&app));
+        press(&mut app, &[KeyCode::F3]);
+        assert!(!enabled(&app));
+    }
+
+    #[test]
+    fn the_enabled_resource_turns_the_gizmos_on_and_off() {
+        use bevy::gizmos::GizmoAsset;
+        use std::any::TypeId;
+
+        let mut app =
+            crate::tests::app(crate::ChunkyPlugin::default().with_visualizer_state(false));
+        app.world_mut().spawn((Chunk, ChunkPos(IVec3::ZERO)));
+        // Frames with chunk gizmos keep a gizmo asset around, others drop it
+        let drawn = |app: &mut App| {
+            app.update();
+            app.update();
+            app.world()
+                .resource::<Assets<GizmoAsset>>()
+                .iter()
+                .filter(|(_, gizmo)| gizmo.config_typeid() == TypeId::of::<ChunkGizmos>())
+                .count()
+        };
+        assert_eq!(drawn(&mut app), 0);
+        app.insert_resource(ChunkVisualizerEnabled(true));
+        assert_eq!(drawn(&mut app), 1);
+        app.insert_resource(ChunkVisualizerEnabled(false));
+        assert_eq!(drawn(&mut app), 0);
+    }
+
+    #[test]
+    fn heat_goes_from_blue_to_red() {
+        let blue = Color::from(tailwind::BLUE_500);
+        let red = Color::from(tailwind::RED_500);
+        assert_eq!(heat_color(0, 1, 9), None);
+        assert_eq!(heat_color(1, 1, 9), Some(blue));
+        assert_eq!(heat_color(5, 1, 9), Some(blue.mix(&red, 0.5)));
+        assert_eq!(heat_color(9, 1, 9), Some(red));
+        assert_eq!(heat_color(50, 1, 9), Some(red));
+        // Counts below the minimum stay blue, and an empty range doesn't divide by zero
+        assert_eq!(heat_color(1, 4, 9), Some(blue));
+        assert_eq!(heat_color(3, 3, 3), Some(blue));
+        assert_eq!(heat_color(4, 3, 3), Some(red));
+    }
+
+    #[test]
+    fn fresh_chunks_fade_to_their_color() {
+        let (fresh, color) = (Color::srgb(1.0, 1.0, 1.0), Color::srgb(0.0, 1.0, 0.0));
+        assert_eq!(fade_from_fresh(fresh, color, 0.0, 2.0), fresh);
+        assert_eq!(
+            fade_from_fresh(fresh, color, 0.5, 2.0),
+            fresh.mix(&color, 0.25)
+        );
+        assert_eq!(
+            fade_from_fresh(fresh, color, 1.0, 2.0),
+            fresh.mix(&color, 0.5)
+        );
+        assert_eq!(fade_from_fresh(fresh, color, 2.0, 2.0), color);
+        assert_eq!(fade_from_fresh(fresh, color, 60.0, 2.0), color);
+        // Stamped after the frame started, and fading turned off
+        assert_eq!(fade_from_fresh(fresh, color, -0.1, 2.0), fresh);
+        assert_eq!(fade_from_fresh(fresh, color, 0.0, 0.0), color);
+    }
+
+    /// A manager with a solid 3x3x3 block of chunks around the origin
+    fn solid_block() -> ChunkManager {
+        let mut chunk_manager = ChunkManager::new(Vec3::ONE);
+        let mut index = 0;
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    index += 1;
+                    chunk_manager.insert(IVec3::new(x, y, z), Entity::from_raw_u32(index).unwrap());
+                }
+            }
+        }
+        chunk_manager
+    }
+
+    #[test]
+    fn the_frontier_leaves_out_the_center_of_a_solid_block() {
+        let chunk_manager = solid_block();
+        let frontier: HashSet<IVec3> = chunk_manager
+            .boundary_chunks()
+            .map(|(pos, _)| pos)
+            .collect();
+        let shown = |draw_mode| {
+            chunk_manager
+                .iter()
+                .filter(|(pos, _)| shown_in_mode(draw_mode, Some(&frontier), false, pos))
+                .count()
+        };
+        assert_eq!(shown(DrawMode::Full), 27);
+        assert_eq!(shown(DrawMode::FrontierOnly), 26);
+        assert!(!shown_in_mode(
+            DrawMode::FrontierOnly,
+            Some(&frontier),
+            false,
+            &IVec3::ZERO
+        ));
+    }
+
+    /// Chunks along the x axis from 0 to `count - 1`, with their x
+    fn row(count: i32) -> impl Iterator<Item = (IVec3, i32)> {
+        (0..count).map(|x| (IVec3::new(x, 0, 0), x))
+    }
+
+    #[test]
+    fn the_draw_cap_keeps_the_nearest_chunks() {
+        let chunk_manager = ChunkManager::new(Vec3::ONE);
+        let drawn = |max_drawn_chunks, camera| {
+            let config = ChunkVisualizerConfig {
+                max_drawn_chunks,
+                ..default()
+            };
+            let mut drawn: Vec<_> =
+                cull_chunks(&chunk_manager, &config, ChunkUpAxis::Y, camera, row(10))
+                    .into_iter()
+                    .map(|(.., x)| x)
+                    .collect();
+            drawn.sort();
+            drawn
+        };
+        assert_eq!(drawn(None, Some(Vec3::ZERO)).len(), 10);
+        assert_eq!(drawn(Some(3), Some(Vec3::ZERO)), [0, 1, 2]);
+        assert_eq!(drawn(Some(3), Some(vec3(9.5, 0.0, 0.0))), [7, 8, 9]);
+        assert!(drawn(Some(0), Some(Vec3::ZERO)).is_empty());
+        // Without a camera any chunks are kept, still capped
+        assert_eq!(drawn(Some(4), None).len(), 4);
+    }
+
+    #[test]
+    fn chunks_beyond_the_draw_distance_are_culled() {
+        let chunk_manager = ChunkManager::new(Vec3::ONE);
+        let config = ChunkVisualizerConfig {
+            max_draw_distance: Some(3.0),
+            ..default()
+        };
+        // The chunk at x is x - 0.5 away from a camera in the middle of chunk 0
+        let camera = Vec3::splat(0.5);
+        let mut drawn: Vec<_> = cull_chunks(
+            &chunk_manager,
+            &config,
+            ChunkUpAxis::Y,
+            Some(camera),
+            row(10),
+        )
+        .into_iter()
+        .map(|(distance, _, x)| (x, distance))
+        .collect();
+        drawn.sort_by_key(|&(x, _)| x);
+        assert_eq!(drawn, [(0, 0.0), (1, 0.5), (2, 1.5), (3, 2.5)]);
+
+        let drawn = cull_chunks(&chunk_manager, &config, ChunkUpAxis::Y, None, row(10));
+        assert_eq!(drawn.len(), 10);
+    }
+
+    #[test]
+    fn the_outer_shell_only_draws_edges_on_the_surface() {
+        let chunk_manager = solid_block();
+        let edges = |outer_shell, rect_bit| {
+            chunk_manager
+                .iter()
+                .map(|(pos, _)| chunk_edges(&chunk_manager, pos, rect_bit, outer_shell).count())
+                .sum::<usize>()
+        };
+        assert_eq!(edges(false, None), 27 * 12);
+        // 8 corner chunks with 9 outer edges, 12 edge chunks with 7 and 6 face
+        // chunks with 4, the center has none
+        assert_eq!(edges(true, None), 8 * 9 + 12 * 7 + 6 * 4);
+        assert_eq!(
+            chunk_edges(&chunk_manager, IVec3::ZERO, None, true).count(),
+            0
+        );
+        assert_eq!(edges(false, Some(2)), 27 * 4);
+
+        // Isolated chunks are drawn fully
+        let isolated = IVec3::new(5, 0, 0);
+        assert_eq!(
+            chunk_edges(&chunk_manager, isolated, None, true).count(),
+            12
+        );
+    }
+
+    #[test]
+    fn labels_are_despawned_with_their_chunks() {
+        let mut app =
+            crate::tests::app(crate::ChunkyPlugin::default().with_visualizer_state(false));
+        app.insert_resource(ChunkVisualizerEnabled(true));
+        app.world_mut()
+            .resource_mut::<ChunkVisualizerConfig>()
+            .show_labels = true;
+        let mut chunks: Vec<_> = (0..3)
+            .map(|x| app.world_mut().spawn((Chunk, ChunkPos(IVec3::X * x))).id())
+            .collect();
+        chunks.sort();
+        let labeled = |app: &mut App| {
+            let mut labeled: Vec<_> = app
+                .world_mut()
+                .query::<&ChunkLabel>()
+                .iter(app.world())
+                .map(|label| label.0)
+                .collect();
+            labeled.sort();
+            labeled
+        };
+        app.update();
+        assert_eq!(labeled(&mut app), chunks);
+
+        app.world_mut().despawn(chunks[0]);
+        app.update();
+        assert_eq!(labeled(&mut app), chunks[1..]);
+
+        app.world_mut()
+            .resource_mut::<ChunkVisualizerConfig>()
+            .show_labels = false;
+        app.update();
+        assert_eq!(labeled(&mut app), []);
+    }
+
+    #[test]
+    fn chunk_gizmos_have_their_own_config_group() {
+        use std::any::TypeId;
+
+        let mut app = crate::tests::app(crate::ChunkyPlugin::default());
+        let config = |app: &App| {
+            app.world()
+                .resource::<GizmoConfigStore>()
+                .get_config_dyn(&TypeId::of::<ChunkGizmos>())
+                .map(|(config, _)| config.depth_bias)
+        };
+        app.update();
+        assert_eq!(config(&app), Some(0.0));
 
-    for ChunkPos(chunk_pos) in chunks.iter() {
-        let origin = chunk_pos.as_vec3() * chunk_size;
-
-        // 8 corners of the box
-        let p000 = origin;
-        let p100 = origin + Vec3::new(chunk_size.x, 0.0, 0.0);
-        let p010 = origin + Vec3::new(0.0, chunk_size.y, 0.0);
-        let p110 = origin + Vec3::new(chunk_size.x, chunk_size.y, 0.0);
-
-        let p001 = origin + Vec3::new(0.0, 0.0, chunk_size.z);
-        let p101 = origin + Vec3::new(chunk_size.x, 0.0, chunk_size.z);
-        let p011 = origin + Vec3::new(0.0, chunk_size.y, chunk_size.z);
-        let p111 = origin + Vec3::new(chunk_size.x, chunk_size.y, chunk_size.z);
-
-        let color = bevy::color::palettes::tailwind::GREEN_500;
-
-        // bottom rectangle
-        gizmos.line(p000, p100, color);
-        gizmos.line(p100, p110, color);
-        gizmos.line(p110, p010, color);
-        gizmos.line(p010, p000, color);
-
-        // top rectangle
-        gizmos.line(p001, p101, color);
-        gizmos.line(p101, p111, color);
-        gizmos.line(p111, p011, color);
-        gizmos.line(p011, p001, color);
-
-        // vertical edges
-        gizmos.line(p000, p001, color);
-        gizmos.line(p100, p101, color);
-        gizmos.line(p110, p111, color);
-        gizmos.line(p010, p011, color);
+        app.insert_resource(VisualizerDepth::Overlay);
+        app.update();
+        assert_eq!(config(&app), Some(-1.0));
+        // Other gizmos are left alone
+        let (default_config, _) = app
+            .world()
+            .resource::<GizmoConfigStore>()
+            .config::<DefaultGizmoConfigGroup>();
+        assert_eq!(default_config.depth_bias, 0.0);
     }
 }
@@ -27,27 +27,149 @@
 //!
 //! - `chunk_visualizer` (default) - Enables debug visualization of chunk boundaries
 //! - `chunk_loader` (default) - Enables automatic chunk loading around ChunkLoader entities
+//! - `chunk_generation` - Enables asynchronous chunk generation via [`ChunkGenerator`](prelude::ChunkGenerator)
 //! - `chunk_info` - Logs chunk spawn/despawn events
+//! - `noise_example` - A noise terrain generator demonstrating chunk generation
+//! - `chunk_persistence` - Saves chunk data to a [`ChunkStore`](prelude::ChunkStore)
+//!   and loads it back instead of generating
+//! - `serde` - [`SerdeCodec`](prelude::SerdeCodec) for encoding persisted chunks with serde
+//! - `ron` - The [`Ron`](prelude::Ron) format for `SerdeCodec`
+//! - `bincode` - The [`Bincode`](prelude::Bincode) format for `SerdeCodec`
+//! - `lz4`, `zstd` - Compression of persisted chunks, see
+//!   [`ChunkCompression`](prelude::ChunkCompression)
+//! - `immediate_generation` - Generates on the main thread even in
+//!   [`GenerationMode::Async`](prelude::GenerationMode::Async), as done on `wasm32`
+//! - `physics` - Spawns a collider covering each chunk through a
+//!   [`ChunkColliderBuilder`](prelude::ChunkColliderBuilder) for your physics engine
+//! - `scene` - [`export_chunks`](scene::export_chunks) saves the loaded chunks as a
+//!   `DynamicScene`
+//! - `chunk_minimap` - [`ChunkMinimapPlugin`](prelude::ChunkMinimapPlugin) shows a
+//!   top-down minimap of the loaded chunks
+//! - `chunk_debug_ui` - [`ChunkDebugUiPlugin`](prelude::ChunkDebugUiPlugin) shows
+//!   chunk streaming stats on screen while the visualizer is on
+//! - `chunk_picking` - [`ChunkPickingPlugin`](prelude::ChunkPickingPlugin) selects the
+//!   chunk under the cursor on click
 
 #[cfg(feature = "chunk_loader")]
 mod chunk_loader;
 
+#[cfg(feature = "chunk_generation")]
+mod chunk_generation;
+
+#[cfg(feature = "chunk_generation")]
+mod chunk_stages;
+
+#[cfg(feature = "chunk_persistence")]
+mod chunk_persistence;
+
+#[cfg(feature = "chunk_persistence")]
+mod chunk_payload_cache;
+
+#[cfg(feature = "chunk_persistence")]
+mod chunk_region_file;
+
+#[cfg(feature = "physics")]
+mod chunk_physics;
+
 #[cfg(feature = "chunk_visualizer")]
 mod chunk_visualizer;
 
+#[cfg(feature = "chunk_minimap")]
+mod chunk_minimap;
+
+#[cfg(feature = "chunk_debug_ui")]
+mod chunk_debug_ui;
+
+#[cfg(feature = "chunk_picking")]
+mod chunk_picking;
+
 #[cfg(feature = "chunk_unloader")]
 mod chunk_unloader;
 
+#[cfg(feature = "noise_example")]
+mod noise_example;
+
+#[cfg(feature = "scene")]
+pub mod scene;
+
+mod chunk_access;
+mod chunk_prewarm;
+mod chunk_progress;
+mod chunk_snapshot;
+mod chunk_state;
+
+#[cfg(any(feature = "chunk_loader", feature = "chunk_unloader"))]
+use bevy::ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
 use bevy::{
     ecs::{lifecycle::HookContext, world::DeferredWorld},
+    math::I64Vec3,
     prelude::*,
 };
 use std::collections::HashMap;
 
+pub use chunk_access::ChunkAccess;
+pub use chunk_prewarm::{ChunkPrewarm, ChunkPrewarmRegion};
+#[cfg(feature = "chunk_loader")]
+pub use chunk_progress::initial_area_ready;
+pub use chunk_progress::{ChunkStats, ChunkStreamingProgress};
+#[cfg(feature = "chunk_loader")]
+pub use chunk_snapshot::LoaderSnapshot;
+pub use chunk_snapshot::{ChunkSnapshot, ChunkWorldSnapshot};
+pub use chunk_state::{
+    ChunkLoadState, ChunkLoadStateCommands, ChunkReady, ChunkReadyTiming, FailedChunks,
+    GeneratingChunks, InvalidLoadStateTransition, NewlyReadyChunks, ReadyChunks, RequestedChunks,
+    load_state,
+};
+
 /// Re-exports of commonly used types
 pub mod prelude {
+    #[cfg(feature = "chunk_debug_ui")]
+    pub use crate::chunk_debug_ui::{ChunkDebugUiPanel, ChunkDebugUiPlugin};
+    #[cfg(feature = "chunk_generation")]
+    pub use crate::chunk_generation::{
+        ChunkAborted, ChunkData, ChunkGenerationFailed, ChunkGenerationPlugin,
+        ChunkGenerationRetry, ChunkGenerator, ChunkRng, GeneratingChunk, GenerationBudget,
+        GenerationContext, GenerationError, GenerationMode, GenerationResult,
+        GenerationRetryPolicy, RegenerateChunk, WorldSeed, chunk_seed,
+    };
     #[cfg(feature = "chunk_loader")]
-    pub use crate::chunk_loader::ChunkLoader;
+    pub use crate::chunk_loader::{
+        ChunkLoadTrigger, ChunkLoader, ChunkLoaderDisabled, ChunkLoaderInterval,
+        ChunkLoaderSettings, ChunkLoaderStats, ChunkLoaderSweep, ChunkRegionSettled, LoaderRegion,
+        LoaderReport, PooledChunk,
+    };
+    #[cfg(feature = "chunk_minimap")]
+    pub use crate::chunk_minimap::{
+        ChunkMinimapConfig, ChunkMinimapFocus, ChunkMinimapImage, ChunkMinimapPanel,
+        ChunkMinimapPlugin, MinimapPalette,
+    };
+    #[cfg(feature = "chunk_persistence")]
+    pub use crate::chunk_payload_cache::{CacheCapacity, CacheEviction, ChunkPayloadCache};
+    #[cfg(feature = "bincode")]
+    pub use crate::chunk_persistence::Bincode;
+    #[cfg(feature = "ron")]
+    pub use crate::chunk_persistence::Ron;
+    #[cfg(feature = "chunk_persistence")]
+    pub use crate::chunk_persistence::{
+        ChunkCompression, ChunkDirty, ChunkDirtyCommands, ChunkLoadCorrupt, ChunkMigrationFailed,
+        ChunkPersistencePlugin, ChunkPersistenceStats, ChunkStore, ChunkStorePath,
+        CorruptChunkPolicy, DecodeFn, EncodeFn, FilesystemChunkStore, LoadingFromDisk, MigrateFn,
+        PERSISTENCE_COMPRESSION_RATIO, switch_save_slot,
+    };
+    #[cfg(all(feature = "chunk_persistence", feature = "serde"))]
+    pub use crate::chunk_persistence::{SerdeCodec, SerdeFormat};
+    #[cfg(feature = "physics")]
+    pub use crate::chunk_physics::{ChunkCollider, ChunkColliderBuilder, ChunkColliderPlugin};
+    #[cfg(feature = "chunk_picking")]
+    pub use crate::chunk_picking::{
+        ChunkPickingConfig, ChunkPickingPlugin, ChunkSelected, SelectedChunk,
+    };
+    #[cfg(feature = "chunk_persistence")]
+    pub use crate::chunk_region_file::RegionFileStore;
+    #[cfg(feature = "chunk_generation")]
+    pub use crate::chunk_stages::{
+        ChunkStage, ChunkStageCompleted, GenerationStage, StageContext, StageNeighborhood,
+    };
     #[cfg(all(feature = "chunk_unloader", feature = "chunk_loader"))]
     pub use crate::chunk_unloader::ChunkUnloadRadius;
     #[cfg(feature = "chunk_unloader")]
@@ -55,9 +177,27 @@ pub mod prelude {
         ChunkLastAccess, ChunkPinned, ChunkUnloadByDistance, ChunkUnloadEvent, ChunkUnloadLimit,
         ChunkUnloadReason,
     };
+    #[cfg(all(feature = "chunk_visualizer", feature = "chunk_loader"))]
+    pub use crate::chunk_visualizer::ChunkLoaderPreview;
     #[cfg(feature = "chunk_visualizer")]
-    pub use crate::chunk_visualizer::ChunkBoundryVisualizer;
-    pub use crate::{Chunk, ChunkManager, ChunkPos, ChunkyPlugin};
+    pub use crate::chunk_visualizer::{
+        ChunkBoundryVisualizer, ChunkGizmos, ChunkHeatSource, ChunkHighlightTarget, ChunkLabel,
+        ChunkLabels, ChunkLoadedAt, ChunkVisualizerConfig, ChunkVisualizerEnabled,
+        ChunkVisualizerMode, ChunkWireframe, DrawMode, VisualizerBackend, VisualizerDepth,
+    };
+    #[cfg(feature = "noise_example")]
+    pub use crate::noise_example::{HeightField, NoiseHeightGenerator, NoiseTerrainPlugin};
+    pub use crate::{
+        Chunk, ChunkAccess, ChunkAnchor, ChunkCollisionPolicy, ChunkLayer, ChunkLoadState,
+        ChunkLoadStateCommands, ChunkManager, ChunkOccupancy, ChunkPersistent, ChunkPos,
+        ChunkPrewarm, ChunkPrewarmRegion, ChunkRay, ChunkReady, ChunkReadyTiming, ChunkRef,
+        ChunkSnapshot, ChunkStats, ChunkStreamingProgress, ChunkUpAxis, ChunkWorldSnapshot,
+        ChunkyPlugin, ChunkySystems, FailedChunks, GeneratingChunks, NEIGHBORS_6, NEIGHBORS_26,
+        NewlyReadyChunks, ReadyChunks, RegionActivated, RegionDeactivated, RegionSize,
+        RequestedChunks, RoundingMode, SightBlocker, UnmanagedChunk,
+    };
+    #[cfg(feature = "chunk_loader")]
+    pub use crate::{LoaderSnapshot, initial_area_ready};
 }
 
 /// The main plugin for chunk management.
@@ -74,40 +214,363 @@ pub mod prelude {
 /// ```
 pub struct ChunkyPlugin {
     chunk_size: Vec3,
+    int_chunk_size: Option<IVec3>,
+    region_size: RegionSize,
+    rounding_mode: RoundingMode,
+    collision_policy: ChunkCollisionPolicy,
+    ready_timing: ChunkReadyTiming,
+    up_axis: ChunkUpAxis,
+    anchor: ChunkAnchor,
+    world_offset: Vec3,
+    /// Chunks the ChunkManager makes room for up front
+    capacity: usize,
+    #[cfg(feature = "chunk_loader")]
+    continuous_loading: bool,
+    /// Inserted over the default settings if set
+    #[cfg(feature = "chunk_loader")]
+    loader_settings: Option<chunk_loader::ChunkLoaderSettings>,
+    #[cfg(feature = "chunk_visualizer")]
+    visualizer_state: bool,
+    /// The schedule of the loader and unloader, `Update` and `PostUpdate` if
+    /// unset
+    #[cfg(any(feature = "chunk_loader", feature = "chunk_unloader"))]
+    schedule: Option<InternedScheduleLabel>,
 }
 
 impl Plugin for ChunkyPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ChunkManager::new(self.chunk_size));
+        let mut chunk_manager = match self.int_chunk_size {
+            Some(chunk_size) => ChunkManager::new_int(chunk_size),
+            None => ChunkManager::new(self.chunk_size),
+        }
+        .with_region_size(self.region_size)
+        .with_rounding_mode(self.rounding_mode)
+        .with_anchor(self.anchor);
+        chunk_manager.set_world_offset(self.world_offset);
+        chunk_manager.reserve(self.capacity);
+        app.insert_resource(chunk_manager)
+            .insert_resource(self.collision_policy)
+            .init_resource::<ChunkCollisionWarnings>()
+            .insert_resource(self.ready_timing)
+            .insert_resource(self.up_axis)
+            .init_resource::<ChunkStreamingProgress>()
+            .init_resource::<ChunkStats>()
+            .init_resource::<chunk_state::ReplacedLoadStates>()
+            .add_message::<RegionActivated>()
+            .add_message::<RegionDeactivated>()
+            .add_message::<ChunkReady>()
+            .configure_sets(
+                Update,
+                (
+                    ChunkySystems::Load,
+                    ChunkySystems::Generate,
+                    ChunkySystems::PostGenerate,
+                    ChunkySystems::Visualize,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    chunk_state::send_chunk_ready,
+                    chunk_progress::update_chunk_stats,
+                )
+                    .after(ChunkySystems::PostGenerate),
+            )
+            .add_plugins(chunk_prewarm::ChunkPrewarmPlugin)
+            .add_systems(PostUpdate, despawn_rejected_chunks)
+            .add_systems(Last, warn_chunk_collisions);
+        #[cfg(debug_assertions)]
+        app.add_systems(Last, warn_leaked_chunks.run_if(on_message::<AppExit>));
+        #[cfg(any(feature = "chunk_loader", feature = "chunk_unloader"))]
+        if let Some(schedule) = self.schedule {
+            // Pooling a chunk before generation results are applied to it
+            // would leave them on the pooled entity
+            app.configure_sets(
+                schedule,
+                ChunkySystems::Unload
+                    .after(ChunkySystems::Load)
+                    .after(ChunkySystems::PostGenerate),
+            );
+        }
+        #[cfg(feature = "scene")]
+        app.add_plugins(scene::ChunkScenePlugin);
+        #[cfg(feature = "chunk_loader")]
+        if let Some(loader_settings) = self.loader_settings {
+            app.insert_resource(loader_settings);
+        }
         #[cfg(feature = "chunk_loader")]
-        app.add_plugins(chunk_loader::ChunkLoaderPlugin);
+        app.add_plugins(chunk_loader::ChunkLoaderPlugin {
+            continuous: self.continuous_loading,
+            schedule: self.schedule.unwrap_or(Update.intern()),
+        });
         #[cfg(feature = "chunk_visualizer")]
-        app.add_plugins(chunk_visualizer::ChunkBoundryVisualizerPlugin);
+        app.add_plugins(chunk_visualizer::ChunkBoundryVisualizerPlugin {
+            use_state: self.visualizer_state,
+        });
         #[cfg(feature = "chunk_unloader")]
-        app.add_plugins(chunk_unloader::ChunkUnloaderPlugin);
+        app.add_plugins(chunk_unloader::ChunkUnloaderPlugin {
+            schedule: self.schedule.unwrap_or(PostUpdate.intern()),
+        });
         #[cfg(feature = "reflect")]
-        app.register_type::<ChunkPos>()
+        app.register_type::<Chunk>()
+            .register_type::<ChunkPos>()
+            .register_type::<UnmanagedChunk>()
+            .register_type::<ChunkPersistent>()
+            .register_type::<ChunkLayer>()
+            .register_type::<ChunkLoadState>()
+            .register_type::<ChunkCollisionPolicy>()
+            .register_type::<ChunkReadyTiming>()
+            .register_type::<ChunkUpAxis>()
+            .register_type::<ChunkAnchor>()
             .register_type::<ChunkManager>();
     }
 }
 
 impl ChunkyPlugin {
     /// Standard 3D chunk configuration with 10x10x10 sized chunks
-    pub const THREE_DIMETION: Self = Self {
+    pub const THREE_DIMENSION: Self = Self {
         chunk_size: vec3(10.0, 10.0, 10.0),
+        int_chunk_size: None,
+        region_size: RegionSize::DEFAULT,
+        rounding_mode: RoundingMode::Floor,
+        collision_policy: ChunkCollisionPolicy::DespawnNew,
+        ready_timing: ChunkReadyTiming::SameFrame,
+        up_axis: ChunkUpAxis::Y,
+        anchor: ChunkAnchor::Corner,
+        world_offset: Vec3::ZERO,
+        capacity: 0,
+        #[cfg(feature = "chunk_loader")]
+        continuous_loading: true,
+        #[cfg(feature = "chunk_loader")]
+        loader_settings: None,
+        #[cfg(feature = "chunk_visualizer")]
+        visualizer_state: true,
+        #[cfg(any(feature = "chunk_loader", feature = "chunk_unloader"))]
+        schedule: None,
+    };
+
+    /// Misspelled old name of [`THREE_DIMENSION`](Self::THREE_DIMENSION)
+    #[deprecated(note = "renamed to `THREE_DIMENSION`")]
+    pub const THREE_DIMETION: Self = Self::THREE_DIMENSION;
+
+    /// Standard 2D chunk configuration with 10x10 sized chunks on the XY
+    /// plane, with Z up as in Bevy's 2D camera
+    pub const TWO_DIMENSION: Self = Self {
+        chunk_size: vec3(10.0, 10.0, 0.0),
+        up_axis: ChunkUpAxis::Z,
+        ..Self::THREE_DIMENSION
     };
+
+    /// Starts from the [`THREE_DIMENSION`](Self::THREE_DIMENSION) preset, to
+    /// be configured with the `with_` methods
+    pub const fn new() -> Self {
+        Self::THREE_DIMENSION
+    }
+
+    /// Sets the size of chunks in world units. A size of zero along an axis
+    /// makes chunks flat across it, e.g. for 2D worlds.
+    pub fn with_chunk_size(mut self, chunk_size: Vec3) -> Self {
+        self.chunk_size = chunk_size;
+        self.int_chunk_size = None;
+        self
+    }
+
+    /// Uses chunks of an integer size, for grid-aligned worlds where `f32`
+    /// rounding must not pick the wrong chunk, see [`ChunkManager::new_int`]
+    pub fn with_int_chunk_size(mut self, chunk_size: IVec3) -> Self {
+        self.chunk_size = chunk_size.as_vec3();
+        self.int_chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Sets how many chunks are grouped into a single region on each axis
+    pub fn with_region_size(mut self, region_size: RegionSize) -> Self {
+        self.region_size = region_size;
+        self
+    }
+
+    /// Makes room in the [`ChunkManager`] for `capacity` chunks up front, so
+    /// games that know their world size avoid hitches from growing the map
+    /// while loading, see [`ChunkManager::reserve`]
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets how world positions are rounded to chunk positions
+    pub fn with_rounding_mode(mut self, rounding_mode: RoundingMode) -> Self {
+        self.rounding_mode = rounding_mode;
+        self
+    }
+
+    /// Sets what happens when a chunk is spawned where one is already registered
+    pub fn with_duplicate_policy(mut self, collision_policy: ChunkCollisionPolicy) -> Self {
+        self.collision_policy = collision_policy;
+        self
+    }
+
+    /// Sets in which frame [`ChunkReady`] is sent.
+    ///
+    /// [`ChunkReadyTiming::NextFrame`] registers every chunk of a batch before
+    /// any of them is announced.
+    pub fn with_ready_timing(mut self, ready_timing: ChunkReadyTiming) -> Self {
+        self.ready_timing = ready_timing;
+        self
+    }
+
+    /// Sets which axis is vertical for the convenience helpers, see
+    /// [`ChunkUpAxis`]
+    pub fn with_up_axis(mut self, up_axis: ChunkUpAxis) -> Self {
+        self.up_axis = up_axis;
+        self
+    }
+
+    /// Sets where the [`Transform`] of chunk entities sits within their chunk
+    pub fn with_anchor(mut self, anchor: ChunkAnchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Shifts the chunk grid so chunk `(0, 0, 0)` starts at `origin`, see
+    /// [`ChunkManager::set_world_offset`]
+    pub fn with_origin(mut self, origin: Vec3) -> Self {
+        self.world_offset = origin;
+        self
+    }
+
+    /// Inserts the [`ChunkLoaderSettings`](prelude::ChunkLoaderSettings)
+    /// resource, instead of the default settings
+    #[cfg(feature = "chunk_loader")]
+    pub fn with_loader_settings(
+        mut self,
+        loader_settings: chunk_loader::ChunkLoaderSettings,
+    ) -> Self {
+        self.loader_settings = Some(loader_settings);
+        self
+    }
+
+    /// Sets whether loaders load chunks every frame (default).
+    ///
+    /// When turned off, loading only happens when a
+    /// [`ChunkLoadTrigger`](prelude::ChunkLoadTrigger) is sent.
+    #[cfg(feature = "chunk_loader")]
+    pub fn with_continuous_loading(mut self, continuous_loading: bool) -> Self {
+        self.continuous_loading = continuous_loading;
+        self
+    }
+
+    /// Sets whether the visualizer is toggled with the
+    /// [`ChunkBoundryVisualizer`](prelude::ChunkBoundryVisualizer) state
+    /// (default).
+    ///
+    /// When turned off, the state is not added and the visualizer is toggled
+    /// with the plain [`ChunkVisualizerEnabled`](prelude::ChunkVisualizerEnabled)
+    /// resource instead.
+    #[cfg(feature = "chunk_visualizer")]
+    pub fn with_visualizer_state(mut self, visualizer_state: bool) -> Self {
+        self.visualizer_state = visualizer_state;
+        self
+    }
+
+    /// Runs the chunk loader and unloader in `schedule`, e.g. `FixedUpdate` for
+    /// deterministic simulations. By default loaders run in `Update` and the
+    /// unloader in `PostUpdate`.
+    ///
+    /// In another schedule, [`ChunkySystems::Unload`] runs after
+    /// [`ChunkySystems::Load`], and after [`ChunkySystems::PostGenerate`] in
+    /// `Update`. Loaders read their `GlobalTransform`, which
+    /// is only propagated in `PostUpdate`: in `FixedUpdate` it is the one of
+    /// the last frame, so a loader moved in `FixedUpdate` loads around its new
+    /// position one frame later. The `LoaderReport` and `ChunkRegionSettled`
+    /// follow the loaders into `schedule`.
+    #[cfg(any(feature = "chunk_loader", feature = "chunk_unloader"))]
+    pub fn with_schedule(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.schedule = Some(schedule.intern());
+        self
+    }
+}
+
+/// System sets for ordering systems around the chunk pipeline.
+///
+/// In `Update`, [`Load`](Self::Load), [`Generate`](Self::Generate),
+/// [`PostGenerate`](Self::PostGenerate) and [`Visualize`](Self::Visualize) run
+/// in this order. Commands of one set are applied before systems ordered after
+/// it run, so a system `.after(ChunkySystems::Load)` sees the chunks spawned by
+/// loaders in the same frame. [`Unload`](Self::Unload) runs in `PostUpdate`,
+/// after everything in `Update`.
+///
+/// # Example
+///
+/// Systems in [`ChunkySystems::PostGenerate`] see every generated chunk exactly
+/// once, after its generation result is applied and before [`ChunkReady`] is
+/// sent:
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn build_meshes(
+///     mut commands: Commands,
+///     mut meshes: ResMut<Assets<Mesh>>,
+///     chunks: Query<Entity, NewlyReadyChunks>,
+/// ) {
+///     for entity in chunks.iter() {
+///         let mesh = meshes.add(Cuboid::new(10.0, 10.0, 10.0));
+///         commands.entity(entity).insert(Mesh3d(mesh));
+///     }
+/// }
+///
+/// App::new().add_systems(Update, build_meshes.in_set(ChunkySystems::PostGenerate));
+/// ```
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChunkySystems {
+    /// Spawns the chunks around chunk loaders
+    Load,
+    /// Dispatches chunk generation and applies finished results
+    Generate,
+    /// Runs after generation results are applied, before [`ChunkReady`] is sent
+    PostGenerate,
+    /// Draws the chunk visualizer's gizmos
+    Visualize,
+    /// Despawns chunks chosen by the unloader, in `PostUpdate` unless set
+    /// otherwise with [`ChunkyPlugin::with_schedule`]
+    Unload,
+}
+
+/// Decides what happens when a chunk is spawned at a position that already
+/// has a registered chunk.
+///
+/// Inserted as a resource by [`ChunkyPlugin`], so it can be changed at runtime.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub enum ChunkCollisionPolicy {
+    /// Logs a warning and keeps both entities; the new one stays unregistered.
+    /// Collisions of the same frame are logged as one warning.
+    Warn,
+    /// Registers the new chunk and despawns the old one
+    ReplaceOld,
+    /// Despawns the new chunk at the end of the frame, keeping the old one (default)
+    #[default]
+    DespawnNew,
+    /// Panics, useful to catch overlapping loaders in tests
+    Panic,
 }
 
 impl Default for ChunkyPlugin {
     fn default() -> Self {
-        Self::THREE_DIMETION
+        Self::THREE_DIMENSION
     }
 }
 
 /// Utility functions for spawning chunks in bulk
 pub mod helpers {
-    use crate::{Chunk, ChunkPos};
-    use bevy::prelude::*;
+    use crate::{Chunk, ChunkLayer, ChunkLoadState, ChunkPos, RejectedChunk, UnmanagedChunk};
+    use bevy::{math::I64Vec3, prelude::*};
+    use std::fmt;
+
+    pub use crate::chunk_prewarm::prewarm_region;
 
     /// Spawns chunks in a rectangular region defined by two chunk positions.
     ///
@@ -142,12 +605,71 @@ pub mod helpers {
             for y in y_small..=y_big {
                 for z in z_small..=z_big {
                     let chunk_pos = ivec3(x, y, z);
-                    commands.spawn((Chunk, ChunkPos(chunk_pos)));
+                    commands.spawn((Chunk, ChunkPos(chunk_pos), ChunkLoadState::Requested));
                 }
             }
         }
     }
 
+    /// Spawns chunks in a rectangular region like [`spawn_chunks_rect`], but
+    /// refuses regions with more than `max_chunks` chunks.
+    ///
+    /// Returns the number of spawned chunks. Guards against typos turning into
+    /// millions of chunks.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use chunky_bevy::helpers::*;
+    ///
+    /// fn setup(mut commands: Commands) {
+    ///     if let Err(error) =
+    ///         try_spawn_chunks_rect(&mut commands, IVec3::ZERO, IVec3::splat(5), 10_000)
+    ///     {
+    ///         warn!("{}", error);
+    ///     }
+    /// }
+    /// ```
+    pub fn try_spawn_chunks_rect(
+        commands: &mut Commands,
+        chunk_pos_0: IVec3,
+        chunk_pos_1: IVec3,
+        max_chunks: usize,
+    ) -> Result<usize, TooManyChunks> {
+        let size = (chunk_pos_0.as_i64vec3() - chunk_pos_1.as_i64vec3()).abs() + I64Vec3::ONE;
+        let count = (size.x as u64)
+            .saturating_mul(size.y as u64)
+            .saturating_mul(size.z as u64);
+        if count > max_chunks as u64 {
+            return Err(TooManyChunks { count, max_chunks });
+        }
+        spawn_chunks_rect(commands, chunk_pos_0, chunk_pos_1);
+        Ok(count as usize)
+    }
+
+    /// Error returned by [`try_spawn_chunks_rect`] for regions that are too
+    /// large.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct TooManyChunks {
+        /// Number of chunks in the region
+        pub count: u64,
+        /// The limit that was exceeded
+        pub max_chunks: usize,
+    }
+
+    impl fmt::Display for TooManyChunks {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "region of {} chunks exceeds the limit of {}",
+                self.count, self.max_chunks
+            )
+        }
+    }
+
+    impl std::error::Error for TooManyChunks {}
+
     /// Spawns chunks in a rectangular region defined by two world positions.
     ///
     /// The world positions are converted to chunk positions before spawning.
@@ -176,6 +698,100 @@ pub mod helpers {
         let chunk_pos_1 = chunk_pos_1.floor().as_ivec3();
         spawn_chunks_rect(commands, chunk_pos_0, chunk_pos_1);
     }
+
+    /// Spawns the chunks a straight line between two chunk positions passes
+    /// through, e.g. for roads or tunnels.
+    ///
+    /// Uses 3D Bresenham, so consecutive chunks touch at least at a corner.
+    /// Returns the spawned entities in order from `from` to `to`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use chunky_bevy::helpers::*;
+    ///
+    /// fn dig_tunnel(mut commands: Commands) {
+    ///     let tunnel = spawn_chunks_line(&mut commands, IVec3::ZERO, IVec3::new(10, -3, 4));
+    /// }
+    /// ```
+    pub fn spawn_chunks_line(commands: &mut Commands, from: IVec3, to: IVec3) -> Vec<Entity> {
+        line_chunk_positions(from, to)
+            .into_iter()
+            .map(|chunk_pos| {
+                commands
+                    .spawn((Chunk, ChunkPos(chunk_pos), ChunkLoadState::Requested))
+                    .id()
+            })
+            .collect()
+    }
+
+    /// Finds the chunk entity at `pos` by scanning all chunks, for code
+    /// without access to the [`ChunkManager`], e.g. while building it.
+    ///
+    /// Like [`ChunkManager::get_chunk`], only finds registered chunks on the
+    /// default layer 0, skipping [`UnmanagedChunk`]s and rejected duplicates.
+    /// Takes time linear in the number of chunks, so prefer
+    /// [`ChunkManager::get_chunk`] wherever it is available.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use chunky_bevy::{helpers::find_chunk_entity, prelude::*};
+    ///
+    /// fn spawn_chunk_content(world: &mut World) {
+    ///     if let Some(chunk) = find_chunk_entity(world, IVec3::ZERO) {
+    ///         world.spawn(ChildOf(chunk));
+    ///     }
+    /// }
+    /// ```
+    pub fn find_chunk_entity(world: &World, pos: IVec3) -> Option<Entity> {
+        find_chunk_entity_in_layer(world, pos, 0)
+    }
+
+    /// Finds the chunk entity at `pos` on a [`ChunkLayer`] by scanning all
+    /// chunks, like [`find_chunk_entity`] does for layer 0
+    pub fn find_chunk_entity_in_layer(world: &World, pos: IVec3, layer: i32) -> Option<Entity> {
+        let mut chunks = world.try_query_filtered::<
+            (Entity, &ChunkPos, Option<&ChunkLayer>),
+            (With<Chunk>, Without<UnmanagedChunk>, Without<RejectedChunk>),
+        >()?;
+        chunks
+            .iter(world)
+            .find_map(|(entity, ChunkPos(chunk_pos), chunk_layer)| {
+                (*chunk_pos == pos && chunk_layer.map_or(0, |chunk_layer| chunk_layer.0) == layer)
+                    .then_some(entity)
+            })
+    }
+
+    /// Chunk positions on the line from `from` to `to`, both included
+    pub(crate) fn line_chunk_positions(from: IVec3, to: IVec3) -> Vec<IVec3> {
+        let delta = (to - from).abs();
+        let step = (to - from).signum();
+        let steps = delta.max_element();
+
+        // Error terms of the two minor axes relative to the driving one
+        let mut error = IVec3::splat(steps) - delta * 2;
+        let mut current = from;
+        let mut positions = Vec::with_capacity(steps as usize + 1);
+        positions.push(current);
+        for _ in 0..steps {
+            for axis in 0..3 {
+                if delta[axis] == steps {
+                    current[axis] += step[axis];
+                } else {
+                    if error[axis] < 0 {
+                        current[axis] += step[axis];
+                        error[axis] += steps * 2;
+                    }
+                    error[axis] -= delta[axis] * 2;
+                }
+            }
+            positions.push(current);
+        }
+        positions
+    }
 }
 
 /// Marks an entity as a chunk.
@@ -185,6 +801,14 @@ pub mod helpers {
 /// - Unregisters the chunk when removed
 /// - Requires [`ChunkPos`] and [`Visibility`] components
 ///
+/// # Hook Ordering
+///
+/// All components of a spawned bundle, including required ones, are inserted
+/// before any `on_add` hook runs. Registration therefore always sees the
+/// final [`ChunkPos`], whether it was given explicitly or defaulted to
+/// `(0, 0, 0)` through `#[require]`. Should [`ChunkPos`] still be missing,
+/// registration is deferred to a command instead of panicking.
+///
 /// # Example
 ///
 /// ```no_run
@@ -199,6 +823,8 @@ pub mod helpers {
 /// }
 /// ```
 #[derive(Component)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
 #[require(ChunkPos, Visibility)]
 #[component(
     immutable,
@@ -207,37 +833,11 @@ pub mod helpers {
 )]
 pub struct Chunk;
 
-/// Adds Chunk to ChunkManager
-fn on_add_chunk(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
-    let chunk_pos = world.get::<ChunkPos>(entity).unwrap().0;
-    let mut chunk_manager = world.get_resource_mut::<ChunkManager>().unwrap();
-    if chunk_manager.is_loaded(&chunk_pos) {
-        warn!(
-            "New chunk at pos:{} was not spawned there was already a chunk there",
-            chunk_pos
-        );
-        return;
-    }
-
-    chunk_manager.insert(chunk_pos, entity);
-
-    #[cfg(feature = "chunk_info")]
-    info!("[ChunkInfo]ChunkPos: {chunk_pos:?}");
-}
-
-/// Removes Chunk from ChunkManager
-fn on_remove_chunk(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
-    let chunk_pos = world.get::<ChunkPos>(entity).unwrap().0;
-    world
-        .get_resource_mut::<ChunkManager>()
-        .unwrap()
-        .remove(&chunk_pos);
-}
-
-/// The position of a chunk in chunk-space coordinates.
+/// Keeps a [`Chunk`] out of the [`ChunkManager`], e.g. for a decorative
+/// preview ghost that shouldn't take the slot of a real chunk.
 ///
-/// When added to an entity, automatically updates the entity's [`Transform`]
-/// to match the chunk's world position.
+/// Must be spawned together with [`Chunk`], as chunks are registered when
+/// [`Chunk`] is added. [`ChunkPos`] still places the entity as usual.
 ///
 /// # Example
 ///
@@ -245,37 +845,46 @@ fn on_remove_chunk(mut world: DeferredWorld, HookContext { entity, .. }: HookCon
 /// use bevy::prelude::*;
 /// use chunky_bevy::prelude::*;
 ///
-/// fn spawn_chunk(mut commands: Commands) {
-///     // Spawns a chunk at chunk position (5, 0, 3)
-///     // With default 10x10x10 chunks, this will be at world position (50, 0, 30)
-///     commands.spawn((
-///         Chunk,
-///         ChunkPos(IVec3::new(5, 0, 3)),
-///     ));
+/// fn spawn_ghost(mut commands: Commands) {
+///     commands.spawn((Chunk, ChunkPos(IVec3::new(2, 0, 0)), UnmanagedChunk));
 /// }
 /// ```
-#[derive(Component, Default, Deref, DerefMut)]
+#[derive(Component, Debug, Clone, Copy, Default)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
 #[cfg_attr(feature = "reflect", reflect(Component))]
-#[require(Transform)]
-#[component(
-    immutable,
-    on_add= on_add_chunk_pos,
-)]
-pub struct ChunkPos(pub IVec3);
+pub struct UnmanagedChunk;
 
-/// Updates Transform to match ChunkPos
-fn on_add_chunk_pos(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
-    let chunk_pos = world.get::<ChunkPos>(entity).unwrap();
-    let chunk_size = world.get_resource::<ChunkManager>().unwrap().chunk_size;
-    let translation = chunk_pos.as_vec3() * chunk_size;
-    world.get_mut::<Transform>(entity).unwrap().translation = translation;
-}
+/// Marks a chunk whose data is kept in a chunk store, as it was loaded from
+/// the store or saved to it since.
+///
+/// Inserted by the `chunk_persistence` feature. The visualizer draws these
+/// chunks in its `persistent_color`.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+pub struct ChunkPersistent;
 
-/// Resource for managing all chunks in the world.
+/// An extra dimension of chunk positions, e.g. a dimension or world index,
+/// so chunks at the same [`ChunkPos`] on different layers coexist.
 ///
-/// Provides methods to query chunks by position and convert between
-/// world positions and chunk positions.
+/// Chunks without it are on layer 0, the default layer. Inserting, replacing
+/// or removing it moves a registered chunk to its new layer. Other layers are
+/// kept apart in the [`ChunkManager`] and only seen by its `_layer` methods.
+///
+/// Generation, including stages and neighbor data, looks up neighbors on the
+/// chunk's own layer; snapshots and the streaming progress keep each chunk's
+/// layer, and orphans are detected per layer. The unloader treats chunks of
+/// every layer alike, by their position. Everything else works on the default
+/// layer only:
+///
+/// - Loaders only spawn default layer chunks, though unload cooldowns are
+///   kept per layer.
+/// - Regions and [`RegionActivated`] only count default layer chunks.
+/// - Stores are keyed by position only, so chunks on other layers are never
+///   saved or loaded and always generate.
+/// - `RegenerateChunk` only regenerates default layer chunks.
+/// - Position lookups such as `get_chunk`, `raycast` or
+///   `entities_in_world_aabb` only see the default layer.
 ///
 /// # Example
 ///
@@ -283,60 +892,666 @@ fn on_add_chunk_pos(mut world: DeferredWorld, HookContext { entity, .. }: HookCo
 /// use bevy::prelude::*;
 /// use chunky_bevy::prelude::*;
 ///
-/// fn check_chunk(
-///     chunk_manager: Res<ChunkManager>,
-///     player_pos: Vec3,
-/// ) {
-///     // Get the chunk position the player is in
-///     let chunk_pos = chunk_manager.get_chunk_pos(&player_pos);
-///     
-///     // Check if that chunk is loaded
-///     if chunk_manager.is_loaded(&chunk_pos) {
-///         println!("Player is in a loaded chunk!");
-///     }
+/// fn open_nether(mut commands: Commands) {
+///     // Next to the overworld chunk at (0, 0, 0)
+///     commands.spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLayer(-1)));
+/// }
+///
+/// fn nether_chunk(chunk_manager: Res<ChunkManager>) -> Option<Entity> {
+///     chunk_manager.get_chunk_in_layer(&IVec3::ZERO, -1)
 /// }
 /// ```
-#[derive(Resource, Default)]
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "reflect", derive(Reflect))]
-#[cfg_attr(feature = "reflect", reflect(Resource))]
-pub struct ChunkManager {
-    chunk_size: Vec3,
-    chunks: HashMap<IVec3, Entity>,
-}
+#[cfg_attr(feature = "reflect", reflect(Component))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[component(
+    immutable,
+    on_insert = on_insert_chunk_layer,
+    on_replace = on_replace_chunk_layer
+)]
+pub struct ChunkLayer(pub i32);
 
-impl ChunkManager {
-    /// Creates a new chunk manager with the specified chunk size
-    pub fn new(chunk_size: Vec3) -> Self {
-        Self {
-            chunk_size,
-            chunks: default(),
-        }
+/// Moves a chunk registered before its ChunkLayer was added from the default
+/// layer to its layer
+fn on_insert_chunk_layer(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let layer = world.get::<ChunkLayer>(entity).unwrap().0;
+    let Some(chunk_pos) = world.get::<ChunkPos>(entity).map(|pos| pos.0) else {
+        return;
+    };
+    if layer == 0 || world.resource::<ChunkManager>().get_chunk(&chunk_pos) != Some(entity) {
+        return;
+    }
+    unregister_chunk(&mut world, entity, chunk_pos, 0);
+    set_ready_in_layer(&mut world, entity, chunk_pos, 0, false);
+    register_chunk(world.reborrow(), entity, chunk_pos);
+    if world
+        .resource::<ChunkManager>()
+        .get_chunk_in_layer(&chunk_pos, layer)
+        == Some(entity)
+    {
+        set_ready_in_layer(&mut world, entity, chunk_pos, layer, true);
     }
+}
 
-    /// Returns the size of chunks in world units
-    pub fn get_size(&self) -> Vec3 {
-        self.chunk_size
+/// Unregisters a chunk from the layer it leaves, registering it again on its
+/// new layer once the ChunkLayer is replaced or removed
+fn on_replace_chunk_layer(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let layer = world.get::<ChunkLayer>(entity).unwrap().0;
+    let Some(chunk_pos) = world.get::<ChunkPos>(entity).map(|pos| pos.0) else {
+        return;
+    };
+    if world
+        .resource::<ChunkManager>()
+        .get_chunk_in_layer(&chunk_pos, layer)
+        != Some(entity)
+    {
+        return;
     }
+    unregister_chunk(&mut world, entity, chunk_pos, layer);
+    set_ready_in_layer(&mut world, entity, chunk_pos, layer, false);
+    world.commands().queue(move |world: &mut World| {
+        // Despawned chunks are gone for good
+        if !world
+            .get_entity(entity)
+            .is_ok_and(|e| e.contains::<Chunk>() && !e.contains::<RejectedChunk>())
+        {
+            return;
+        }
+        let layer = world.get::<ChunkLayer>(entity).map_or(0, |layer| layer.0);
+        let mut world = DeferredWorld::from(world);
+        register_chunk(world.reborrow(), entity, chunk_pos);
+        if world
+            .resource::<ChunkManager>()
+            .get_chunk_in_layer(&chunk_pos, layer)
+            == Some(entity)
+        {
+            set_ready_in_layer(&mut world, entity, chunk_pos, layer, true);
+        }
+    });
+}
 
-    /// Inserts a new chunk into the manager.
-    ///
-    /// Returns the previous chunk entity if one already existed at this position.
-    ///
-    /// Note: This is called automatically when a [`Chunk`] component is added.
-    pub fn insert(&mut self, pos: IVec3, id: Entity) -> Option<Entity> {
-        self.chunks.insert(pos, id)
+/// Moves the ChunkStreamingProgress entry of a ready chunk to or away from a
+/// layer
+fn set_ready_in_layer(
+    world: &mut DeferredWorld,
+    entity: Entity,
+    chunk_pos: IVec3,
+    layer: i32,
+    ready: bool,
+) {
+    if world.get::<ChunkLoadState>(entity) != Some(&ChunkLoadState::Ready) {
+        return;
+    }
+    if let Some(mut progress) = world.get_resource_mut::<ChunkStreamingProgress>() {
+        progress.set_ready_at(chunk_pos.extend(layer), ready);
     }
+}
 
-    /// Removes a chunk from the manager.
+/// Adds Chunk to ChunkManager
+fn on_add_chunk(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    if world.entity(entity).contains::<UnmanagedChunk>() {
+        return;
+    }
+    let Some(chunk_pos) = world.get::<ChunkPos>(entity).map(|pos| pos.0) else {
+        world.commands().queue(move |world: &mut World| {
+            if !world
+                .get_entity(entity)
+                .is_ok_and(|e| e.contains::<Chunk>())
+            {
+                return;
+            }
+            let Some(chunk_pos) = world.get::<ChunkPos>(entity).map(|pos| pos.0) else {
+                warn!("Chunk {} has no ChunkPos and was not registered", entity);
+                return;
+            };
+            register_chunk(world.into(), entity, chunk_pos);
+        });
+        return;
+    };
+    register_chunk(world, entity, chunk_pos);
+}
+
+/// Registers a chunk entity at `chunk_pos` with the ChunkManager
+fn register_chunk(mut world: DeferredWorld, entity: Entity, chunk_pos: IVec3) {
+    let collision_policy = world
+        .get_resource::<ChunkCollisionPolicy>()
+        .copied()
+        .unwrap_or_default();
+    let layer = world.get::<ChunkLayer>(entity).map_or(0, |layer| layer.0);
+    let mut chunk_manager = world.get_resource_mut::<ChunkManager>().unwrap();
+    if let Some(existing) = chunk_manager.get_chunk_in_layer(&chunk_pos, layer) {
+        // Already registered, e.g. with `ChunkManager::insert_many`
+        if existing == entity {
+            return;
+        }
+        match collision_policy {
+            ChunkCollisionPolicy::Warn => {
+                match world.get_resource_mut::<ChunkCollisionWarnings>() {
+                    Some(mut warnings) => {
+                        warnings.first.get_or_insert(chunk_pos);
+                        warnings.count += 1;
+                    }
+                    None => warn!(
+                        "New chunk at pos:{} was not spawned there was already a chunk there",
+                        chunk_pos
+                    ),
+                }
+            }
+            ChunkCollisionPolicy::ReplaceOld => {
+                chunk_manager.insert_in_layer(chunk_pos, layer, entity);
+                world.commands().entity(existing).try_despawn();
+            }
+            ChunkCollisionPolicy::DespawnNew => {
+                // Despawning here would invalidate the entity handed out by `World::spawn`
+                world.commands().entity(entity).try_insert(RejectedChunk);
+            }
+            ChunkCollisionPolicy::Panic => {
+                panic!("New chunk at pos:{chunk_pos} collides with existing chunk {existing}");
+            }
+        }
+        return;
+    }
+
+    chunk_manager.insert_in_layer(chunk_pos, layer, entity);
+
+    // Regions only track the default layer
+    let region_pos = chunk_manager.region_of(chunk_pos);
+    let region_activated = layer == 0 && chunk_manager.region_chunk_count(&region_pos) == 1;
+    if region_activated {
+        world.write_message(RegionActivated { region_pos });
+    }
+
+    #[cfg(feature = "chunk_info")]
+    info!("[ChunkInfo]ChunkPos: {chunk_pos:?}");
+}
+
+/// Collisions ignored by [`ChunkCollisionPolicy::Warn`] this frame, logged
+/// together so duplicate spawns don't flood the log
+#[derive(Resource, Default)]
+struct ChunkCollisionWarnings {
+    count: usize,
+    first: Option<IVec3>,
+}
+
+/// Logs one warning for all collisions of the frame
+fn warn_chunk_collisions(mut warnings: ResMut<ChunkCollisionWarnings>) {
+    let Some(chunk_pos) = warnings.first.take() else {
+        return;
+    };
+    match std::mem::take(&mut warnings.count) {
+        1 => warn!(
+            "New chunk at pos:{} was not spawned there was already a chunk there",
+            chunk_pos
+        ),
+        count => warn!(
+            "{} new chunks were not spawned as there already were chunks there, first at pos:{}",
+            count, chunk_pos
+        ),
+    }
+}
+
+/// Warns in debug builds about chunks still registered on exit whose entities
+/// are gone, e.g. after registering them by hand, which helps catching leaked
+/// entries in tests
+#[cfg(debug_assertions)]
+fn warn_leaked_chunks(chunk_manager: Res<ChunkManager>, chunks: Query<(), With<Chunk>>) {
+    let mut leaked = chunk_manager
+        .iter_all_layers()
+        .filter(|(_, _, entity)| !chunks.contains(*entity));
+    let Some((chunk_pos, ..)) = leaked.next() else {
+        return;
+    };
+    warn!(
+        "Registered chunks without their entity on exit: {}, first at pos:{}",
+        leaked.count() + 1,
+        chunk_pos
+    );
+}
+
+/// Marks a chunk rejected by [`ChunkCollisionPolicy::DespawnNew`]
+#[derive(Component)]
+pub(crate) struct RejectedChunk;
+
+/// Despawns chunks rejected by the collision policy
+fn despawn_rejected_chunks(mut commands: Commands, rejected: Query<Entity, With<RejectedChunk>>) {
+    for entity in rejected.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Removes Chunk from ChunkManager
+fn on_remove_chunk(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let Some(chunk_pos) = world.get::<ChunkPos>(entity).map(|pos| pos.0) else {
+        return;
+    };
+    let layer = world.get::<ChunkLayer>(entity).map_or(0, |layer| layer.0);
+    unregister_chunk(&mut world, entity, chunk_pos, layer);
+}
+
+/// Unregisters a chunk entity from `chunk_pos` on a layer, if it is the one
+/// registered there
+fn unregister_chunk(world: &mut DeferredWorld, entity: Entity, chunk_pos: IVec3, layer: i32) {
+    let mut chunk_manager = world.get_resource_mut::<ChunkManager>().unwrap();
+    if chunk_manager.get_chunk_in_layer(&chunk_pos, layer) != Some(entity) {
+        return;
+    }
+    if layer != 0 {
+        chunk_manager.layered.remove(&chunk_pos.extend(layer));
+        return;
+    }
+    chunk_manager.remove(&chunk_pos);
+
+    let region_pos = chunk_manager.region_of(chunk_pos);
+    let region_deactivated = chunk_manager.region_chunk_count(&region_pos) == 0;
+    if region_deactivated {
+        world.write_message(RegionDeactivated { region_pos });
+    }
+}
+
+/// The position of a chunk in chunk-space coordinates.
+///
+/// When added to an entity, automatically updates the entity's [`Transform`]
+/// to match the chunk's world position.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn spawn_chunk(mut commands: Commands) {
+///     // Spawns a chunk at chunk position (5, 0, 3)
+///     // With default 10x10x10 chunks, this will be at world position (50, 0, 30)
+///     commands.spawn((
+///         Chunk,
+///         ChunkPos(IVec3::new(5, 0, 3)),
+///     ));
+/// }
+/// ```
+#[derive(Component, Default, Deref, DerefMut)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Component))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[require(Transform)]
+#[component(
+    immutable,
+    on_add= on_add_chunk_pos,
+)]
+pub struct ChunkPos(pub IVec3);
+
+/// Updates Transform to match ChunkPos
+fn on_add_chunk_pos(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let chunk_pos = world.get::<ChunkPos>(entity).unwrap();
+    let chunk_manager = world.get_resource::<ChunkManager>().unwrap();
+    let translation = chunk_manager.get_world_pos(chunk_pos) + chunk_manager.anchor_offset();
+    world.get_mut::<Transform>(entity).unwrap().translation = translation;
+}
+
+/// Offsets of the 6 chunks sharing a face with a chunk, in the order
+/// `+X, -X, +Y, -Y, +Z, -Z`.
+pub const NEIGHBORS_6: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// Offsets of the 26 chunks around a chunk.
+///
+/// Ordered by `z`, then `y`, then `x`, each from `-1` to `1`, skipping the
+/// chunk itself: `(-1, -1, -1), (0, -1, -1), (1, -1, -1), (-1, 0, -1), ...`.
+pub const NEIGHBORS_26: [IVec3; 26] = {
+    let mut offsets = [IVec3::ZERO; 26];
+    let mut i = 0;
+    let mut n = 0;
+    while n < 27 {
+        let offset = IVec3::new(n % 3 - 1, n / 3 % 3 - 1, n / 9 - 1);
+        if n != 13 {
+            offsets[i] = offset;
+            i += 1;
+        }
+        n += 1;
+    }
+    offsets
+};
+
+/// Resource for managing all chunks in the world.
+///
+/// Provides methods to query chunks by position and convert between
+/// world positions and chunk positions.
+///
+/// Registering or removing a chunk marks the resource as changed, while
+/// lookups and rejected duplicate chunks don't. Systems can therefore react
+/// to the chunk set changing with `resource_changed::<ChunkManager>`; order
+/// them after [`ChunkySystems::Load`] to see the chunks loaders spawned that
+/// frame.
+///
+/// Methods taking a plain chunk position, like [`get_chunk`](Self::get_chunk),
+/// and the ones going over all chunks, like [`iter`](Self::iter), only see
+/// the default layer 0. Chunks with another [`ChunkLayer`] are found with
+/// [`get_chunk_in_layer`](Self::get_chunk_in_layer) and
+/// [`iter_layer`](Self::iter_layer). Only [`len`](Self::len),
+/// [`is_empty`](Self::is_empty) and [`drain`](Self::drain) cover every layer.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn rebuild_navmesh(chunk_manager: Res<ChunkManager>) {
+///     info!("{} chunks loaded", chunk_manager.iter().count());
+/// }
+///
+/// App::new().add_systems(
+///     Update,
+///     rebuild_navmesh
+///         .run_if(resource_changed::<ChunkManager>)
+///         .after(ChunkySystems::Load),
+/// );
+///
+/// fn check_chunk(
+///     chunk_manager: Res<ChunkManager>,
+///     player_pos: Vec3,
+/// ) {
+///     // Get the chunk position the player is in
+///     let chunk_pos = chunk_manager.get_chunk_pos(&player_pos);
+///     
+///     // Check if that chunk is loaded
+///     if chunk_manager.is_loaded(&chunk_pos) {
+///         println!("Player is in a loaded chunk!");
+///     }
+/// }
+/// ```
+#[derive(Resource, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub struct ChunkManager {
+    chunk_size: Vec3,
+    /// The chunk size of grid-exact worlds, see [`ChunkManager::new_int`]
+    int_chunk_size: Option<IVec3>,
+    chunks: HashMap<IVec3, Entity>,
+    region_size: RegionSize,
+    regions: HashMap<IVec3, usize>,
+    rounding_mode: RoundingMode,
+    world_offset: Vec3,
+    anchor: ChunkAnchor,
+    /// Chunks on layers other than the default one, keyed by their position
+    /// with the layer as `w`
+    layered: HashMap<IVec4, Entity>,
+}
+
+/// A chunk entity together with its chunk position, see
+/// [`ChunkManager::get_chunk_ref`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkRef {
+    /// Position of the chunk in chunk coordinates
+    pub pos: IVec3,
+    /// The chunk entity
+    pub entity: Entity,
+}
+
+impl From<ChunkRef> for Entity {
+    fn from(chunk: ChunkRef) -> Self {
+        chunk.entity
+    }
+}
+
+/// How densely the loaded chunks fill their bounding box, see
+/// [`ChunkManager::occupancy_stats`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkOccupancy {
+    /// Number of loaded chunks on the default layer 0
+    pub total: usize,
+    /// Number of chunk positions in the box spanned by the loaded chunks
+    pub bounding_volume: u64,
+    /// `total / bounding_volume`, 1 if the box is completely loaded and 0 if
+    /// no chunk is loaded
+    pub fill_ratio: f32,
+}
+
+impl ChunkManager {
+    /// Creates a new chunk manager with the specified chunk size
+    pub fn new(chunk_size: Vec3) -> Self {
+        Self {
+            chunk_size,
+            int_chunk_size: None,
+            chunks: default(),
+            region_size: default(),
+            regions: default(),
+            rounding_mode: default(),
+            world_offset: Vec3::ZERO,
+            anchor: default(),
+            layered: default(),
+        }
+    }
+
+    /// Creates a new chunk manager with an integer chunk size, for grid-aligned
+    /// worlds such as voxel worlds.
+    ///
+    /// World positions with whole coordinates are then mapped to chunk
+    /// positions with integer division, so they stay exact at coordinates
+    /// where `f32` division would round into the wrong chunk. See
+    /// [`get_chunk_pos_int`](Self::get_chunk_pos_int) to skip `f32` entirely.
+    pub fn new_int(chunk_size: IVec3) -> Self {
+        let mut chunk_manager = Self::new(chunk_size.as_vec3());
+        chunk_manager.int_chunk_size = Some(chunk_size);
+        chunk_manager
+    }
+
+    /// Creates a new chunk manager with room for `capacity` chunks, so
+    /// loading a world of known size doesn't grow the chunk map step by step
+    pub fn with_capacity(chunk_size: Vec3, capacity: usize) -> Self {
+        let mut chunk_manager = Self::new(chunk_size);
+        chunk_manager.reserve(capacity);
+        chunk_manager
+    }
+
+    /// Makes room for at least `additional` more chunks on the default layer
+    /// 0, e.g. before spawning a large region at once
+    pub fn reserve(&mut self, additional: usize) {
+        self.chunks.reserve(additional);
+    }
+
+    /// Sets the region size used to group chunks. Sizes below 1 chunk are
+    /// raised to 1.
+    ///
+    /// Should be set before any chunks are inserted, as existing region
+    /// counts are not recomputed.
+    pub fn with_region_size(mut self, region_size: RegionSize) -> Self {
+        self.region_size = region_size.clamped();
+        self
+    }
+
+    /// Sets how world positions are rounded to chunk positions
+    pub fn with_rounding_mode(mut self, rounding_mode: RoundingMode) -> Self {
+        self.rounding_mode = rounding_mode;
+        self
+    }
+
+    /// Returns how world positions are rounded to chunk positions
+    pub fn get_rounding_mode(&self) -> RoundingMode {
+        self.rounding_mode
+    }
+
+    /// Sets where the [`Transform`] of newly spawned chunk entities sits
+    /// within their chunk
+    pub fn with_anchor(mut self, anchor: ChunkAnchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Returns where the [`Transform`] of chunk entities sits within their
+    /// chunk
+    pub fn get_anchor(&self) -> ChunkAnchor {
+        self.anchor
+    }
+
+    /// Returns the offset of a chunk entity's [`Transform`] from the origin
+    /// corner of its chunk, e.g. to place children relative to the corner
+    pub fn anchor_offset(&self) -> Vec3 {
+        match self.anchor {
+            ChunkAnchor::Corner => Vec3::ZERO,
+            ChunkAnchor::Center => self.chunk_size / 2.0,
+        }
+    }
+
+    /// Returns the size of chunks in world units
+    pub fn get_size(&self) -> Vec3 {
+        self.chunk_size
+    }
+
+    /// Returns the integer size of chunks, if the manager was created with
+    /// [`new_int`](Self::new_int)
+    pub fn get_int_size(&self) -> Option<IVec3> {
+        self.int_chunk_size
+    }
+
+    /// Returns the volume of a chunk, e.g. to size voxel buffers.
+    ///
+    /// Axes of size zero are left out, so 2D chunks of size `(32, 0, 32)`
+    /// give their area, 1024.
+    pub fn chunk_volume(&self) -> f32 {
+        nonzero_product(self.chunk_size.to_array())
+    }
+
+    /// Returns the area of the chunk faces across `axis`, a face normal such
+    /// as `IVec3::Y` or one of [`NEIGHBORS_6`].
+    ///
+    /// Like [`chunk_volume`](Self::chunk_volume), axes of size zero are left
+    /// out, so across the flat axis of a 2D chunk this is its area, and across
+    /// the others its edge length.
+    pub fn chunk_face_area(&self, axis: IVec3) -> f32 {
+        let across = axis.abs().max_position();
+        let mut sizes = self.chunk_size.to_array();
+        sizes[across] = 0.0;
+        nonzero_product(sizes)
+    }
+
+    /// Returns the size of regions in chunks
+    pub fn get_region_size(&self) -> RegionSize {
+        self.region_size
+    }
+
+    /// Sets the world position of the chunk grid's origin, e.g. for a
+    /// floating origin.
+    ///
+    /// When the world is shifted by `delta`, shift the offset by the same
+    /// `delta` so world positions keep mapping to the same chunks. Chunks
+    /// spawned afterwards are placed relative to the new offset; moving the
+    /// existing chunk entities is left to the floating origin. Generation
+    /// still sees unshifted positions, so its output does not depend on the
+    /// offset.
+    pub fn set_world_offset(&mut self, world_offset: Vec3) {
+        self.world_offset = world_offset;
+    }
+
+    /// Returns the world position of the chunk grid's origin
+    pub fn get_world_offset(&self) -> Vec3 {
+        self.world_offset
+    }
+
+    /// Inserts a new chunk into the manager.
+    ///
+    /// Returns the previous chunk entity if one already existed at this position.
+    ///
+    /// Note: This is called automatically when a [`Chunk`] component is added.
+    pub fn insert(&mut self, pos: IVec3, id: Entity) -> Option<Entity> {
+        let previous = self.chunks.insert(pos, id);
+        if previous.is_none() {
+            *self.regions.entry(self.region_of(pos)).or_default() += 1;
+        }
+        previous
+    }
+
+    /// Removes a chunk from the manager.
     ///
     /// Returns the chunk's entity if it existed.
     ///
     /// Note: This is called automatically when a [`Chunk`] component is removed.
     pub fn remove(&mut self, pos: &IVec3) -> Option<Entity> {
-        self.chunks.remove(pos)
+        let removed = self.chunks.remove(pos);
+        if removed.is_some() {
+            let region_pos = self.region_of(*pos);
+            if let Some(count) = self.regions.get_mut(&region_pos) {
+                *count -= 1;
+                if *count == 0 {
+                    self.regions.remove(&region_pos);
+                }
+            }
+        }
+        removed
+    }
+
+    /// Inserts many chunks at once, e.g. from a loader outside this crate.
+    ///
+    /// Unlike adding the [`Chunk`] component, this bypasses the component
+    /// hooks: the [`ChunkCollisionPolicy`] is not applied, chunks already
+    /// registered at a position are replaced, and no [`RegionActivated`]
+    /// messages are sent. Callers must also add the [`Chunk`] component with
+    /// the same [`ChunkPos`], or keep the map authoritative themselves, e.g. by
+    /// removing the chunks again with [`remove_many`](Self::remove_many).
+    pub fn insert_many(&mut self, chunks: impl IntoIterator<Item = (IVec3, Entity)>) {
+        let chunks = chunks.into_iter();
+        self.chunks.reserve(chunks.size_hint().0);
+        for (pos, id) in chunks {
+            self.insert(pos, id);
+        }
+    }
+
+    /// Removes many chunks at once, the counterpart of
+    /// [`insert_many`](Self::insert_many).
+    ///
+    /// Bypasses the component hooks as well: the entities are neither
+    /// despawned nor lose their [`Chunk`] component, and no
+    /// [`RegionDeactivated`] messages are sent.
+    pub fn remove_many(&mut self, positions: impl IntoIterator<Item = IVec3>) {
+        for pos in positions {
+            self.remove(&pos);
+        }
+    }
+
+    /// Iterates over the positions and entities of the registered chunks on
+    /// the default layer 0, see [`iter_all_layers`](Self::iter_all_layers)
+    /// for every layer
+    pub fn iter(&self) -> impl Iterator<Item = (IVec3, Entity)> + '_ {
+        self.chunks.iter().map(|(pos, entity)| (*pos, *entity))
+    }
+
+    /// Returns the number of registered chunks, on all [`ChunkLayer`]s unlike
+    /// [`iter`](Self::iter)
+    pub fn len(&self) -> usize {
+        self.chunks.len() + self.layered.len()
+    }
+
+    /// Checks if no chunk is registered on any [`ChunkLayer`]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty() && self.layered.is_empty()
     }
 
-    /// Converts world coordinates into chunk position
+    /// Removes all chunks from the manager, on all [`ChunkLayer`]s, returning
+    /// their positions, layers and entities.
+    ///
+    /// The entities are not despawned and no [`RegionDeactivated`] messages
+    /// are sent; despawn the returned entities to avoid leaking them.
+    pub fn drain(&mut self) -> impl Iterator<Item = (IVec3, i32, Entity)> + '_ {
+        self.regions.clear();
+        let layered = self
+            .layered
+            .drain()
+            .map(|(pos, entity)| (pos.truncate(), pos.w, entity));
+        self.chunks
+            .drain()
+            .map(|(pos, entity)| (pos, 0, entity))
+            .chain(layered)
+    }
+
+    /// Converts world coordinates into chunk position, rounded according to
+    /// the [`RoundingMode`]. Axes with a chunk size of zero, like the flat
+    /// axis of 2D chunks, are always `0`.
     ///
     /// # Example
     ///
@@ -351,7 +1566,86 @@ impl ChunkManager {
     /// }
     /// ```
     pub fn get_chunk_pos(&self, pos: &Vec3) -> IVec3 {
-        (*pos / self.chunk_size).floor().as_ivec3()
+        if self.int_chunk_size.is_some() && *pos == pos.round() {
+            return self.get_chunk_pos_int(&pos.as_ivec3());
+        }
+        let pos = (*pos - self.world_offset) / self.chunk_size;
+        let chunk_pos = match self.rounding_mode {
+            RoundingMode::Floor => pos.floor(),
+            RoundingMode::Round => pos.round(),
+            RoundingMode::Ceil => pos.ceil(),
+        }
+        .as_ivec3();
+        // Flat axes of 2D chunks hold a single layer of chunks
+        IVec3::select(self.chunk_size.cmpeq(Vec3::ZERO), IVec3::ZERO, chunk_pos)
+    }
+
+    /// Converts integer world coordinates into a chunk position, rounded
+    /// according to the [`RoundingMode`].
+    ///
+    /// With an integer chunk size, see [`new_int`](Self::new_int), and a world
+    /// offset with whole coordinates, this uses integer division only.
+    /// Otherwise it is the same as [`get_chunk_pos`](Self::get_chunk_pos).
+    pub fn get_chunk_pos_int(&self, pos: &IVec3) -> IVec3 {
+        let offset = self.world_offset;
+        let Some(chunk_size) = self.int_chunk_size.filter(|_| offset == offset.round()) else {
+            let pos = (pos.as_vec3() - offset) / self.chunk_size;
+            let chunk_pos = match self.rounding_mode {
+                RoundingMode::Floor => pos.floor(),
+                RoundingMode::Round => pos.round(),
+                RoundingMode::Ceil => pos.ceil(),
+            }
+            .as_ivec3();
+            return IVec3::select(self.chunk_size.cmpeq(Vec3::ZERO), IVec3::ZERO, chunk_pos);
+        };
+        let pos = pos.as_i64vec3() - offset.as_i64vec3();
+        let size = chunk_size.as_i64vec3();
+        let divide = |pos: i64, size: i64| match self.rounding_mode {
+            _ if size == 0 => 0,
+            RoundingMode::Floor => pos.div_euclid(size),
+            RoundingMode::Ceil => -(-pos).div_euclid(size),
+            // Halfway rounds away from zero, like f32::round
+            RoundingMode::Round if pos < 0 => -(size - 2 * pos).div_euclid(2 * size),
+            RoundingMode::Round => (2 * pos + size).div_euclid(2 * size),
+        };
+        I64Vec3::new(
+            divide(pos.x, size.x),
+            divide(pos.y, size.y),
+            divide(pos.z, size.z),
+        )
+        .as_ivec3()
+    }
+
+    /// Converts a chunk position into the world position of its origin corner,
+    /// including the world offset
+    pub fn get_world_pos(&self, chunk_pos: &IVec3) -> Vec3 {
+        chunk_pos.as_vec3() * self.chunk_size + self.world_offset
+    }
+
+    /// The chunk containing a world position, with flat axes at zero.
+    ///
+    /// Containment always rounds down, regardless of the [`RoundingMode`].
+    fn containing_chunk(&self, pos: Vec3) -> IVec3 {
+        let chunk_pos = ((pos - self.world_offset) / self.chunk_size)
+            .floor()
+            .as_ivec3();
+        IVec3::select(self.chunk_size.cmpeq(Vec3::ZERO), IVec3::ZERO, chunk_pos)
+    }
+
+    /// Snaps a world position to the origin corner of the chunk containing it,
+    /// e.g. for placing objects on chunk boundaries in level tools.
+    ///
+    /// Always rounds down, regardless of the [`RoundingMode`]. Coordinates
+    /// along flat axes of 2D chunks are kept as they are.
+    pub fn snap_to_chunk_origin(&self, pos: Vec3) -> Vec3 {
+        let origin = self.get_world_pos(&self.containing_chunk(pos));
+        Vec3::select(self.chunk_size.cmpeq(Vec3::ZERO), pos, origin)
+    }
+
+    /// Snaps a world position to the center of the chunk containing it, see
+    /// [`snap_to_chunk_origin`](Self::snap_to_chunk_origin)
+    pub fn snap_to_chunk_center(&self, pos: Vec3) -> Vec3 {
+        self.snap_to_chunk_origin(pos) + self.chunk_size / 2.0
     }
 
     /// Gets the chunk entity at the specified chunk position if it exists
@@ -359,13 +1653,1612 @@ impl ChunkManager {
         self.chunks.get(chunk_pos).copied()
     }
 
-    /// Gets the chunk entity at the specified world position if it exists
-    pub fn get_chunk_form_pos(&self, pos: &Vec3) -> Option<Entity> {
-        self.get_chunk(&self.get_chunk_pos(pos))
+    /// Gets the chunk entity at the specified chunk position on a
+    /// [`ChunkLayer`], layer 0 being the one of [`get_chunk`](Self::get_chunk)
+    pub fn get_chunk_in_layer(&self, chunk_pos: &IVec3, layer: i32) -> Option<Entity> {
+        match layer {
+            0 => self.get_chunk(chunk_pos),
+            _ => self.layered.get(&chunk_pos.extend(layer)).copied(),
+        }
     }
 
-    /// Checks if a chunk is loaded at the specified chunk position
-    pub fn is_loaded(&self, chunk_pos: &IVec3) -> bool {
-        self.chunks.contains_key(chunk_pos)
+    /// Checks if a chunk is loaded at the specified chunk position on a
+    /// [`ChunkLayer`]
+    pub fn is_loaded_in_layer(&self, chunk_pos: &IVec3, layer: i32) -> bool {
+        self.get_chunk_in_layer(chunk_pos, layer).is_some()
+    }
+
+    /// Iterates over the positions and entities of the chunks on a
+    /// [`ChunkLayer`], layer 0 being the one of [`iter`](Self::iter)
+    pub fn iter_layer(&self, layer: i32) -> impl Iterator<Item = (IVec3, Entity)> + '_ {
+        let default_layer = (layer == 0).then(|| self.iter()).into_iter().flatten();
+        let layered = self
+            .layered
+            .iter()
+            .filter(move |(pos, _)| layer != 0 && pos.w == layer)
+            .map(|(pos, entity)| (pos.truncate(), *entity));
+        default_layer.chain(layered)
+    }
+
+    /// Iterates over the positions, layers and entities of the registered
+    /// chunks on all [`ChunkLayer`]s
+    pub fn iter_all_layers(&self) -> impl Iterator<Item = (IVec3, i32, Entity)> + '_ {
+        let layered = self
+            .layered
+            .iter()
+            .map(|(pos, entity)| (pos.truncate(), pos.w, *entity));
+        self.iter()
+            .map(|(pos, entity)| (pos, 0, entity))
+            .chain(layered)
+    }
+
+    /// Registers a chunk on a layer, keeping regions to the default layer
+    fn insert_in_layer(&mut self, pos: IVec3, layer: i32, id: Entity) -> Option<Entity> {
+        match layer {
+            0 => self.insert(pos, id),
+            _ => self.layered.insert(pos.extend(layer), id),
+        }
+    }
+
+    /// Gets the chunk at the specified chunk position if it exists, keeping
+    /// its position next to its entity
+    pub fn get_chunk_ref(&self, chunk_pos: &IVec3) -> Option<ChunkRef> {
+        self.get_chunk(chunk_pos).map(|entity| ChunkRef {
+            pos: *chunk_pos,
+            entity,
+        })
+    }
+
+    /// Gets the chunk entity at the specified world position if it exists
+    pub fn get_chunk_form_pos(&self, pos: &Vec3) -> Option<Entity> {
+        self.get_chunk(&self.get_chunk_pos(pos))
+    }
+
+    /// Checks if a chunk is loaded at the specified chunk position
+    pub fn is_loaded(&self, chunk_pos: &IVec3) -> bool {
+        self.chunks.contains_key(chunk_pos)
+    }
+
+    /// Checks if the line between two chunks passes only through chunks that
+    /// don't block it, as decided by `blocker`.
+    ///
+    /// The chunks on the line are the ones of
+    /// [`spawn_chunks_line`](helpers::spawn_chunks_line). `from` and `to`
+    /// themselves are never checked, so neighboring chunks always see each
+    /// other.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use chunky_bevy::prelude::*;
+    ///
+    /// fn can_path(chunk_manager: &ChunkManager, npc: IVec3, target: IVec3) -> bool {
+    ///     // Only walk through the loaded world
+    ///     chunk_manager.line_of_sight(npc, target, SightBlocker::Unloaded)
+    /// }
+    /// ```
+    pub fn line_of_sight(&self, from: IVec3, to: IVec3, blocker: SightBlocker) -> bool {
+        helpers::line_chunk_positions(from, to)
+            .into_iter()
+            .filter(|&chunk_pos| chunk_pos != from && chunk_pos != to)
+            .all(|chunk_pos| match blocker {
+                SightBlocker::Unloaded => self.is_loaded(&chunk_pos),
+                SightBlocker::Loaded => !self.is_loaded(&chunk_pos),
+            })
+    }
+
+    /// Walks the chunk positions a ray passes through, in order, up to
+    /// `max_distance` from its origin, loaded or not.
+    ///
+    /// The chunks are the ones containing the points of the ray, so always
+    /// rounded down regardless of the [`RoundingMode`]. Flat axes of 2D chunks
+    /// stay at zero.
+    pub fn ray_chunks(&self, ray: Ray3d, max_distance: f32) -> ChunkRay {
+        let origin = ray.origin;
+        let direction = *ray.direction;
+        let local = (origin - self.world_offset) / self.chunk_size;
+        let flat = self.chunk_size.cmpeq(Vec3::ZERO);
+        let current = IVec3::select(flat, IVec3::ZERO, local.floor().as_ivec3());
+        let mut step = IVec3::ZERO;
+        let mut t_max = Vec3::INFINITY;
+        let mut t_delta = Vec3::INFINITY;
+        for axis in 0..3 {
+            let size = self.chunk_size[axis];
+            let dir = direction[axis];
+            if flat.test(axis) || dir == 0.0 {
+                continue;
+            }
+            // Distance along the ray to the first boundary crossed on this axis
+            let boundary =
+                (current[axis] + i32::from(dir > 0.0)) as f32 * size + self.world_offset[axis];
+            step[axis] = dir.signum() as i32;
+            t_max[axis] = (boundary - origin[axis]) / dir;
+            t_delta[axis] = size / dir.abs();
+        }
+        ChunkRay {
+            current,
+            step,
+            t_max,
+            t_delta,
+            max_distance,
+            done: false,
+        }
+    }
+
+    /// Returns the first loaded chunk along a ray within `max_distance` of its
+    /// origin, see [`ray_chunks`](Self::ray_chunks).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use chunky_bevy::prelude::*;
+    ///
+    /// fn aimed_chunk(chunk_manager: &ChunkManager, eye: &GlobalTransform) -> Option<ChunkRef> {
+    ///     let ray = Ray3d::new(eye.translation(), eye.forward());
+    ///     chunk_manager.raycast(ray, 100.0)
+    /// }
+    /// ```
+    pub fn raycast(&self, ray: Ray3d, max_distance: f32) -> Option<ChunkRef> {
+        self.ray_chunks(ray, max_distance).find_map(|chunk_pos| {
+            self.get_chunk(&chunk_pos).map(|entity| ChunkRef {
+                pos: chunk_pos,
+                entity,
+            })
+        })
+    }
+
+    /// Iterates over the loaded chunks on the frontier of the loaded world:
+    /// the ones with at least one of their [`NEIGHBORS_6`] not loaded. Only
+    /// covers the default layer 0.
+    ///
+    /// In a solid block of chunks these are the chunks on its surface.
+    pub fn boundary_chunks(&self) -> impl Iterator<Item = (IVec3, Entity)> + '_ {
+        self.iter().filter(|(pos, _)| {
+            NEIGHBORS_6
+                .iter()
+                .any(|offset| !self.is_loaded(&(pos + offset)))
+        })
+    }
+
+    /// Collects the entities of all loaded chunks overlapping a world-space box.
+    ///
+    /// The corners may be given in any order. Only covers the default layer 0.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use chunky_bevy::prelude::*;
+    ///
+    /// fn explode(chunk_manager: Res<ChunkManager>) {
+    ///     let center = Vec3::new(12.0, 3.0, -4.0);
+    ///     let radius = Vec3::splat(8.0);
+    ///     for entity in chunk_manager.entities_in_world_aabb(center - radius, center + radius) {
+    ///         // Damage the chunk
+    ///     }
+    /// }
+    /// ```
+    pub fn entities_in_world_aabb(&self, min: Vec3, max: Vec3) -> Vec<Entity> {
+        let chunk_0 = self.containing_chunk(min);
+        let chunk_1 = self.containing_chunk(max);
+        let (min, max) = (chunk_0.min(chunk_1), chunk_0.max(chunk_1));
+
+        // Boxes covering more positions than there are chunks are cheaper to
+        // check against the loaded chunks
+        let size = max.as_i64vec3() - min.as_i64vec3() + I64Vec3::ONE;
+        let positions = size.x.saturating_mul(size.y).saturating_mul(size.z);
+        if positions > self.chunks.len() as i64 {
+            return self
+                .chunks
+                .iter()
+                .filter(|(pos, _)| pos.cmpge(min).all() && pos.cmple(max).all())
+                .map(|(_, entity)| *entity)
+                .collect();
+        }
+
+        let mut entities = Vec::new();
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    if let Some(entity) = self.get_chunk(&ivec3(x, y, z)) {
+                        entities.push(entity);
+                    }
+                }
+            }
+        }
+        entities
+    }
+
+    /// Returns the inclusive minimum and maximum loaded chunk positions on the
+    /// default layer 0, or `None` if no chunk is loaded there.
+    pub fn extent(&self) -> Option<(IVec3, IVec3)> {
+        self.chunks
+            .keys()
+            .fold(None, |extent, &chunk_pos| match extent {
+                None => Some((chunk_pos, chunk_pos)),
+                Some((min, max)) => Some((min.min(chunk_pos), max.max(chunk_pos))),
+            })
+    }
+
+    /// Returns the world-space box covering all loaded chunks on the default
+    /// layer 0, from the origin corner of the minimum chunk to the far corner
+    /// of the maximum one, or `None` if no chunk is loaded there.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use chunky_bevy::prelude::*;
+    ///
+    /// fn frame_world(chunk_manager: Res<ChunkManager>, mut camera: Single<&mut Transform, With<Camera>>) {
+    ///     if let Some((min, max)) = chunk_manager.world_aabb() {
+    ///         let center = (min + max) / 2.0;
+    ///         camera.translation = center + Vec3::splat(max.distance(min));
+    ///         camera.look_at(center, Vec3::Y);
+    ///     }
+    /// }
+    /// ```
+    pub fn world_aabb(&self) -> Option<(Vec3, Vec3)> {
+        self.extent().map(|(min, max)| {
+            (
+                self.get_world_pos(&min),
+                self.get_world_pos(&(max + IVec3::ONE)),
+            )
+        })
+    }
+
+    /// Measures how densely the loaded chunks fill their bounding box, in a
+    /// single pass over the chunks.
+    ///
+    /// Like [`extent`](Self::extent), only covers the default layer 0, so
+    /// with other [`ChunkLayer`]s the `total` is below [`len`](Self::len).
+    ///
+    /// A low fill ratio with many chunks hints at scattered loading, e.g. by
+    /// many small loaders.
+    pub fn occupancy_stats(&self) -> ChunkOccupancy {
+        let bounding_volume = self.extent().map_or(0, |(min, max)| {
+            (max.as_i64vec3() - min.as_i64vec3() + I64Vec3::ONE).element_product() as u64
+        });
+        ChunkOccupancy {
+            total: self.chunks.len(),
+            bounding_volume,
+            fill_ratio: if bounding_volume == 0 {
+                0.0
+            } else {
+                self.chunks.len() as f32 / bounding_volume as f32
+            },
+        }
+    }
+
+    /// Packs which chunks between two chunk positions are loaded on the
+    /// default layer 0 into a bitmask.
+    ///
+    /// Returns the size of the region in chunks and one bit per chunk, set if
+    /// loaded. Chunks are ordered row-major with `x` varying fastest, then `y`,
+    /// then `z`, starting at the minimum corner; bit `i` is bit `i % 8` of byte
+    /// `i / 8`.
+    ///
+    /// Regions too large to index, spanning more than `i32::MAX` chunks along
+    /// an axis or more than `isize::MAX` chunks in total, give a size of zero
+    /// and no bits.
+    pub fn occupancy_bitmask(&self, chunk_pos_0: IVec3, chunk_pos_1: IVec3) -> (IVec3, Vec<u8>) {
+        let (min, max) = (chunk_pos_0.min(chunk_pos_1), chunk_pos_0.max(chunk_pos_1));
+        let size = max.as_i64vec3() - min.as_i64vec3() + I64Vec3::ONE;
+        let count = (size.x as u64)
+            .saturating_mul(size.y as u64)
+            .saturating_mul(size.z as u64);
+        let Ok(dims) = IVec3::try_from(size) else {
+            return (IVec3::ZERO, Vec::new());
+        };
+        if count > isize::MAX as u64 {
+            return (IVec3::ZERO, Vec::new());
+        }
+        let mut bits = vec![0; (count as usize).div_ceil(8)];
+
+        let mut i = 0;
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    if self.is_loaded(&ivec3(x, y, z)) {
+                        bits[i / 8] |= 1 << (i % 8);
+                    }
+                    i += 1;
+                }
+            }
+        }
+        (dims, bits)
+    }
+
+    /// Makes the loaded chunks on the default layer 0 between two chunk
+    /// positions match a bitmask from
+    /// [`occupancy_bitmask`](Self::occupancy_bitmask).
+    ///
+    /// Spawns a `Requested` chunk for every set bit without a loaded chunk and
+    /// despawns the loaded chunk of every cleared bit. Missing bytes count as
+    /// cleared bits.
+    pub fn apply_occupancy_bitmask(
+        &self,
+        commands: &mut Commands,
+        chunk_pos_0: IVec3,
+        chunk_pos_1: IVec3,
+        bits: &[u8],
+    ) {
+        let (min, max) = (chunk_pos_0.min(chunk_pos_1), chunk_pos_0.max(chunk_pos_1));
+
+        let mut i = 0;
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let chunk_pos = ivec3(x, y, z);
+                    let occupied = bits
+                        .get(i / 8)
+                        .is_some_and(|byte| byte & (1 << (i % 8)) != 0);
+                    match (occupied, self.get_chunk(&chunk_pos)) {
+                        (true, None) => {
+                            commands.spawn((Chunk, ChunkPos(chunk_pos), ChunkLoadState::Requested));
+                        }
+                        (false, Some(entity)) => commands.entity(entity).despawn(),
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// Converts a chunk position into the position of the region containing it.
+    ///
+    /// Uses floor division, so chunk `-1` belongs to region `-1`, not `0`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use chunky_bevy::prelude::*;
+    ///
+    /// fn example(chunk_manager: Res<ChunkManager>) {
+    ///     // With the default 8x8x8 regions, this returns IVec3(-1, 0, 1)
+    ///     let region_pos = chunk_manager.region_of(IVec3::new(-1, 7, 8));
+    /// }
+    /// ```
+    pub fn region_of(&self, chunk_pos: IVec3) -> IVec3 {
+        chunk_pos.div_euclid(self.region_size.0)
+    }
+
+    /// Converts a chunk position into the position of the coarser chunk
+    /// containing it, `factor` chunks wide on each axis, e.g. for quadtree or
+    /// octree levels of detail layered on the grid.
+    ///
+    /// Uses floor division like [`region_of`](Self::region_of), so with a
+    /// factor of 2 chunks `-2` and `-1` share the parent `-1`. Returns `None`
+    /// if `factor` is not positive.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use chunky_bevy::prelude::*;
+    ///
+    /// fn example(chunk_manager: Res<ChunkManager>) {
+    ///     // Returns Some(IVec3(-1, 1, 0))
+    ///     let parent = chunk_manager.parent_chunk(IVec3::new(-1, 3, 0), 2);
+    /// }
+    /// ```
+    pub fn parent_chunk(&self, pos: IVec3, factor: i32) -> Option<IVec3> {
+        (factor > 0).then(|| pos.div_euclid(IVec3::splat(factor)))
+    }
+
+    /// The `factor³` finer chunk positions whose
+    /// [`parent_chunk`](Self::parent_chunk) is `pos`, with `x` varying fastest,
+    /// then `y`, then `z`.
+    ///
+    /// Returns `None` if `factor` is not positive, or if the children or their
+    /// count don't fit in an `i32`.
+    pub fn child_chunks(&self, pos: IVec3, factor: i32) -> Option<Vec<IVec3>> {
+        if factor <= 0 {
+            return None;
+        }
+        let count = factor.checked_pow(3)?;
+        // Both the first and the last child must fit
+        let min = |axis: i32| {
+            axis.checked_mul(factor)
+                .filter(|min| min.checked_add(factor - 1).is_some())
+        };
+        let min = IVec3::new(min(pos.x)?, min(pos.y)?, min(pos.z)?);
+        let mut children = Vec::with_capacity(count as usize);
+        for z in 0..factor {
+            for y in 0..factor {
+                for x in 0..factor {
+                    children.push(min + ivec3(x, y, z));
+                }
+            }
+        }
+        Some(children)
+    }
+
+    /// Iterates over all loaded chunks inside the specified region
+    pub fn chunks_in_region(
+        &self,
+        region_pos: IVec3,
+    ) -> impl Iterator<Item = (IVec3, Entity)> + '_ {
+        let size = self.region_size.0;
+        let min = region_pos * size;
+        (0..size.x).flat_map(move |x| {
+            (0..size.y).flat_map(move |y| {
+                (0..size.z).filter_map(move |z| {
+                    let chunk_pos = min + ivec3(x, y, z);
+                    self.get_chunk(&chunk_pos).map(|entity| (chunk_pos, entity))
+                })
+            })
+        })
+    }
+
+    /// Returns the number of loaded chunks inside the specified region
+    pub fn region_chunk_count(&self, region_pos: &IVec3) -> usize {
+        self.regions.get(region_pos).copied().unwrap_or(0)
+    }
+
+    /// Checks if any chunk is loaded inside the specified region
+    pub fn is_region_active(&self, region_pos: &IVec3) -> bool {
+        self.regions.contains_key(region_pos)
+    }
+}
+
+/// The product of the non-zero sizes, 0 if all are zero
+fn nonzero_product(sizes: [f32; 3]) -> f32 {
+    let mut sizes = sizes.into_iter().filter(|&size| size != 0.0).peekable();
+    if sizes.peek().is_none() {
+        return 0.0;
+    }
+    sizes.product()
+}
+
+/// The size of a region (a group of chunks) measured in chunks.
+///
+/// Regions give a coarser granularity than single chunks, useful for save
+/// files or interest management. Defaults to 8x8x8 chunks.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// App::new().add_plugins(ChunkyPlugin::default().with_region_size(RegionSize(IVec3::splat(16))));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct RegionSize(pub IVec3);
+
+impl RegionSize {
+    /// 8x8x8 chunks per region
+    pub const DEFAULT: Self = Self(IVec3::splat(8));
+
+    /// The size with every axis at least 1 chunk, as regions of zero or
+    /// negative size can't be divided by
+    pub(crate) fn clamped(self) -> Self {
+        Self(self.0.max(IVec3::ONE))
+    }
+}
+
+impl Default for RegionSize {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How [`ChunkManager::get_chunk_pos`] rounds world positions to chunk positions.
+///
+/// A chunk position `p` corresponds to the world position
+/// `p * chunk_size + world_offset`.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// // Find the chunk origin nearest to an object's center
+/// App::new().add_plugins(ChunkyPlugin::default().with_rounding_mode(RoundingMode::Round));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum RoundingMode {
+    /// The chunk containing the position; boundaries belong to the chunk
+    /// above (default)
+    #[default]
+    Floor,
+    /// The chunk whose origin is nearest; halfway points round away from zero
+    Round,
+    /// The chunk whose origin is the nearest at or above the position
+    Ceil,
+}
+
+/// Where the [`Transform`] of a chunk entity sits within its chunk, see
+/// [`ChunkyPlugin::with_anchor`].
+///
+/// Only the entity's transform moves: chunk positions, the corners returned
+/// by [`ChunkManager::get_world_pos`] and the chunk's bounds stay the same.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// // Chunk entities sit in the middle of their chunk, e.g. to rotate them
+/// App::new().add_plugins(ChunkyPlugin::new().with_anchor(ChunkAnchor::Center));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum ChunkAnchor {
+    /// The origin corner of the chunk, the one with the lowest coordinates
+    /// (default)
+    #[default]
+    Corner,
+    /// The center of the chunk
+    Center,
+}
+
+/// Which chunks block [`ChunkManager::line_of_sight`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum SightBlocker {
+    /// Unloaded chunks are opaque, e.g. to only path through the loaded
+    /// world (default)
+    #[default]
+    Unloaded,
+    /// Loaded chunks are solid, e.g. when chunks stand for obstacles
+    Loaded,
+}
+
+/// The chunk positions along a ray, nearest first, see
+/// [`ChunkManager::ray_chunks`]
+#[derive(Debug, Clone)]
+pub struct ChunkRay {
+    current: IVec3,
+    step: IVec3,
+    /// Distance along the ray to the next boundary on each axis
+    t_max: Vec3,
+    /// Distance along the ray between boundaries on each axis
+    t_delta: Vec3,
+    max_distance: f32,
+    done: bool,
+}
+
+impl Iterator for ChunkRay {
+    type Item = IVec3;
+
+    fn next(&mut self) -> Option<IVec3> {
+        if self.done {
+            return None;
+        }
+        let current = self.current;
+        let axis = self.t_max.min_position();
+        let t = self.t_max[axis];
+        if !t.is_finite() || t > self.max_distance {
+            self.done = true;
+        } else {
+            self.current[axis] += self.step[axis];
+            self.t_max[axis] += self.t_delta[axis];
+        }
+        Some(current)
+    }
+}
+
+/// The vertical axis of the world.
+///
+/// Only convenience helpers, such as [`flat`](Self::flat) and the visualizer's
+/// height coloring, use it; raw chunk coordinates like [`ChunkPos`] and the
+/// [`ChunkLoader`](prelude::ChunkLoader) radius are never swapped.
+///
+/// Inserted as a resource by [`ChunkyPlugin`].
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use chunky_bevy::prelude::*;
+///
+/// fn spawn_player(mut commands: Commands, up_axis: Res<ChunkUpAxis>) {
+///     // Loads a 11x11 area on the ground plane, whichever axis is up
+///     commands.spawn((Transform::default(), ChunkLoader(up_axis.flat(5))));
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "reflect", reflect(Resource))]
+pub enum ChunkUpAxis {
+    /// Y is up, the ground is the X/Z plane (default)
+    #[default]
+    Y,
+    /// Z is up, the ground is the X/Y plane
+    Z,
+}
+
+impl ChunkUpAxis {
+    /// The unit vector pointing up
+    pub fn up(self) -> IVec3 {
+        match self {
+            ChunkUpAxis::Y => IVec3::Y,
+            ChunkUpAxis::Z => IVec3::Z,
+        }
+    }
+
+    /// Returns `radius` on both ground axes and `0` on the up axis
+    pub fn flat(self, radius: i32) -> IVec3 {
+        self.from_ground(IVec2::splat(radius), 0)
+    }
+
+    /// The height of a chunk position along the up axis
+    pub fn height(self, chunk_pos: IVec3) -> i32 {
+        chunk_pos.dot(self.up())
+    }
+
+    /// The position of a chunk on the ground plane, `(x, z)` for Y-up and
+    /// `(x, y)` for Z-up
+    pub fn ground(self, chunk_pos: IVec3) -> IVec2 {
+        match self {
+            ChunkUpAxis::Y => chunk_pos.xz(),
+            ChunkUpAxis::Z => chunk_pos.xy(),
+        }
+    }
+
+    /// Builds a chunk position from a ground plane position and a height
+    pub fn from_ground(self, ground: IVec2, height: i32) -> IVec3 {
+        match self {
+            ChunkUpAxis::Y => ivec3(ground.x, height, ground.y),
+            ChunkUpAxis::Z => ivec3(ground.x, ground.y, height),
+        }
+    }
+}
+
+/// Fired when the first chunk inside a region is loaded.
+#[derive(Message, Debug, Clone)]
+pub struct RegionActivated {
+    /// Position of the region in region coordinates, see
+    /// [`ChunkManager::region_of`]
+    pub region_pos: IVec3,
+}
+
+/// Fired when the last chunk inside a region is unloaded.
+#[derive(Message, Debug, Clone)]
+pub struct RegionDeactivated {
+    /// Position of the region in region coordinates, see
+    /// [`ChunkManager::region_of`]
+    pub region_pos: IVec3,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A headless app with the given plugins, e.g. a ChunkyPlugin, ready to
+    /// update
+    pub(crate) fn app<M>(plugins: impl bevy::app::Plugins<M>) -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            bevy::state::app::StatesPlugin,
+            AssetPlugin::default(),
+            bevy::gizmos::GizmoPlugin,
+        ))
+        .add_plugins(plugins);
+        app.finish();
+        app.cleanup();
+        app
+    }
+
+    /// Runs `f` and returns the warnings it logged on this thread
+    pub(crate) fn logged_warnings(f: impl FnOnce()) -> Vec<String> {
+        use bevy::log::{
+            Level,
+            tracing_subscriber::{fmt, util::SubscriberInitExt},
+        };
+        use std::{
+            io,
+            sync::{Arc, Mutex},
+        };
+
+        struct Captured(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let writer = output.clone();
+        let guard = fmt()
+            .with_writer(move || Captured(writer.clone()))
+            .with_max_level(Level::WARN)
+            .with_ansi(false)
+            .finish()
+            .set_default();
+        f();
+        drop(guard);
+        let output = output.lock().unwrap();
+        String::from_utf8_lossy(&output)
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Updates the app until `done`, giving tasks time to finish
+    #[cfg(feature = "chunk_generation")]
+    pub(crate) fn update_until(app: &mut App, mut done: impl FnMut(&World) -> bool) {
+        for _ in 0..100 {
+            app.update();
+            if done(app.world()) {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        panic!("the app never got there");
+    }
+
+    /// An empty directory for the files of one test
+    #[cfg(feature = "chunk_persistence")]
+    pub(crate) fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("chunky_bevy_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Serializes `value` to JSON and back
+    #[cfg(feature = "serde")]
+    pub(crate) fn json_round_trip<T>(value: &T) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    fn chunk_manager(app: &App) -> &ChunkManager {
+        app.world().resource::<ChunkManager>()
+    }
+
+    #[test]
+    fn the_builder_configures_the_resources() {
+        let plugin = ChunkyPlugin::new()
+            .with_chunk_size(Vec3::splat(5.0))
+            .with_int_chunk_size(IVec3::new(16, 32, 8))
+            .with_region_size(RegionSize(IVec3::splat(4)))
+            .with_capacity(64)
+            .with_rounding_mode(RoundingMode::Round)
+            .with_duplicate_policy(ChunkCollisionPolicy::ReplaceOld)
+            .with_ready_timing(ChunkReadyTiming::NextFrame)
+            .with_up_axis(ChunkUpAxis::Z)
+            .with_anchor(ChunkAnchor::Center)
+            .with_origin(vec3(1.0, 2.0, 3.0));
+        #[cfg(feature = "chunk_loader")]
+        let plugin = plugin.with_loader_settings(chunk_loader::ChunkLoaderSettings {
+            unload_cooldown_frames: 5,
+            pool_size: 3,
+            soft_limit: 100,
+        });
+        #[cfg(feature = "chunk_loader")]
+        let plugin = plugin
+            .with_schedule(PreUpdate)
+            .with_continuous_loading(false);
+        #[cfg(feature = "chunk_visualizer")]
+        let plugin = plugin.with_visualizer_state(false);
+        let app = app(plugin);
+        let world = app.world();
+
+        let chunks = chunk_manager(&app);
+        assert_eq!(chunks.get_size(), vec3(16.0, 32.0, 8.0));
+        assert_eq!(chunks.get_int_size(), Some(IVec3::new(16, 32, 8)));
+        assert_eq!(chunks.get_region_size(), RegionSize(IVec3::splat(4)));
+        assert!(chunks.chunks.capacity() >= 64);
+        assert_eq!(chunks.get_rounding_mode(), RoundingMode::Round);
+        assert_eq!(chunks.get_anchor(), ChunkAnchor::Center);
+        assert_eq!(chunks.get_world_offset(), vec3(1.0, 2.0, 3.0));
+        assert_eq!(
+            *world.resource::<ChunkCollisionPolicy>(),
+            ChunkCollisionPolicy::ReplaceOld
+        );
+        assert_eq!(
+            *world.resource::<ChunkReadyTiming>(),
+            ChunkReadyTiming::NextFrame
+        );
+        assert_eq!(*world.resource::<ChunkUpAxis>(), ChunkUpAxis::Z);
+        #[cfg(feature = "chunk_loader")]
+        assert_eq!(
+            *world.resource::<chunk_loader::ChunkLoaderSettings>(),
+            chunk_loader::ChunkLoaderSettings {
+                unload_cooldown_frames: 5,
+                pool_size: 3,
+                soft_limit: 100,
+            }
+        );
+        #[cfg(feature = "chunk_visualizer")]
+        assert!(world.contains_resource::<chunk_visualizer::ChunkVisualizerEnabled>());
+
+        // Loaders only run in `PreUpdate`, and only when triggered
+        #[cfg(feature = "chunk_loader")]
+        {
+            let mut app = app;
+            let world = app.world_mut();
+            world.spawn((Transform::default(), chunk_loader::ChunkLoader(IVec3::ZERO)));
+            world.run_schedule(PreUpdate);
+            assert!(world.resource::<ChunkManager>().is_empty());
+            world.write_message(chunk_loader::ChunkLoadTrigger);
+            world.run_schedule(Update);
+            assert!(world.resource::<ChunkManager>().is_empty());
+            world.run_schedule(PreUpdate);
+            assert_eq!(world.resource::<ChunkManager>().len(), 1);
+        }
+    }
+
+    #[test]
+    fn a_chunk_without_a_position_registers_at_the_origin() {
+        let mut app = app(ChunkyPlugin::default());
+        let chunk = app.world_mut().spawn(Chunk).id();
+        app.update();
+
+        assert_eq!(
+            app.world().get::<ChunkPos>(chunk).map(|pos| pos.0),
+            Some(IVec3::ZERO)
+        );
+        assert_eq!(chunk_manager(&app).get_chunk(&IVec3::ZERO), Some(chunk));
+        assert_eq!(chunk_manager(&app).len(), 1);
+    }
+
+    #[test]
+    fn unmanaged_chunks_stay_out_of_the_manager() {
+        let mut app = app(ChunkyPlugin::default());
+        let pos = ivec3(2, 0, -1);
+        let managed = app.world_mut().spawn((Chunk, ChunkPos(pos))).id();
+        let warnings = logged_warnings(|| {
+            let ghost = app
+                .world_mut()
+                .spawn((Chunk, ChunkPos(pos), UnmanagedChunk))
+                .id();
+            let lone = app
+                .world_mut()
+                .spawn((Chunk, ChunkPos(IVec3::X * 5), UnmanagedChunk))
+                .id();
+            app.update();
+
+            assert!(!chunk_manager(&app).is_loaded(&(IVec3::X * 5)));
+            assert_eq!(chunk_manager(&app).get_chunk(&pos), Some(managed));
+            assert_eq!(
+                app.world().get::<Transform>(ghost).unwrap().translation,
+                vec3(20.0, 0.0, -10.0)
+            );
+            assert_eq!(
+                app.world().get::<Transform>(lone).unwrap().translation,
+                vec3(50.0, 0.0, 0.0)
+            );
+
+            app.world_mut().despawn(ghost);
+            app.world_mut().despawn(lone);
+        });
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(chunk_manager(&app).get_chunk(&pos), Some(managed));
+        assert_eq!(chunk_manager(&app).len(), 1);
+    }
+
+    #[test]
+    fn chunks_on_different_layers_coexist() {
+        let mut app = app(ChunkyPlugin::default());
+        let overworld = app.world_mut().spawn((Chunk, ChunkPos(IVec3::ONE))).id();
+        let nether = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(IVec3::ONE), ChunkLayer(-1)))
+            .id();
+        app.update();
+
+        let chunks = chunk_manager(&app);
+        assert_eq!(chunks.get_chunk(&IVec3::ONE), Some(overworld));
+        assert_eq!(chunks.get_chunk_in_layer(&IVec3::ONE, -1), Some(nether));
+        assert_eq!(chunks.len(), 2);
+        assert!(app.world().get_entity(nether).is_ok());
+
+        app.world_mut().despawn(nether);
+        let chunks = chunk_manager(&app);
+        assert_eq!(chunks.get_chunk_in_layer(&IVec3::ONE, -1), None);
+        assert_eq!(chunks.get_chunk(&IVec3::ONE), Some(overworld));
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[test]
+    fn changing_the_layer_moves_the_chunk() {
+        let mut app = app(ChunkyPlugin::default());
+        let chunk = app.world_mut().spawn((Chunk, ChunkPos(IVec3::ZERO))).id();
+        app.update();
+
+        app.world_mut().entity_mut(chunk).insert(ChunkLayer(2));
+        app.world_mut().flush();
+        assert_eq!(chunk_manager(&app).get_chunk(&IVec3::ZERO), None);
+        assert_eq!(
+            chunk_manager(&app).get_chunk_in_layer(&IVec3::ZERO, 2),
+            Some(chunk)
+        );
+
+        app.world_mut().entity_mut(chunk).insert(ChunkLayer(3));
+        app.world_mut().flush();
+        assert_eq!(
+            chunk_manager(&app).get_chunk_in_layer(&IVec3::ZERO, 2),
+            None
+        );
+        assert_eq!(
+            chunk_manager(&app).get_chunk_in_layer(&IVec3::ZERO, 3),
+            Some(chunk)
+        );
+
+        app.world_mut().entity_mut(chunk).remove::<ChunkLayer>();
+        app.world_mut().flush();
+        assert_eq!(
+            chunk_manager(&app).get_chunk_in_layer(&IVec3::ZERO, 3),
+            None
+        );
+        assert_eq!(chunk_manager(&app).get_chunk(&IVec3::ZERO), Some(chunk));
+        assert_eq!(chunk_manager(&app).len(), 1);
+    }
+
+    #[cfg(feature = "chunk_loader")]
+    #[test]
+    fn a_loader_on_a_region_border_activates_both_regions() {
+        let mut app = app(ChunkyPlugin::default().with_region_size(RegionSize(IVec3::splat(4))));
+        // Chunks -1 to 1 along x straddle the border of regions -1 and 0
+        app.world_mut().spawn((
+            Transform::default(),
+            chunk_loader::ChunkLoader(IVec3::new(1, 0, 0)),
+        ));
+        app.update();
+
+        let mut activated: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Messages<RegionActivated>>()
+            .drain()
+            .map(|activated| activated.region_pos)
+            .collect();
+        activated.sort_by_key(|region_pos| region_pos.x);
+        assert_eq!(activated, [IVec3::new(-1, 0, 0), IVec3::ZERO]);
+    }
+
+    #[cfg(feature = "chunk_loader")]
+    #[test]
+    fn only_registering_chunks_marks_the_manager_changed() {
+        #[derive(Resource, Default)]
+        struct ChangedFrames(Vec<bool>);
+
+        let mut app = app(ChunkyPlugin::default());
+        app.init_resource::<ChangedFrames>().add_systems(
+            Update,
+            (|chunk_manager: Res<ChunkManager>, mut frames: ResMut<ChangedFrames>| {
+                frames.0.push(chunk_manager.is_changed());
+            })
+            .after(ChunkySystems::Load),
+        );
+        // Everything counts as changed in the first run of a system
+        app.update();
+        app.world_mut().resource_mut::<ChangedFrames>().0.clear();
+
+        app.world_mut()
+            .spawn((Transform::default(), chunk_loader::ChunkLoader(IVec3::ZERO)));
+        app.update();
+        app.update();
+        // Rejected by the default DespawnNew policy at the end of the frame
+        app.world_mut().spawn((Chunk, ChunkPos(IVec3::ZERO)));
+        app.update();
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<ChangedFrames>().0,
+            [true, false, false, false]
+        );
+        assert_eq!(chunk_manager(&app).len(), 1);
+    }
+
+    #[test]
+    fn duplicate_spawns_log_one_warning_per_frame() {
+        let mut app =
+            app(ChunkyPlugin::default().with_duplicate_policy(ChunkCollisionPolicy::Warn));
+        for _ in 0..101 {
+            app.world_mut().spawn((Chunk, ChunkPos(IVec3::ONE)));
+        }
+        // Run on this thread, where the warnings are captured
+        let world = app.world_mut();
+        let warnings = logged_warnings(|| {
+            world.run_system_cached(warn_chunk_collisions).unwrap();
+            world.run_system_cached(warn_chunk_collisions).unwrap();
+        });
+
+        let [warning] = &warnings[..] else {
+            panic!("expected one warning, got {warnings:?}");
+        };
+        assert!(
+            warning.contains("100 new chunks were not spawned"),
+            "{warning}"
+        );
+        assert_eq!(chunk_manager(&app).len(), 1);
+    }
+
+    #[test]
+    fn each_collision_policy_resolves_a_duplicate_spawn() {
+        for (policy, old_kept, new_kept, new_registered) in [
+            (ChunkCollisionPolicy::Warn, true, true, false),
+            (ChunkCollisionPolicy::ReplaceOld, false, true, true),
+            (ChunkCollisionPolicy::DespawnNew, true, false, false),
+        ] {
+            let mut app = app(ChunkyPlugin::default().with_duplicate_policy(policy));
+            let old = app.world_mut().spawn((Chunk, ChunkPos(IVec3::ONE))).id();
+            let new = app.world_mut().spawn((Chunk, ChunkPos(IVec3::ONE))).id();
+            app.update();
+
+            let world = app.world();
+            assert_eq!(world.get_entity(old).is_ok(), old_kept, "{policy:?}");
+            assert_eq!(world.get_entity(new).is_ok(), new_kept, "{policy:?}");
+            let registered = if new_registered { new } else { old };
+            assert_eq!(
+                chunk_manager(&app).get_chunk(&IVec3::ONE),
+                Some(registered),
+                "{policy:?}"
+            );
+            assert_eq!(chunk_manager(&app).len(), 1, "{policy:?}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "collides with existing chunk")]
+    fn the_panic_policy_panics_on_a_duplicate_spawn() {
+        let mut app =
+            app(ChunkyPlugin::default().with_duplicate_policy(ChunkCollisionPolicy::Panic));
+        app.world_mut().spawn((Chunk, ChunkPos(IVec3::ONE)));
+        app.world_mut().spawn((Chunk, ChunkPos(IVec3::ONE)));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn only_chunks_registered_without_their_entity_are_leaks() {
+        let mut app = app(ChunkyPlugin::default());
+        app.world_mut().spawn((Chunk, ChunkPos(IVec3::ZERO)));
+        let gone = app.world_mut().spawn_empty().id();
+        app.world_mut().despawn(gone);
+        let world = app.world_mut();
+        // Run on this thread, where the warnings are captured
+        let clean = logged_warnings(|| world.run_system_cached(warn_leaked_chunks).unwrap());
+        assert_eq!(clean, Vec::<String>::new());
+
+        world
+            .resource_mut::<ChunkManager>()
+            .insert_in_layer(IVec3::X, 3, gone);
+        let leaked = logged_warnings(|| world.run_system_cached(warn_leaked_chunks).unwrap());
+        let [warning] = &leaked[..] else {
+            panic!("expected one warning, got {leaked:?}");
+        };
+        assert!(
+            warning.contains("without their entity on exit: 1, first at pos:[1, 0, 0]"),
+            "{warning}"
+        );
+    }
+
+    #[test]
+    fn a_box_straddling_chunk_boundaries_finds_every_chunk_it_overlaps() {
+        let mut chunk_manager = ChunkManager::new(Vec3::splat(10.0));
+        let entities: HashMap<_, _> = [
+            ivec3(0, 0, 0),
+            ivec3(1, 0, 0),
+            ivec3(0, 0, 1),
+            ivec3(1, 0, 1),
+            ivec3(-1, 0, 0),
+            ivec3(2, 0, 0),
+            ivec3(0, 1, 0),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, pos)| {
+            let entity = Entity::from_raw_u32(i as u32 + 1).unwrap();
+            chunk_manager.insert(pos, entity);
+            (pos, entity)
+        })
+        .collect();
+        let found = |min: Vec3, max: Vec3| {
+            let mut found = chunk_manager.entities_in_world_aabb(min, max);
+            found.sort();
+            found
+        };
+        let expected = |positions: &[IVec3]| {
+            let mut expected: Vec<_> = positions.iter().map(|pos| entities[pos]).collect();
+            expected.sort();
+            expected
+        };
+
+        let straddling = [
+            ivec3(0, 0, 0),
+            ivec3(1, 0, 0),
+            ivec3(0, 0, 1),
+            ivec3(1, 0, 1),
+        ];
+        assert_eq!(
+            found(vec3(5.0, 1.0, 5.0), vec3(15.0, 2.0, 12.0)),
+            expected(&straddling)
+        );
+        // Corners in any order
+        assert_eq!(
+            found(vec3(15.0, 2.0, 5.0), vec3(5.0, 1.0, 12.0)),
+            expected(&straddling)
+        );
+        assert_eq!(
+            found(vec3(-0.5, 5.0, 5.0), vec3(0.5, 5.0, 5.0)),
+            expected(&[ivec3(-1, 0, 0), ivec3(0, 0, 0)])
+        );
+        // Larger than the loaded chunks
+        assert_eq!(
+            found(Vec3::splat(-1e9), Vec3::splat(1e9)).len(),
+            entities.len()
+        );
+    }
+
+    #[test]
+    fn flat_axes_span_the_whole_box() {
+        let mut chunk_manager = ChunkManager::new(vec3(10.0, 10.0, 0.0));
+        let entity = Entity::from_raw_u32(1).unwrap();
+        chunk_manager.insert(ivec3(-1, 2, 0), entity);
+        assert_eq!(
+            chunk_manager.entities_in_world_aabb(vec3(-5.0, 25.0, -100.0), vec3(0.0, 25.0, 100.0)),
+            [entity]
+        );
+    }
+
+    #[test]
+    fn snapping_rounds_down_to_the_containing_chunk() {
+        let mut chunk_manager = ChunkManager::new(vec3(16.0, 4.0, 10.0));
+        assert_eq!(
+            chunk_manager.snap_to_chunk_origin(vec3(-0.5, -4.0, -25.0)),
+            vec3(-16.0, -4.0, -30.0)
+        );
+        assert_eq!(
+            chunk_manager.snap_to_chunk_center(vec3(-0.5, -4.0, -25.0)),
+            vec3(-8.0, -2.0, -25.0)
+        );
+        assert_eq!(
+            chunk_manager.snap_to_chunk_origin(vec3(33.0, 3.9, 10.0)),
+            vec3(32.0, 0.0, 10.0)
+        );
+
+        chunk_manager.set_world_offset(vec3(1.0, 1.0, 1.0));
+        assert_eq!(
+            chunk_manager.snap_to_chunk_origin(vec3(-15.0, 0.0, 0.0)),
+            vec3(-15.0, -3.0, -9.0)
+        );
+    }
+
+    #[test]
+    fn snapping_keeps_flat_axes() {
+        let chunk_manager = ChunkManager::new(vec3(10.0, 10.0, 0.0));
+        assert_eq!(
+            chunk_manager.snap_to_chunk_origin(vec3(-12.0, 7.0, 3.0)),
+            vec3(-20.0, 0.0, 3.0)
+        );
+        assert_eq!(
+            chunk_manager.snap_to_chunk_center(vec3(-12.0, 7.0, 3.0)),
+            vec3(-15.0, 5.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn each_rounding_mode_rounds_at_chunk_boundaries() {
+        let cases = [
+            (
+                RoundingMode::Floor,
+                [(10, 1), (9, 0), (15, 1), (-10, -1), (-15, -2)],
+            ),
+            (
+                RoundingMode::Round,
+                [(10, 1), (14, 1), (15, 2), (-14, -1), (-15, -2)],
+            ),
+            (
+                RoundingMode::Ceil,
+                [(10, 1), (11, 2), (9, 1), (-10, -1), (-15, -1)],
+            ),
+        ];
+        for (rounding_mode, positions) in cases {
+            let float = ChunkManager::new(Vec3::splat(10.0)).with_rounding_mode(rounding_mode);
+            let int = ChunkManager::new_int(IVec3::splat(10)).with_rounding_mode(rounding_mode);
+            for (x, chunk_x) in positions {
+                let expected = IVec3::new(chunk_x, 0, 0);
+                assert_eq!(
+                    float.get_chunk_pos(&vec3(x as f32, 0.0, 0.0)),
+                    expected,
+                    "{rounding_mode:?} at {x}"
+                );
+                assert_eq!(
+                    int.get_chunk_pos_int(&IVec3::new(x, 0, 0)),
+                    expected,
+                    "{rounding_mode:?} at {x}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parents_and_children_round_down_at_factor_2() {
+        let chunk_manager = ChunkManager::new(Vec3::ONE);
+        assert_eq!(
+            chunk_manager.parent_chunk(ivec3(-1, -2, 3), 2),
+            Some(ivec3(-1, -1, 1))
+        );
+        assert_eq!(
+            chunk_manager.parent_chunk(ivec3(-3, 0, 1), 2),
+            Some(ivec3(-2, 0, 0))
+        );
+
+        let children = chunk_manager.child_chunks(ivec3(-1, 0, -2), 2).unwrap();
+        assert_eq!(children.len(), 8);
+        assert_eq!(children[0], ivec3(-2, 0, -4));
+        assert_eq!(children[7], ivec3(-1, 1, -3));
+        assert!(
+            children
+                .iter()
+                .all(|&child| { chunk_manager.parent_chunk(child, 2) == Some(ivec3(-1, 0, -2)) })
+        );
+    }
+
+    #[test]
+    fn invalid_factors_and_overflowing_children_are_none() {
+        let chunk_manager = ChunkManager::new(Vec3::ONE);
+        assert_eq!(chunk_manager.parent_chunk(IVec3::ONE, 0), None);
+        assert_eq!(chunk_manager.child_chunks(IVec3::ONE, -2), None);
+        assert_eq!(chunk_manager.child_chunks(IVec3::ONE, 2000), None);
+        assert_eq!(chunk_manager.child_chunks(IVec3::MAX, 2), None);
+        assert_eq!(chunk_manager.child_chunks(IVec3::MIN, 2), None);
+        assert_eq!(
+            chunk_manager.child_chunks(IVec3::splat(i32::MAX / 2), 2),
+            Some(Vec::from_iter((0..8).map(|i| {
+                IVec3::splat(i32::MAX - 1) + ivec3(i & 1, i >> 1 & 1, i >> 2 & 1)
+            })))
+        );
+    }
+
+    #[test]
+    fn diagonal_lines_and_rays_are_connected_chains() {
+        let mut app = app(ChunkyPlugin::default());
+        let (from, to) = (IVec3::new(-3, 2, 1), IVec3::new(4, -2, 6));
+        let line = helpers::spawn_chunks_line(&mut app.world_mut().commands(), from, to);
+        app.world_mut().flush();
+        let line: Vec<_> = line
+            .into_iter()
+            .map(|chunk| app.world().get::<ChunkPos>(chunk).unwrap().0)
+            .collect();
+        // One chunk per step along the longest axis, at most one step on the others
+        assert_eq!(line.len(), 8);
+        assert_eq!((line[0], line[7]), (from, to));
+        assert!(line.windows(2).all(|pair| {
+            let step = pair[1] - pair[0];
+            step.x == 1 && step.abs().max_element() == 1
+        }));
+        let single = helpers::spawn_chunks_line(&mut app.world_mut().commands(), to, to);
+        assert_eq!(single.len(), 1);
+
+        // Rays cross one chunk face at a time
+        let ray = Ray3d::new(Vec3::splat(5.0), Dir3::new(vec3(1.0, -2.0, 3.0)).unwrap());
+        let chunks: Vec<_> = chunk_manager(&app).ray_chunks(ray, 100.0).collect();
+        assert_eq!(chunks.first(), Some(&IVec3::ZERO));
+        assert!(chunks.windows(2).all(|pair| {
+            let step = pair[1] - pair[0];
+            step.abs().element_sum() == 1 && step.cmple(IVec3::new(1, 0, 1)).all()
+        }));
+        assert!(chunks.len() > 10);
+    }
+
+    #[test]
+    fn finding_a_chunk_skips_unmanaged_rejected_and_layered_ones() {
+        let mut app = app(ChunkyPlugin::default());
+        let pos = IVec3::new(1, 2, 3);
+        // Spawned first, so a scan in spawn order would meet them first
+        let ghost = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(pos), UnmanagedChunk))
+            .id();
+        let layered = app
+            .world_mut()
+            .spawn((Chunk, ChunkPos(pos), ChunkLayer(1)))
+            .id();
+        let chunk = app.world_mut().spawn((Chunk, ChunkPos(pos))).id();
+        // Rejected duplicates are only despawned on the next update
+        let rejected = app.world_mut().spawn((Chunk, ChunkPos(pos))).id();
+        app.world_mut().flush();
+
+        let world = app.world();
+        assert!(world.entity(rejected).contains::<RejectedChunk>());
+        assert!(world.get_entity(ghost).is_ok());
+        assert_eq!(helpers::find_chunk_entity(world, pos), Some(chunk));
+        assert_eq!(
+            helpers::find_chunk_entity_in_layer(world, pos, 1),
+            Some(layered)
+        );
+        assert_eq!(helpers::find_chunk_entity_in_layer(world, pos, 2), None);
+        assert_eq!(helpers::find_chunk_entity(world, IVec3::ZERO), None);
+    }
+
+    #[test]
+    fn rects_over_the_limit_spawn_nothing() {
+        let mut app = app(ChunkyPlugin::default());
+        let (from, to) = (ivec3(2, 1, 3), IVec3::ZERO);
+        let result = helpers::try_spawn_chunks_rect(&mut app.world_mut().commands(), from, to, 23);
+        app.world_mut().flush();
+        assert_eq!(
+            result,
+            Err(helpers::TooManyChunks {
+                count: 24,
+                max_chunks: 23
+            })
+        );
+        assert_eq!(chunk_manager(&app).len(), 0);
+
+        let result = helpers::try_spawn_chunks_rect(&mut app.world_mut().commands(), from, to, 24);
+        app.world_mut().flush();
+        assert_eq!(result, Ok(24));
+        assert_eq!(chunk_manager(&app).len(), 24);
+    }
+
+    #[test]
+    fn missing_chunks_block_the_line_of_sight() {
+        let mut chunk_manager = ChunkManager::new(Vec3::ONE);
+        for x in 0..=5 {
+            chunk_manager.insert(
+                IVec3::new(x, 0, 0),
+                Entity::from_raw_u32(x as u32 + 1).unwrap(),
+            );
+        }
+        let (from, to) = (IVec3::ZERO, IVec3::new(5, 0, 0));
+        assert!(chunk_manager.line_of_sight(from, to, SightBlocker::Unloaded));
+        assert!(!chunk_manager.line_of_sight(from, to, SightBlocker::Loaded));
+
+        chunk_manager.remove(&IVec3::new(3, 0, 0));
+        assert!(!chunk_manager.line_of_sight(from, to, SightBlocker::Unloaded));
+        assert!(chunk_manager.line_of_sight(from, IVec3::new(2, 0, 0), SightBlocker::Unloaded));
+        // The ends are never checked
+        assert!(chunk_manager.line_of_sight(from, IVec3::X, SightBlocker::Loaded));
+        assert!(chunk_manager.line_of_sight(from, IVec3::new(3, 0, 0), SightBlocker::Unloaded));
+    }
+
+    #[test]
+    fn the_extent_includes_the_outermost_chunks() {
+        let mut chunk_manager = ChunkManager::new(Vec3::ONE);
+        assert_eq!(chunk_manager.extent(), None);
+
+        let positions = [ivec3(-3, 1, 0), ivec3(2, -4, 5), ivec3(0, 0, -1)];
+        for (index, pos) in positions.into_iter().enumerate() {
+            chunk_manager.insert(pos, Entity::from_raw_u32(index as u32 + 1).unwrap());
+        }
+        assert_eq!(
+            chunk_manager.extent(),
+            Some((ivec3(-3, -4, -1), ivec3(2, 1, 5)))
+        );
+
+        chunk_manager.remove_many([ivec3(-3, 1, 0), ivec3(2, -4, 5)]);
+        assert_eq!(
+            chunk_manager.extent(),
+            Some((ivec3(0, 0, -1), ivec3(0, 0, -1)))
+        );
+    }
+
+    #[test]
+    fn the_world_aabb_spans_the_outermost_chunks() {
+        let mut chunk_manager = ChunkManager::new(vec3(16.0, 4.0, 10.0));
+        assert_eq!(chunk_manager.world_aabb(), None);
+
+        chunk_manager.insert(ivec3(-1, 2, 0), Entity::from_raw_u32(1).unwrap());
+        chunk_manager.insert(ivec3(1, -3, 4), Entity::from_raw_u32(2).unwrap());
+        assert_eq!(
+            chunk_manager.world_aabb(),
+            Some((vec3(-16.0, -12.0, 0.0), vec3(32.0, 12.0, 50.0)))
+        );
+    }
+
+    #[test]
+    fn only_the_length_counts_other_layers() {
+        let mut chunk_manager = ChunkManager::new(Vec3::ONE);
+        for (index, pos) in [IVec3::ZERO, IVec3::X].into_iter().enumerate() {
+            chunk_manager.insert(pos, Entity::from_raw_u32(index as u32 + 1).unwrap());
+        }
+        for (index, pos) in [IVec3::ZERO, IVec3::splat(-9), IVec3::splat(9)]
+            .into_iter()
+            .enumerate()
+        {
+            chunk_manager.insert_in_layer(pos, 2, Entity::from_raw_u32(index as u32 + 10).unwrap());
+        }
+
+        assert_eq!(chunk_manager.len(), 5);
+        assert_eq!(chunk_manager.iter_all_layers().count(), 5);
+        let default_layer = chunk_manager.iter_layer(0).count();
+        assert_eq!(default_layer, 2);
+        assert_eq!(chunk_manager.iter().count(), default_layer);
+        assert_eq!(chunk_manager.occupancy_stats().total, default_layer);
+        assert_eq!(
+            chunk_manager.occupancy_stats().bounding_volume,
+            2,
+            "layered chunks widened the box"
+        );
+        assert_eq!(chunk_manager.extent(), Some((IVec3::ZERO, IVec3::X)));
+        assert_eq!(
+            chunk_manager
+                .entities_in_world_aabb(Vec3::splat(-100.0), Vec3::splat(100.0))
+                .len(),
+            default_layer
+        );
+    }
+
+    #[test]
+    fn the_fill_ratio_compares_chunks_to_their_bounding_box() {
+        let mut chunk_manager = ChunkManager::new(Vec3::ONE);
+        assert_eq!(
+            chunk_manager.occupancy_stats(),
+            ChunkOccupancy {
+                total: 0,
+                bounding_volume: 0,
+                fill_ratio: 0.0
+            }
+        );
+
+        for index in 0..8 {
+            let pos = ivec3(index & 1, index >> 1 & 1, index >> 2);
+            chunk_manager.insert(pos, Entity::from_raw_u32(index as u32 + 1).unwrap());
+        }
+        assert_eq!(
+            chunk_manager.occupancy_stats(),
+            ChunkOccupancy {
+                total: 8,
+                bounding_volume: 8,
+                fill_ratio: 1.0
+            }
+        );
+
+        // Two far apart corners span a box of 10x10x10
+        chunk_manager.insert(IVec3::splat(9), Entity::from_raw_u32(9).unwrap());
+        chunk_manager.remove_many((1..8).map(|index| ivec3(index & 1, index >> 1 & 1, index >> 2)));
+        assert_eq!(
+            chunk_manager.occupancy_stats(),
+            ChunkOccupancy {
+                total: 2,
+                bounding_volume: 1000,
+                fill_ratio: 0.002
+            }
+        );
+    }
+
+    #[test]
+    fn a_checkerboard_round_trips_through_the_occupancy_bitmask() {
+        // 27 chunks, so the last byte is only partly used
+        let (min, max) = (IVec3::NEG_ONE, IVec3::ONE);
+        let region: Vec<_> = (0..27)
+            .map(|index| min + ivec3(index % 3, index / 3 % 3, index / 9))
+            .collect();
+        let checkered = |pos: &IVec3| pos.element_sum().rem_euclid(2) == 0;
+
+        let mut checkerboard = ChunkManager::new(Vec3::ONE);
+        for (index, pos) in region.iter().filter(|pos| checkered(pos)).enumerate() {
+            checkerboard.insert(*pos, Entity::from_raw_u32(index as u32 + 1).unwrap());
+        }
+        let (size, bits) = checkerboard.occupancy_bitmask(max, min);
+        assert_eq!(size, IVec3::splat(3));
+        assert_eq!(bits.len(), 4);
+        for (i, pos) in region.iter().enumerate() {
+            assert_eq!(bits[i / 8] & (1 << (i % 8)) != 0, checkered(pos));
+        }
+        assert_eq!(bits[3] >> 3, 0);
+
+        // Applied over a full region, only the checkerboard stays loaded
+        let mut app = app(ChunkyPlugin::default());
+        for &pos in &region {
+            app.world_mut()
+                .spawn((Chunk, ChunkPos(pos), ChunkLoadState::Ready));
+        }
+        app.world_mut()
+            .resource_scope(|world, chunk_manager: Mut<ChunkManager>| {
+                chunk_manager.apply_occupancy_bitmask(&mut world.commands(), min, max, &bits);
+            });
+        app.world_mut().flush();
+        let chunk_manager = chunk_manager(&app);
+        assert_eq!(chunk_manager.len(), 13);
+        assert!(
+            region
+                .iter()
+                .all(|pos| chunk_manager.is_loaded(pos) == checkered(pos))
+        );
+        assert_eq!(chunk_manager.occupancy_bitmask(min, max), (size, bits));
+    }
+
+    #[test]
+    fn bulk_inserts_fill_the_map_and_regions() {
+        let mut chunk_manager =
+            ChunkManager::new(Vec3::ONE).with_region_size(RegionSize(IVec3::splat(4)));
+        let chunks: Vec<_> = (0..6)
+            .map(|x| (ivec3(x, 0, 0), Entity::from_raw_u32(x as u32 + 1).unwrap()))
+            .collect();
+        chunk_manager.insert_many(chunks.iter().copied());
+
+        assert_eq!(chunk_manager.len(), 6);
+        for &(pos, entity) in &chunks {
+            assert_eq!(chunk_manager.get_chunk(&pos), Some(entity));
+        }
+        assert_eq!(chunk_manager.region_chunk_count(&IVec3::ZERO), 4);
+        assert_eq!(chunk_manager.region_chunk_count(&IVec3::X), 2);
+
+        // Re-inserting a position replaces its entity without counting it twice
+        let replacement = Entity::from_raw_u32(100).unwrap();
+        chunk_manager.insert_many([(IVec3::ZERO, replacement)]);
+        assert_eq!(chunk_manager.get_chunk(&IVec3::ZERO), Some(replacement));
+        assert_eq!(chunk_manager.region_chunk_count(&IVec3::ZERO), 4);
+
+        chunk_manager.remove_many(chunks.iter().map(|&(pos, _)| pos));
+        assert!(chunk_manager.is_empty());
+        assert!(!chunk_manager.is_region_active(&IVec3::X));
+    }
+
+    #[test]
+    fn volumes_and_face_areas_follow_the_chunk_size() {
+        let cube = ChunkManager::new(Vec3::splat(16.0));
+        assert_eq!(cube.chunk_volume(), 4096.0);
+        for axis in NEIGHBORS_6 {
+            assert_eq!(cube.chunk_face_area(axis), 256.0);
+        }
+
+        let slab = ChunkManager::new(vec3(16.0, 32.0, 8.0));
+        assert_eq!(slab.chunk_volume(), 4096.0);
+        assert_eq!(slab.chunk_face_area(IVec3::X), 256.0);
+        assert_eq!(slab.chunk_face_area(IVec3::NEG_Y), 128.0);
+        assert_eq!(slab.chunk_face_area(IVec3::Z), 512.0);
+
+        let flat = ChunkManager::new(vec3(32.0, 0.0, 16.0));
+        assert_eq!(flat.chunk_volume(), 512.0);
+        assert_eq!(flat.chunk_face_area(IVec3::Y), 512.0);
+        assert_eq!(flat.chunk_face_area(IVec3::X), 16.0);
+    }
+
+    #[test]
+    fn integer_chunk_sizes_stay_exact_at_large_coordinates() {
+        let int = ChunkManager::new_int(IVec3::splat(3));
+        let float = ChunkManager::new(Vec3::splat(3.0));
+        let x = 100_000_001;
+        // f32 can't hold x exactly, so the float manager picks the wrong chunk
+        assert_ne!(float.get_chunk_pos_int(&IVec3::new(x, 0, 0)).x, 33_333_333);
+        assert_eq!(
+            int.get_chunk_pos_int(&IVec3::new(x, -x, i32::MAX)),
+            IVec3::new(33_333_333, -33_333_334, 715_827_882)
+        );
+        assert_eq!(
+            int.get_chunk_pos_int(&IVec3::new(x - 2, x - 3, i32::MIN)),
+            IVec3::new(33_333_333, 33_333_332, -715_827_883)
+        );
+    }
+
+    #[test]
+    fn drain_empties_every_layer() {
+        let mut chunk_manager = ChunkManager::new(Vec3::ONE);
+        let entity = Entity::from_raw_u32(1).unwrap();
+        chunk_manager.insert_in_layer(IVec3::ZERO, 0, entity);
+        chunk_manager.insert_in_layer(IVec3::ZERO, 5, entity);
+        assert_eq!(chunk_manager.len(), 2);
+
+        let mut drained: Vec<_> = chunk_manager.drain().collect();
+        drained.sort_by_key(|(_, layer, _)| *layer);
+        assert_eq!(
+            drained,
+            [(IVec3::ZERO, 0, entity), (IVec3::ZERO, 5, entity)]
+        );
+        assert!(chunk_manager.is_empty());
+        assert_eq!(chunk_manager.get_chunk_in_layer(&IVec3::ZERO, 5), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn chunk_pos_round_trips_through_json() {
+        let chunk_pos = ChunkPos(IVec3::new(-3, 7, i32::MAX));
+        assert_eq!(json_round_trip(&chunk_pos).0, chunk_pos.0);
     }
 }
@@ -0,0 +1,208 @@
+//! A small noise terrain generator showing the generation pipeline end to end.
+//!
+//! [`NoiseTerrainPlugin`] generates a [`HeightField`] for every chunk with
+//! [`NoiseHeightGenerator`], seeded from the
+//! [`WorldSeed`](crate::prelude::WorldSeed), and spawns a colored box for each
+//! chunk once it becomes `Ready`.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::prelude::*;
+//!
+//! App::new()
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugins(ChunkyPlugin::default())
+//!     .add_plugins(NoiseTerrainPlugin::new(42));
+//! ```
+
+use bevy::prelude::*;
+
+use crate::{
+    ChunkManager, ChunkySystems, NewlyReadyChunks,
+    chunk_generation::{
+        ChunkData, ChunkGenerationFailed, ChunkGenerationPlugin, ChunkGenerator, GenerationContext,
+        GenerationResult, chunk_seed,
+    },
+};
+
+/// Generates chunks with [`NoiseHeightGenerator`] and shows them as boxes.
+pub struct NoiseTerrainPlugin {
+    generator: NoiseHeightGenerator,
+    seed: u64,
+}
+
+impl NoiseTerrainPlugin {
+    /// Creates the plugin with the default generator and the given world seed
+    pub fn new(seed: u64) -> Self {
+        Self {
+            generator: NoiseHeightGenerator::default(),
+            seed,
+        }
+    }
+
+    /// Replaces the default generator
+    pub fn with_generator(mut self, generator: NoiseHeightGenerator) -> Self {
+        self.generator = generator;
+        self
+    }
+}
+
+impl Plugin for NoiseTerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ChunkGenerationPlugin::new(self.generator.clone()).with_seed(self.seed))
+            .insert_resource(TerrainAmplitude(self.generator.amplitude))
+            .add_systems(
+                Update,
+                (
+                    spawn_terrain_boxes.in_set(ChunkySystems::PostGenerate),
+                    log_failed_chunks,
+                ),
+            );
+    }
+}
+
+/// Heights sampled on a regular grid over a chunk's XZ footprint.
+///
+/// The samples include both edges, so neighboring chunks share their border
+/// heights.
+#[derive(Debug, Clone)]
+pub struct HeightField {
+    /// Number of samples along each axis
+    pub resolution: usize,
+    /// Heights in world units, row by row along X
+    pub heights: Vec<f32>,
+}
+
+impl HeightField {
+    /// Returns the height at sample `(x, z)`
+    pub fn get(&self, x: usize, z: usize) -> f32 {
+        self.heights[z * self.resolution + x]
+    }
+
+    /// Returns the average of all heights
+    pub fn average(&self) -> f32 {
+        self.heights.iter().sum::<f32>() / self.heights.len().max(1) as f32
+    }
+}
+
+/// Fractal value noise height generator.
+///
+/// Heights lie between `0` and `amplitude`. The same world seed always
+/// produces the same terrain.
+#[derive(Debug, Clone)]
+pub struct NoiseHeightGenerator {
+    /// Number of samples along each axis of a chunk, at least 2
+    pub resolution: usize,
+    /// Noise features per world unit
+    pub frequency: f32,
+    /// Maximum height in world units
+    pub amplitude: f32,
+    /// Number of noise layers, each with double the frequency of the last
+    pub octaves: u32,
+}
+
+impl Default for NoiseHeightGenerator {
+    fn default() -> Self {
+        Self {
+            resolution: 9,
+            frequency: 0.02,
+            amplitude: 20.0,
+            octaves: 4,
+        }
+    }
+}
+
+impl NoiseHeightGenerator {
+    /// Samples the height at a world XZ position
+    pub fn height_at(&self, seed: u64, pos: Vec2) -> f32 {
+        let mut total = 0.0;
+        let mut weight = 1.0;
+        let mut weights = 0.0;
+        let mut frequency = self.frequency;
+        for octave in 0..self.octaves.max(1) {
+            total += value_noise(seed.wrapping_add(octave as u64), pos * frequency) * weight;
+            weights += weight;
+            weight *= 0.5;
+            frequency *= 2.0;
+        }
+        total / weights * self.amplitude
+    }
+}
+
+impl ChunkGenerator for NoiseHeightGenerator {
+    type Output = HeightField;
+
+    fn generate(&self, _pos: IVec3, ctx: GenerationContext) -> GenerationResult<HeightField> {
+        let resolution = self.resolution.max(2);
+        let step = ctx.chunk_size.xz() / (resolution - 1) as f32;
+
+        let mut heights = Vec::with_capacity(resolution * resolution);
+        for z in 0..resolution {
+            for x in 0..resolution {
+                let pos = ctx.origin.xz() + vec2(x as f32, z as f32) * step;
+                heights.push(self.height_at(ctx.seed, pos));
+            }
+        }
+        Ok(HeightField {
+            resolution,
+            heights,
+        })
+    }
+}
+
+/// Smoothly interpolated random values on an integer lattice, in `[0, 1)`
+fn value_noise(seed: u64, pos: Vec2) -> f32 {
+    let cell = pos.floor();
+    let t = pos - cell;
+    let t = t * t * (3.0 - 2.0 * t);
+    let cell = cell.as_ivec2();
+
+    let lattice = |x: i32, z: i32| {
+        let hash = chunk_seed(seed, ivec3(cell.x + x, 0, cell.y + z));
+        (hash >> 40) as f32 / (1u64 << 24) as f32
+    };
+    let bottom = lattice(0, 0).lerp(lattice(1, 0), t.x);
+    let top = lattice(0, 1).lerp(lattice(1, 1), t.x);
+    bottom.lerp(top, t.y)
+}
+
+/// The generator's amplitude, used to color the boxes
+#[derive(Resource)]
+struct TerrainAmplitude(f32);
+
+/// Spawns a box as tall as the average height of every newly ready chunk
+fn spawn_terrain_boxes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut unit_box: Local<Option<Handle<Mesh>>>,
+    chunk_manager: Res<ChunkManager>,
+    amplitude: Res<TerrainAmplitude>,
+    chunks: Query<(Entity, &ChunkData<HeightField>), NewlyReadyChunks>,
+) {
+    let unit_box = unit_box
+        .get_or_insert_with(|| meshes.add(Cuboid::new(1.0, 1.0, 1.0)))
+        .clone();
+    let chunk_size = chunk_manager.get_size();
+
+    for (entity, height_field) in chunks.iter() {
+        let height = height_field.average().max(0.01);
+        let t = (height / amplitude.0).clamp(0.0, 1.0);
+        let color = Color::srgb(0.2, 0.5, 0.2).mix(&Color::srgb(0.95, 0.95, 0.95), t);
+
+        let size = vec3(chunk_size.x, height, chunk_size.z);
+        commands.entity(entity).with_child((
+            Mesh3d(unit_box.clone()),
+            MeshMaterial3d(materials.add(color)),
+            Transform::from_translation(size / 2.0 - chunk_manager.anchor_offset())
+                .with_scale(size),
+        ));
+    }
+}
+
+/// Logs chunks whose generation failed
+fn log_failed_chunks(mut failed: MessageReader<ChunkGenerationFailed>) {
+    for ChunkGenerationFailed { pos, error } in failed.read() {
+        warn!("Terrain chunk at pos:{} failed: {}", pos, error);
+    }
+}
@@ -0,0 +1,146 @@
+//! Saving the loaded chunks as a [`DynamicScene`] and spawning them back.
+//!
+//! [`export_chunks`] captures every chunk registered with the [`ChunkManager`],
+//! on all layers, with its [`ChunkPos`], its [`ChunkLayer`] and the other
+//! reflected components passing the filter, e.g. the [`ChunkData`](crate::prelude::ChunkData) of a generator
+//! whose output is registered. Components of your own types must be registered
+//! with [`App::register_type`] to be captured.
+//!
+//! Instead of [`Chunk`], the scene holds a [`ChunkFromScene`] marker. Once the
+//! scene is spawned, e.g. through a `DynamicSceneRoot` or
+//! [`DynamicScene::write_to_world`], the marker is replaced by [`Chunk`] in
+//! [`PostUpdate`], after every component of the entity is inserted. The chunks
+//! are then registered like any other spawn, so positions that are already
+//! loaded are handled by the [`ChunkCollisionPolicy`](crate::ChunkCollisionPolicy).
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use chunky_bevy::{prelude::*, scene::export_chunks};
+//!
+//! fn save_level(world: &mut World) {
+//!     // Only the positions, without any other component
+//!     let scene = export_chunks(world, SceneFilter::deny_all());
+//!     let scene = world.resource_mut::<Assets<DynamicScene>>().add(scene);
+//!     world.spawn(DynamicSceneRoot(scene));
+//! }
+//! ```
+
+use bevy::prelude::*;
+
+use crate::{Chunk, ChunkLayer, ChunkManager, ChunkPos, despawn_rejected_chunks};
+
+/// Registers [`ChunkFromScene`] and turns spawned scene entities into chunks
+pub(crate) struct ChunkScenePlugin;
+
+impl Plugin for ChunkScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ChunkFromScene>().add_systems(
+            PostUpdate,
+            insert_scene_chunks.before(despawn_rejected_chunks),
+        );
+    }
+}
+
+/// Marks an entity spawned from a scene made by [`export_chunks`] that becomes
+/// a [`Chunk`] in the next [`PostUpdate`].
+///
+/// Scenes insert components one at a time and in no particular order, while a
+/// [`Chunk`] is registered at its [`ChunkPos`] as soon as it is inserted.
+#[derive(Component, Reflect, Debug, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct ChunkFromScene;
+
+/// Captures the chunks registered with the [`ChunkManager`] on all layers in a
+/// [`DynamicScene`].
+///
+/// Besides [`ChunkPos`] and [`ChunkLayer`], the reflected components allowed
+/// by `filter` are captured. The transform and hierarchy of chunks are always left out:
+/// spawning a chunk places it at its [`ChunkPos`], and its children are not
+/// part of the scene.
+pub fn export_chunks(world: &World, filter: SceneFilter) -> DynamicScene {
+    let filter = match filter {
+        SceneFilter::Unset => SceneFilter::allow_all(),
+        filter => filter,
+    }
+    .allow::<ChunkPos>()
+    .allow::<ChunkLayer>()
+    .deny::<Chunk>()
+    .deny::<Transform>()
+    .deny::<GlobalTransform>()
+    .deny::<Children>()
+    .deny::<ChildOf>();
+    let mut scene = DynamicSceneBuilder::from_world(world)
+        .with_component_filter(filter)
+        .extract_entities(
+            world
+                .resource::<ChunkManager>()
+                .iter_all_layers()
+                .map(|(.., entity)| entity),
+        )
+        .build();
+    for entity in &mut scene.entities {
+        entity.components.push(Box::new(ChunkFromScene));
+    }
+    scene
+}
+
+/// Turns entities spawned from a chunk scene into chunks
+fn insert_scene_chunks(
+    mut commands: Commands,
+    spawned: Query<Entity, (With<ChunkFromScene>, With<ChunkPos>)>,
+) {
+    for entity in spawned.iter() {
+        commands
+            .entity(entity)
+            .remove::<ChunkFromScene>()
+            .insert(Chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::entity::EntityHashMap;
+
+    use super::*;
+    use crate::{ChunkyPlugin, tests::app};
+
+    /// The position and layer of every registered chunk
+    fn footprint(world: &World) -> Vec<(IVec3, i32)> {
+        let mut footprint: Vec<_> = world
+            .resource::<ChunkManager>()
+            .iter_all_layers()
+            .map(|(pos, layer, _)| (pos, layer))
+            .collect();
+        footprint.sort_by_key(|&(pos, layer)| (layer, pos.to_array()));
+        footprint
+    }
+
+    #[test]
+    fn spawning_an_exported_scene_restores_the_chunks() {
+        let mut app = app(ChunkyPlugin::default());
+        let world = app.world_mut();
+        for x in -1..=1 {
+            world.spawn((Chunk, ChunkPos(IVec3::new(x, 0, 2))));
+        }
+        world.spawn((Chunk, ChunkPos(IVec3::ZERO), ChunkLayer(3)));
+        let exported = footprint(world);
+        assert_eq!(exported.len(), 4);
+
+        let scene = export_chunks(world, SceneFilter::deny_all());
+        let chunks: Vec<_> = world
+            .resource::<ChunkManager>()
+            .iter_all_layers()
+            .map(|(.., entity)| entity)
+            .collect();
+        for chunk in chunks {
+            world.despawn(chunk);
+        }
+        assert!(world.resource::<ChunkManager>().is_empty());
+
+        scene
+            .write_to_world(world, &mut EntityHashMap::default())
+            .unwrap();
+        app.update();
+        assert_eq!(footprint(app.world()), exported);
+    }
+}